@@ -2,7 +2,9 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use common_error::AppError;
+use common_kafka::encoder::RecordEncoder;
 use common_kafka::partition_of;
+use common_kafka::schema_cache::SchemaCache;
 use kafka_schema_accommodation::schema_create_accommodation::CreateAccommodationAvro;
 use kafka_schema_accommodation::schema_create_accommodation::SCHEMA_NAME_CREATE_ACCOMMODATION;
 use kafka_schema_accommodation::schema_update_accommodation::UpdateAccommodationAvro;
@@ -11,8 +13,6 @@ use kafka_schema_accommodation::DATA_TYPE_ACCOMMODATION;
 use kafka_schema_common::schema_key::KeyAvro;
 use kafka_schema_common::schema_key::SCHEMA_NAME_KEY;
 use kafka_schema_common::IdentifierAvro;
-use schema_registry_converter::async_impl::avro::AvroEncoder;
-use schema_registry_converter::schema_registry_common::SubjectNameStrategy;
 use tracing::instrument;
 
 use crate::accommodation::model::Accommodation;
@@ -23,24 +23,25 @@ use crate::event::service::dto::EventDto;
 use crate::event::service::dto::SerializableEventDto;
 use crate::event::EventConverter;
 
-pub struct AccommodationEventEncoder<'a> {
-    pub(crate) avro_encoder: Arc<AvroEncoder<'a>>,
+pub struct AccommodationEventEncoder {
+    pub(crate) record_encoder: Arc<RecordEncoder>,
     pub(crate) topic_configuration: TopicProperties,
 }
 
-impl<'a> AccommodationEventEncoder<'a> {
-    pub fn new<'b>(
-        config: &'b KafkaConfiguration,
-    ) -> Result<AccommodationEventEncoder<'a>, AppError> {
-        Ok(AccommodationEventEncoder {
-            avro_encoder: Arc::new(kafka::init_avro_encoder(config)?),
+impl AccommodationEventEncoder {
+    pub fn new(
+        config: &KafkaConfiguration,
+        schema_cache: Arc<SchemaCache>,
+    ) -> AccommodationEventEncoder {
+        AccommodationEventEncoder {
+            record_encoder: Arc::new(kafka::init_record_encoder(schema_cache)),
             topic_configuration: config.topic.get_mapping("accommodation"),
-        })
+        }
     }
 }
 
 #[async_trait]
-impl<'a> EventConverter for AccommodationEventEncoder<'a> {
+impl EventConverter for AccommodationEventEncoder {
     fn handles(&self, event_type: String) -> bool {
         matches!(
             event_type.as_str(),
@@ -63,19 +64,19 @@ impl<'a> EventConverter for AccommodationEventEncoder<'a> {
         let partition = partition_of(accommodation_event.id, self.topic_configuration.partitions)
             .expect("Invalid partition number detected");
 
-        // Serialize value
-        let value_sns = SubjectNameStrategy::RecordNameStrategy(event_type.clone());
+        // Serialize value in the format of the topic
+        let format = self.topic_configuration.format;
         let serialized_value: Vec<u8> = if event_type == *SCHEMA_NAME_CREATE_ACCOMMODATION {
             let create_accommodation_avro: CreateAccommodationAvro =
                 accommodation_event.clone().into();
-            self.avro_encoder
-                .encode_struct(create_accommodation_avro, &value_sns)
+            self.record_encoder
+                .encode(create_accommodation_avro, &event_type, format)
                 .await?
         } else if event_type == *SCHEMA_NAME_UPDATE_ACCOMMODATION {
             let update_accommodation_avro: UpdateAccommodationAvro =
                 accommodation_event.clone().into();
-            self.avro_encoder
-                .encode_struct(update_accommodation_avro, &value_sns)
+            self.record_encoder
+                .encode(update_accommodation_avro, &event_type, format)
                 .await?
         } else {
             panic!("Unhandled event type: {:?}", event_type);
@@ -90,15 +91,18 @@ impl<'a> EventConverter for AccommodationEventEncoder<'a> {
                 version: accommodation_event.version,
             },
         };
-        let key_sns = SubjectNameStrategy::RecordNameStrategy(SCHEMA_NAME_KEY.to_string());
-
-        let serialized_key = self.avro_encoder.encode_struct(key_avro, &key_sns).await?;
+        let serialized_key = self
+            .record_encoder
+            .encode(key_avro, SCHEMA_NAME_KEY, format)
+            .await?;
 
         // Get topic
         let topic = self.topic_configuration.topic_name.clone();
 
         // Return dto with required parameters to send it with kafka
         Ok(EventDto {
+            aggregate_id: format!("{}", accommodation_event.id),
+            event_type,
             topic,
             partition,
             key: serialized_key,
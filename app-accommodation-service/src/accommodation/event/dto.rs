@@ -6,8 +6,8 @@ use kafka_schema_accommodation::schema_delete_room_type::DeleteRoomTypeAvro;
 use kafka_schema_accommodation::schema_update_accommodation::UpdateAccommodationAvro;
 use kafka_schema_accommodation::schema_update_room_type::UpdateRoomTypeAvro;
 use kafka_schema_accommodation::AccommodationAddressAvro;
-use kafka_schema_accommodation::BedTypeAvro;
-use kafka_schema_accommodation::IsoCountryCodeEnumAvro;
+use kafka_schema_accommodation::BedTypeEnumAvro;
+use kafka_schema_common::IsoCountryCodeEnumAvro;
 
 use crate::accommodation::model::Accommodation;
 use crate::accommodation::model::BedType;
@@ -29,13 +29,13 @@ impl From<Accommodation> for CreateAccommodationAvro {
             description: accommodation.description,
             address: AccommodationAddressAvro {
                 street: accommodation.address.street,
-                house_number: accommodation.address.house_number,
+                house_number: accommodation.address.house_number.into(),
                 zip_code: accommodation.address.zip_code,
                 city: accommodation.address.city,
                 area: accommodation.address.area,
                 country: match accommodation.address.country {
-                    IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::DE,
-                    IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::US,
+                    IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::De,
+                    IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::Us,
                 },
             },
         }
@@ -50,13 +50,13 @@ impl From<Accommodation> for UpdateAccommodationAvro {
             description: accommodation.description,
             address: AccommodationAddressAvro {
                 street: accommodation.address.street,
-                house_number: accommodation.address.house_number,
+                house_number: accommodation.address.house_number.into(),
                 zip_code: accommodation.address.zip_code,
                 city: accommodation.address.city,
                 area: accommodation.address.area,
                 country: match accommodation.address.country {
-                    IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::DE,
-                    IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::US,
+                    IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::De,
+                    IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::Us,
                 },
             },
         }
@@ -74,13 +74,13 @@ impl From<RoomType> for CreateRoomTypeAvro {
         CreateRoomTypeAvro {
             accommodation_id: r.accommodation_id.to_string(),
             identifier: r.id.to_string(),
-            size: r.size,
+            size: r.size.into(),
             balcony: r.balcony,
             bed_type: match r.bed_type {
-                BedType::Single => BedTypeAvro::Single,
-                BedType::TwinSingle => BedTypeAvro::TwinSingle,
-                BedType::Double => BedTypeAvro::Double,
-                BedType::King => BedTypeAvro::King,
+                BedType::Single => BedTypeEnumAvro::Single,
+                BedType::TwinSingle => BedTypeEnumAvro::TwinSingle,
+                BedType::Double => BedTypeEnumAvro::Double,
+                BedType::King => BedTypeEnumAvro::King,
             },
             tv: r.tv,
             wifi: r.wifi,
@@ -93,13 +93,13 @@ impl From<RoomType> for UpdateRoomTypeAvro {
         UpdateRoomTypeAvro {
             accommodation_id: r.accommodation_id.to_string(),
             identifier: r.id.to_string(),
-            size: r.size,
+            size: r.size.into(),
             balcony: r.balcony,
             bed_type: match r.bed_type {
-                BedType::Single => BedTypeAvro::Single,
-                BedType::TwinSingle => BedTypeAvro::TwinSingle,
-                BedType::Double => BedTypeAvro::Double,
-                BedType::King => BedTypeAvro::King,
+                BedType::Single => BedTypeEnumAvro::Single,
+                BedType::TwinSingle => BedTypeEnumAvro::TwinSingle,
+                BedType::Double => BedTypeEnumAvro::Double,
+                BedType::King => BedTypeEnumAvro::King,
             },
             tv: r.tv,
             wifi: r.wifi,
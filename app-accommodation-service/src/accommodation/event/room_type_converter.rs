@@ -2,7 +2,9 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use common_error::AppError;
+use common_kafka::encoder::RecordEncoder;
 use common_kafka::partition_of;
+use common_kafka::schema_cache::SchemaCache;
 use kafka_schema_accommodation::schema_create_room_type::CreateRoomTypeAvro;
 use kafka_schema_accommodation::schema_create_room_type::SCHEMA_NAME_CREATE_ROOM_TYPE;
 use kafka_schema_accommodation::schema_delete_room_type::DeleteRoomTypeAvro;
@@ -13,8 +15,6 @@ use kafka_schema_accommodation::DATA_TYPE_ROOM_TYPE;
 use kafka_schema_common::schema_key::KeyAvro;
 use kafka_schema_common::schema_key::SCHEMA_NAME_KEY;
 use kafka_schema_common::IdentifierAvro;
-use schema_registry_converter::async_impl::avro::AvroEncoder;
-use schema_registry_converter::schema_registry_common::SubjectNameStrategy;
 use tracing::instrument;
 
 use crate::accommodation::model::RoomType;
@@ -25,22 +25,25 @@ use crate::event::service::dto::EventDto;
 use crate::event::service::dto::SerializableEventDto;
 use crate::event::EventConverter;
 
-pub struct RoomTypeEventEncoder<'a> {
-    pub(crate) avro_encoder: Arc<AvroEncoder<'a>>,
+pub struct RoomTypeEventEncoder {
+    pub(crate) record_encoder: Arc<RecordEncoder>,
     pub(crate) topic_configuration: TopicProperties,
 }
 
-impl<'a> RoomTypeEventEncoder<'a> {
-    pub fn new<'b>(config: &'b KafkaConfiguration) -> Result<RoomTypeEventEncoder<'a>, AppError> {
-        Ok(RoomTypeEventEncoder {
-            avro_encoder: Arc::new(kafka::init_avro_encoder(config)?),
+impl RoomTypeEventEncoder {
+    pub fn new(
+        config: &KafkaConfiguration,
+        schema_cache: Arc<SchemaCache>,
+    ) -> RoomTypeEventEncoder {
+        RoomTypeEventEncoder {
+            record_encoder: Arc::new(kafka::init_record_encoder(schema_cache)),
             topic_configuration: config.topic.get_mapping("accommodation"),
-        })
+        }
     }
 }
 
 #[async_trait]
-impl<'a> EventConverter for RoomTypeEventEncoder<'a> {
+impl EventConverter for RoomTypeEventEncoder {
     fn handles(&self, event_type: String) -> bool {
         matches!(
             event_type.as_str(),
@@ -68,22 +71,22 @@ impl<'a> EventConverter for RoomTypeEventEncoder<'a> {
         )
         .expect("Invalid partition number detected");
 
-        // Serialize value
-        let value_sns = SubjectNameStrategy::RecordNameStrategy(event_type.clone());
+        // Serialize value in the format of the topic
+        let format = self.topic_configuration.format;
         let serialized_value: Vec<u8> = if event_type == *SCHEMA_NAME_CREATE_ROOM_TYPE {
             let create_room_type_avro_avro: CreateRoomTypeAvro = room_type_event.clone().into();
-            self.avro_encoder
-                .encode_struct(create_room_type_avro_avro, &value_sns)
+            self.record_encoder
+                .encode(create_room_type_avro_avro, &event_type, format)
                 .await?
         } else if event_type == *SCHEMA_NAME_UPDATE_ROOM_TYPE {
             let update_room_type_avro: UpdateRoomTypeAvro = room_type_event.clone().into();
-            self.avro_encoder
-                .encode_struct(update_room_type_avro, &value_sns)
+            self.record_encoder
+                .encode(update_room_type_avro, &event_type, format)
                 .await?
         } else if event_type == *SCHEMA_NAME_DELETE_ROOM_TYPE {
             let delete_room_type_avro: DeleteRoomTypeAvro = room_type_event.clone().into();
-            self.avro_encoder
-                .encode_struct(delete_room_type_avro, &value_sns)
+            self.record_encoder
+                .encode(delete_room_type_avro, &event_type, format)
                 .await?
         } else {
             panic!("Unhandled event type: {:?}", event_type);
@@ -99,15 +102,18 @@ impl<'a> EventConverter for RoomTypeEventEncoder<'a> {
                 version: -1,
             },
         };
-        let key_sns = SubjectNameStrategy::RecordNameStrategy(SCHEMA_NAME_KEY.to_string());
-
-        let serialized_key = self.avro_encoder.encode_struct(key_avro, &key_sns).await?;
+        let serialized_key = self
+            .record_encoder
+            .encode(key_avro, SCHEMA_NAME_KEY, format)
+            .await?;
 
         // Get topic
         let topic = self.topic_configuration.topic_name.clone();
 
         // Return dto with required parameters to send it with kafka
         Ok(EventDto {
+            aggregate_id: format!("{}", room_type_event.id),
+            event_type,
             topic,
             partition,
             key: serialized_key,
@@ -8,6 +8,7 @@ use mongodb::IndexModel;
 
 use crate::accommodation::model::Accommodation;
 use crate::accommodation::model::RoomType;
+use crate::user::model::Model as User;
 
 pub async fn create_indexes(client: Arc<Client>) -> Result<(), AppError> {
     let database = client.default_database().expect("No default db specified");
@@ -85,5 +86,23 @@ pub async fn create_indexes(client: Arc<Client>) -> Result<(), AppError> {
         .create_index(ix_room_type_accommodation_id, None)
         .await?;
 
+    // User.identifier
+    let ix_user_identifier = IndexModel::builder()
+        .keys(doc! {
+            "identifier": 1,
+        })
+        .options(
+            IndexOptions::builder()
+                .name(Some("ix_user_identifier".to_string()))
+                .unique(true)
+                .build(),
+        )
+        .build();
+
+    database
+        .collection::<User>("user")
+        .create_index(ix_user_identifier, None)
+        .await?;
+
     Ok(())
 }
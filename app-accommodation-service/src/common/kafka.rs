@@ -1,64 +1,73 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use async_trait::async_trait;
 use common_error::AppError;
+use common_kafka::consumer::retry::RetryPolicy;
+use common_kafka::decoder::SchemaRegistryRecordDecoder;
+use common_kafka::encoder::RecordEncoder;
+use common_kafka::schema_cache::SchemaCache;
+use common_kafka::topic::verify_partitions;
+use kafka_schema_common::RecordSchema;
 use rdkafka::config::RDKafkaLogLevel;
 use rdkafka::consumer::Consumer;
 use rdkafka::consumer::StreamConsumer;
+use rdkafka::producer::FutureProducer;
 use rdkafka::ClientConfig;
-use schema_registry_converter::async_impl::avro::AvroDecoder;
-use schema_registry_converter::async_impl::avro::AvroEncoder;
 use schema_registry_converter::async_impl::schema_registry::SrSettings;
-use schema_registry_converter::avro_common::DecodeResult;
-use schema_registry_converter::error::SRCError;
 
 use crate::config::configuration::KafkaConfiguration;
 
-pub fn init_avro_encoder<'a, 'b>(
-    config: &'a KafkaConfiguration,
-) -> Result<AvroEncoder<'b>, AppError> {
-    let sr_settings = resolve_sr_settings(config)?;
-    Ok(AvroEncoder::new(sr_settings))
-}
+const DEFAULT_MAX_POLL_INTERVAL_MS: u64 = 60000;
 
-pub fn init_avro_decoder<'a, 'b>(
-    config: &'a KafkaConfiguration,
-) -> Result<AvroDecoder<'b>, AppError> {
-    let sr_settings = resolve_sr_settings(config)?;
-    Ok(AvroDecoder::new(sr_settings))
+/// Initializes the cache of the ids of the schemas of the records produced
+/// and consumed by the service.
+pub async fn init_schema_cache(config: &KafkaConfiguration) -> Result<SchemaCache, AppError> {
+    Ok(SchemaCache::init(
+        &config.schema_registry.url,
+        &record_schemas(),
+        config.schema_registry.cache_file.clone(),
+    )
+    .await?)
 }
 
-pub fn resolve_sr_settings(config: &KafkaConfiguration) -> Result<SrSettings, AppError> {
-    Ok(SrSettings::new_builder(config.schema_registry.url.clone()).build()?)
+pub fn init_record_encoder(schema_cache: Arc<SchemaCache>) -> RecordEncoder {
+    RecordEncoder::new(schema_cache)
 }
 
-#[async_trait]
-pub trait RecordDecoder: Send + Sync {
-    async fn decode(&self, bytes: Option<&[u8]>) -> Result<DecodeResult, SRCError>;
+/// Initializes the decoder for records of all serialization formats.
+pub fn init_record_decoder<'a, 'b>(
+    config: &'a KafkaConfiguration,
+    schema_cache: Arc<SchemaCache>,
+) -> Result<SchemaRegistryRecordDecoder<'b>, AppError> {
+    let sr_settings = resolve_sr_settings(config)?;
+    Ok(SchemaRegistryRecordDecoder::new(sr_settings, schema_cache))
 }
 
-pub struct AvroRecordDecoder<'a> {
-    pub avro_decoder: AvroDecoder<'a>,
+/// Schemas of the records that are produced and consumed by the service.
+fn record_schemas() -> Vec<RecordSchema> {
+    [
+        kafka_schema_common::SCHEMAS,
+        kafka_schema_accommodation::SCHEMAS,
+        kafka_schema_user::SCHEMAS,
+    ]
+    .concat()
 }
 
-impl<'a> AvroRecordDecoder<'a> {
-    pub fn new<'b>(config: &'b KafkaConfiguration) -> Result<AvroRecordDecoder<'a>, AppError> {
-        Ok(AvroRecordDecoder {
-            avro_decoder: init_avro_decoder(config)?,
-        })
-    }
+pub fn resolve_sr_settings(config: &KafkaConfiguration) -> Result<SrSettings, AppError> {
+    Ok(SrSettings::new_builder(config.schema_registry.url.clone()).build()?)
 }
 
-#[async_trait]
-impl<'a> RecordDecoder for AvroRecordDecoder<'a> {
-    async fn decode(&self, bytes: Option<&[u8]>) -> Result<DecodeResult, SRCError> {
-        self.avro_decoder.decode(bytes).await
-    }
+/// Consumer of the configured topics together with the consumers of its retry
+/// topics.
+pub struct TopicConsumers {
+    pub consumer: StreamConsumer,
+    pub retry_consumers: Vec<StreamConsumer>,
+    pub retry_policy: RetryPolicy,
 }
 
 pub fn init_consumers(
     config: &KafkaConfiguration,
-) -> Result<HashMap<String, StreamConsumer>, AppError> {
+) -> Result<HashMap<String, TopicConsumers>, AppError> {
     let mut consumers = HashMap::new();
     for consumer_configuration in &config.consumer {
         // Initialize consumer
@@ -67,10 +76,30 @@ pub fn init_consumers(
             consumer_configuration.client_id.clone(),
             consumer_configuration.group_id.clone(),
             consumer_configuration.topic.clone(),
+            DEFAULT_MAX_POLL_INTERVAL_MS,
         )?;
 
+        // Initialize a consumer per retry topic to not block the other topics
+        // while waiting for delayed records. Each retry consumer has its own
+        // group, so that a rebalance of the main consumer doesn't wait for it.
+        let retry_policy = consumer_configuration.retry.clone();
+        let mut retry_consumers = Vec::new();
+        for retry_topic in &retry_policy.topics {
+            retry_consumers.push(init_consumer(
+                config.broker.urls.clone(),
+                format!("{}-{}", consumer_configuration.client_id, retry_topic.topic),
+                format!("{}-{}", consumer_configuration.group_id, retry_topic.topic),
+                vec![retry_topic.topic.clone()],
+                DEFAULT_MAX_POLL_INTERVAL_MS + retry_topic.delay_ms,
+            )?);
+        }
+
         // Add consumer with id to the result map
-        consumers.insert(consumer_configuration.id.clone(), consumer);
+        consumers.insert(consumer_configuration.id.clone(), TopicConsumers {
+            consumer,
+            retry_consumers,
+            retry_policy,
+        });
     }
     Ok(consumers)
 }
@@ -80,6 +109,7 @@ fn init_consumer(
     client_id: String,
     group_id: String,
     topics: Vec<String>,
+    max_poll_interval_ms: u64,
 ) -> Result<StreamConsumer, AppError> {
     // Initialize consumer
     let consumer: StreamConsumer = ClientConfig::new()
@@ -90,7 +120,7 @@ fn init_consumer(
         .set("enable.auto.offset.store", "false") // Don't update offsets in store automatically
         .set("auto.commit.interval.ms", "5000") // Default
         .set("isolation.level", "read_committed")
-        .set("max.poll.interval.ms", "60000")
+        .set("max.poll.interval.ms", max_poll_interval_ms.to_string())
         .set("request.timeout.ms", "10000")
         .set("group.id", group_id)
         .set("client.id", client_id)
@@ -106,3 +136,29 @@ fn init_consumer(
 
     Ok(consumer)
 }
+
+/// Initializes the producer to forward failed records to retry and dead
+/// letter topics.
+pub fn init_retry_producer(config: &KafkaConfiguration) -> Result<FutureProducer, AppError> {
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", config.broker.urls.clone())
+        .set("request.timeout.ms", "10000")
+        .set("delivery.timeout.ms", "15000")
+        .set("enable.idempotence", "true")
+        .set("request.required.acks", "all") // Wait for acknowledge from broker
+        .create()?;
+
+    Ok(producer)
+}
+
+/// Checks that the partitions of the mapped topics match the cluster.
+pub fn verify_topic_partitions(config: &KafkaConfiguration) -> Result<(), AppError> {
+    let topics: Vec<(String, i32)> = config
+        .topic
+        .mappings
+        .iter()
+        .map(|t| (t.topic_name.clone(), t.partitions))
+        .collect();
+
+    verify_partitions(&config.broker.urls, &topics, config.topic.partition_check)
+}
@@ -1,6 +1,9 @@
+use futures::future::select_all;
 use tokio::task::JoinHandle;
 
-pub async fn shutdown_signal(shutdown_handles: Vec<JoinHandle<()>>) {
+/// Completes on Ctrl+C, on SIGTERM or when one of the handles terminates, so
+/// that the service doesn't keep running without its background tasks.
+pub async fn shutdown_signal(mut shutdown_handles: Vec<JoinHandle<()>>) {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
             .await
@@ -18,9 +21,19 @@ pub async fn shutdown_signal(shutdown_handles: Vec<JoinHandle<()>>) {
     #[cfg(not(unix))]
     let terminate = std::future::pending::<()>();
 
+    let terminated = async {
+        if shutdown_handles.is_empty() {
+            std::future::pending::<()>().await;
+        } else {
+            let _ = select_all(shutdown_handles.iter_mut()).await;
+            tracing::error!("Background task terminated");
+        }
+    };
+
     tokio::select! {
         _ = ctrl_c => {},
         _ = terminate => {},
+        _ = terminated => {},
     }
 
     tracing::warn!("Signal received, starting graceful shutdown");
@@ -2,6 +2,8 @@ use serde::Serialize;
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct Model {
+    pub aggregate_id: String,
+    pub event_type: String,
     pub topic: String,
     pub partition: i32,
     #[serde(with = "serde_bytes")]
@@ -14,6 +14,8 @@ pub async fn save(db_session: &ClientSession, event: &EventDto) -> Result<(), Ap
 
     // Build the entity from dto
     let e = Model {
+        aggregate_id: event.aggregate_id.clone(),
+        event_type: event.event_type.clone(),
         key: event.key.clone(),
         payload: event.payload.clone(),
         partition: event.partition,
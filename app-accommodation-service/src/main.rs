@@ -15,7 +15,7 @@ use common_metrics::middleware::RouterMetricsExt;
 use common_security::middleware::RouterSecurityExt;
 use opentelemetry_propagator_b3::propagator::B3Encoding;
 use opentelemetry_propagator_b3::propagator::Propagator;
-use rdkafka::consumer::StreamConsumer;
+use rdkafka::producer::FutureProducer;
 use tokio::task::JoinHandle;
 use tower::limit::ConcurrencyLimitLayer;
 use tower_http::compression::predicate::SizeAbove;
@@ -27,11 +27,10 @@ use crate::common::api::health;
 use crate::common::context::ContextImpl;
 use crate::common::db;
 use crate::common::kafka;
-use crate::common::kafka::AvroRecordDecoder;
+use crate::common::kafka::TopicConsumers;
 use crate::common::security::OAuthConfiguration;
 use crate::common::server::shutdown_signal;
 use crate::config::configuration::Configuration;
-use crate::config::configuration::KafkaConfiguration;
 use crate::config::configuration::ServerConfiguration;
 use crate::config::logging_tracing;
 use crate::event::service::event_dispatcher::EventDispatcher;
@@ -53,19 +52,28 @@ async fn main() -> Result<(), AppError> {
     // Initialize logging and tracing
     logging_tracing::init(&config)?;
 
+    // Check that records are partitioned consistently
+    kafka::verify_topic_partitions(&config.kafka)?;
+
     // Init db client and create indexes
     let db_client = Arc::new(pool::init_db_client(&config.database).await?);
     db::create_indexes(db_client.clone()).await?;
 
-    // Initialize schema decoder
-    let avro_decoder = AvroRecordDecoder::new(&config.kafka)?;
+    // Look up the ids of the schemas, falls back to the persisted ids if the
+    // schema registry isn't available
+    let schema_cache = Arc::new(kafka::init_schema_cache(&config.kafka).await?);
+
+    // Initialize decoder of avro, JSON and Protobuf records
+    let record_decoder = kafka::init_record_decoder(&config.kafka, schema_cache.clone())?;
 
     // Initialize schema encoders
-    let accommodation_event_converter: Arc<DynEventConverter> =
-        Arc::new(Box::new(AccommodationEventEncoder::new(&config.kafka)?));
+    let accommodation_event_converter: Arc<DynEventConverter> = Arc::new(Box::new(
+        AccommodationEventEncoder::new(&config.kafka, schema_cache.clone()),
+    ));
 
-    let room_type_event_converter: Arc<DynEventConverter> =
-        Arc::new(Box::new(RoomTypeEventEncoder::new(&config.kafka)?));
+    let room_type_event_converter: Arc<DynEventConverter> = Arc::new(Box::new(
+        RoomTypeEventEncoder::new(&config.kafka, schema_cache),
+    ));
 
     // Initialize event dispatcher
     let event_dispatcher = EventDispatcher::new(vec![
@@ -75,28 +83,26 @@ async fn main() -> Result<(), AppError> {
 
     // Construct request context
     let context = ContextImpl::new_dyn_context(
-        Arc::new(avro_decoder),
+        Arc::new(record_decoder),
         db_client,
         Arc::new(event_dispatcher),
     );
 
     // Initialize kafka consumers
     let mut consumers = kafka::init_consumers(&config.kafka)?;
+    let retry_producer = kafka::init_retry_producer(&config.kafka)?;
     let propagator = Arc::new(Propagator::with_encoding(B3Encoding::SingleHeader));
-    let user_handle = init_user_kafka_consumer(
+    let user_handles = init_user_kafka_consumer(
         context.clone(),
-        &config.kafka,
         &mut consumers,
+        retry_producer,
         propagator.clone(),
     );
 
     let oauth_configuration = OAuthConfiguration::new(context.clone(), &config.security).await?;
 
     // Start the web-server
-    start_web_server(&config.server, context, oauth_configuration, vec![
-        user_handle,
-    ])
-    .await;
+    start_web_server(&config.server, context, oauth_configuration, user_handles).await;
 
     Ok(())
 }
@@ -148,16 +154,16 @@ fn init_routing(context: DynContext, oauth_configuration: OAuthConfiguration) ->
 
 fn init_user_kafka_consumer(
     context: DynContext,
-    config: &KafkaConfiguration,
-    kafka_consumers: &mut HashMap<String, StreamConsumer>,
+    kafka_consumers: &mut HashMap<String, TopicConsumers>,
+    retry_producer: FutureProducer,
     propagator: Arc<Propagator>,
-) -> JoinHandle<()> {
+) -> Vec<JoinHandle<()>> {
     listen(
         context.clone(),
-        config,
         kafka_consumers
             .remove("user")
             .expect("User consumer not initialized"),
+        retry_producer,
         propagator,
     )
 }
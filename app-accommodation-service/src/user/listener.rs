@@ -1,178 +1,153 @@
-use std::panic;
+use std::iter;
 use std::sync::Arc;
 
-use apache_avro::schema::Name;
+use async_trait::async_trait;
 use common_db_mongodb::transaction::transactional;
-use common_tracing::get_context_from_b3;
+use common_error::AppError;
+use common_kafka::consumer::handler::ConsumerRecord;
+use common_kafka::consumer::handler::EventHandler;
+use common_kafka::consumer::runtime::EventConsumer;
 use futures_util::FutureExt;
 use kafka_schema_common::schema_key::KeyAvro;
 use kafka_schema_user::schema_create_user::CreateUserAvro;
+use kafka_schema_user::schema_create_user::CreateUserAvroV1;
 use kafka_schema_user::schema_create_user::SCHEMA_NAME_CREATE_USER;
+use kafka_schema_user::schema_create_user::SCHEMA_NAME_CREATE_USER_V1;
+use kafka_schema_user::schema_delete_user::DeleteUserAvro;
+use kafka_schema_user::schema_delete_user::SCHEMA_NAME_DELETE_USER;
+use kafka_schema_user::schema_update_user::UpdateUserAvro;
+use kafka_schema_user::schema_update_user::SCHEMA_NAME_UPDATE_USER;
 use opentelemetry_propagator_b3::propagator::Propagator;
-use opentelemetry_propagator_b3::propagator::B3_SINGLE_HEADER;
-use rdkafka::consumer::Consumer;
 use rdkafka::consumer::StreamConsumer;
-use rdkafka::message::BorrowedHeaders;
-use rdkafka::message::FromBytes;
-use rdkafka::message::Headers;
-use rdkafka::Message;
+use rdkafka::producer::FutureProducer;
 use tokio::task::JoinHandle;
-use tracing::debug;
-use tracing::instrument::Instrumented;
-use tracing::span;
-use tracing::warn;
-use tracing::Instrument;
-use tracing::Level;
-use tracing::Span;
-// use common_tracing::B3SpanExt;
+use tracing::error;
 use uuid::Uuid;
 
-use crate::config::configuration::ConsumerConfiguration;
-use crate::config::configuration::KafkaConfiguration;
+use crate::common::kafka::TopicConsumers;
 use crate::user;
 use crate::DynContext;
 
+/// Starts a listener for the user topic and one for each of its retry topics.
 pub fn listen(
     context: DynContext,
-    config: &KafkaConfiguration,
-    stream_consumer: StreamConsumer,
+    consumers: TopicConsumers,
+    producer: FutureProducer,
     tracing_propagator: Arc<Propagator>,
-) -> JoinHandle<()> {
-    let topic = get_user_topic_name(config);
+) -> Vec<JoinHandle<()>> {
+    let retry_policy = consumers.retry_policy;
+
+    iter::once(consumers.consumer)
+        .chain(consumers.retry_consumers)
+        .map(|stream_consumer| {
+            let consumer =
+                init_event_consumer(context.clone(), stream_consumer, tracing_propagator.clone())
+                    .with_retry_policy(retry_policy.clone(), producer.clone());
+
+            // Start listener
+            tokio::spawn(async move {
+                if let Err(e) = consumer.run().await {
+                    error!("Consumption of user events failed: {}", e);
+                }
+            })
+        })
+        .collect()
+}
 
-    // Start listener
-    tokio::spawn(async move {
-        do_listen(context, &stream_consumer, topic, tracing_propagator).await;
+fn init_event_consumer(
+    context: DynContext,
+    stream_consumer: StreamConsumer,
+    tracing_propagator: Arc<Propagator>,
+) -> EventConsumer {
+    EventConsumer::new(
+        stream_consumer,
+        context.record_decoder(),
+        tracing_propagator,
+    )
+    .with_handler(SCHEMA_NAME_CREATE_USER, CreateUserHandler {
+        context: context.clone(),
+    })
+    .with_upcasting_handler::<CreateUserAvroV1, _>(SCHEMA_NAME_CREATE_USER_V1, CreateUserHandler {
+        context: context.clone(),
+    })
+    .with_handler(SCHEMA_NAME_UPDATE_USER, UpdateUserHandler {
+        context: context.clone(),
     })
+    .with_handler(SCHEMA_NAME_DELETE_USER, DeleteUserHandler { context })
 }
 
-pub async fn do_listen(
+struct CreateUserHandler {
     context: DynContext,
-    stream_consumer: &StreamConsumer,
-    user_topic: String,
-    tracing_propagator: Arc<Propagator>,
-) {
-    let decoder = context.avro_decoder().clone();
-
-    loop {
-        match stream_consumer.recv().await {
-            Err(e) => warn!("Error: {}", e),
-            Ok(message) => {
-                let span = init_context(message.headers(), tracing_propagator.clone());
-                let _ = span.enter();
-
-                #[allow(clippy::unit_arg)]
-                let _delivery_result: Instrumented<()> = {
-                    let topic = message.topic();
-                    assert_eq!(
-                        topic, user_topic,
-                        "Message from wrong topic detected. Stopped processing."
-                    );
-
-                    let key_result = decoder
-                        .decode(message.key())
-                        .await
-                        .expect("Couldn't decode avro message");
-
-                    let key = apache_avro::from_value::<KeyAvro>(&key_result.value)
-                        .expect("Couldn't deserialize KeyAvro");
-
-                    let payload_result = decoder
-                        .decode(message.payload())
-                        .await
-                        .expect("Couldn't decode payload");
-
-                    // Check type
-                    assert_eq!(
-                        payload_result
-                            .name
-                            .unwrap_or_else(|| Name {
-                                name: "".to_string(),
-                                namespace: None
-                            })
-                            .name,
-                        SCHEMA_NAME_CREATE_USER.to_string()
-                    );
-
-                    let payload = apache_avro::from_value::<CreateUserAvro>(&payload_result.value)
-                        .expect("Couldn't deserialize CreateUserAvro");
-
-                    debug!(
-                        "Message received (topic: {}, partition: {}, offset: {}",
-                        topic,
-                        message.partition(),
-                        message.offset()
-                    );
-
-                    // debug!("Key: {:?}", key);
-                    // debug!("Value: {:?}", payload);
-
-                    if let Err(e) = transactional(context.db_client(), |db_session| {
-                        let identifier: Uuid = payload.identifier.parse().expect("Invalid UUID");
-                        let version: i64 = key.identifier.version;
-                        let name: String = payload.name.clone();
-
-                        async move {
-                            match user::service::create_user(db_session, identifier, version, name)
-                                .await
-                            {
-                                Ok(_) => Ok(()),
-                                Err(e) => Err(e),
-                            }
-                        }
-                        .boxed()
-                    })
-                    .instrument(span.clone())
-                    .await
-                    {
-                        panic!("Consumption of event failed: {:?}", e);
-                    }
-
-                    if let Err(e) = stream_consumer.store_offset_from_message(&message) {
-                        panic!("Error while storing offset: {}", e);
-                    }
-                }
-                .instrument(span);
-            }
-        }
+}
+
+#[async_trait]
+impl EventHandler for CreateUserHandler {
+    type Key = KeyAvro;
+    type Payload = CreateUserAvro;
+
+    async fn handle(
+        &self,
+        record: ConsumerRecord<KeyAvro, CreateUserAvro>,
+    ) -> Result<(), AppError> {
+        save_user(self.context.clone(), record.key, record.payload.name).await
     }
 }
 
-fn init_context(headers: Option<&BorrowedHeaders>, tracing_propagator: Arc<Propagator>) -> Span {
-    let span = span!(Level::TRACE, "");
-    if let Some(headers) = headers {
-        let header_count = headers.count();
-        for i in 0..header_count {
-            let header = headers.get(i).expect("Invalid header detected");
-            if header.0 == B3_SINGLE_HEADER {
-                if let Ok(trace_id) = str::from_bytes(header.1) {
-                    let context =
-                        get_context_from_b3(tracing_propagator.clone(), trace_id.to_string());
-                    tracing_opentelemetry::OpenTelemetrySpanExt::set_parent(&span, context);
-                }
-            }
-        }
+struct UpdateUserHandler {
+    context: DynContext,
+}
+
+#[async_trait]
+impl EventHandler for UpdateUserHandler {
+    type Key = KeyAvro;
+    type Payload = UpdateUserAvro;
+
+    async fn handle(
+        &self,
+        record: ConsumerRecord<KeyAvro, UpdateUserAvro>,
+    ) -> Result<(), AppError> {
+        save_user(self.context.clone(), record.key, record.payload.name).await
+    }
+}
+
+struct DeleteUserHandler {
+    context: DynContext,
+}
+
+#[async_trait]
+impl EventHandler for DeleteUserHandler {
+    type Key = KeyAvro;
+    type Payload = DeleteUserAvro;
+
+    async fn handle(
+        &self,
+        record: ConsumerRecord<KeyAvro, DeleteUserAvro>,
+    ) -> Result<(), AppError> {
+        let identifier = parse_identifier(&record.key)?;
+        let version = record.key.identifier.version;
+
+        transactional(self.context.db_client(), |db_session| {
+            async move { user::service::delete_user(db_session, identifier, version).await }.boxed()
+        })
+        .await?;
+
+        Ok(())
     }
-    span
 }
 
-fn get_user_topic_name(config: &KafkaConfiguration) -> String {
-    // Get consumer configuration
-    let consumer_config: Vec<&ConsumerConfiguration> = config
-        .consumer
-        .iter()
-        .filter(|c| c.id.clone() == "user")
-        .collect();
-
-    // Get topic name
-    let topic = consumer_config
-        .first()
-        .expect("user consumer configuration not found")
-        .topic
-        .clone()
-        .first()
-        .expect("user topic not found in consumer configuration")
-        .clone();
-
-    topic
+async fn save_user(context: DynContext, key: KeyAvro, name: String) -> Result<(), AppError> {
+    let identifier = parse_identifier(&key)?;
+    let version = key.identifier.version;
+
+    transactional(context.db_client(), |db_session| {
+        let name = name.clone();
+        async move { user::service::save_user(db_session, identifier, version, name).await }.boxed()
+    })
+    .await?;
+
+    Ok(())
+}
+
+fn parse_identifier(key: &KeyAvro) -> Result<Uuid, AppError> {
+    Uuid::parse_str(&key.identifier.identifier).map_err(|e| anyhow::Error::from(e).into())
 }
@@ -9,6 +9,10 @@ pub struct Model {
     pub identifier: Uuid,
     pub version: i64,
     pub name: String,
+    /// Marks the user as deleted. The document is kept as a tombstone, so
+    /// that stale events of the user cannot recreate it.
+    #[serde(default)]
+    pub deleted: bool,
 }
 
 impl UserDetails for Model {}
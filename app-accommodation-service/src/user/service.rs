@@ -3,48 +3,122 @@ use bson::Document;
 use common_db_mongodb::util::get_collection;
 use common_error::AppError;
 use mongodb::options::FindOneOptions;
-use mongodb::options::InsertOneOptions;
+use mongodb::options::ReplaceOptions;
 use mongodb::ClientSession;
 use mongodb::Collection;
+use tracing::debug;
 use tracing::instrument;
 use uuid::Uuid;
 
 use crate::user::model::Model;
 
-#[instrument(name = "create_user", skip_all)]
-pub async fn create_user(
+/// Inserts or replaces the user with the given identifier. The user is only
+/// saved if the version is newer than the version of the stored user or
+/// tombstone, so stale or duplicate events are ignored. Returns whether the
+/// user was saved.
+#[instrument(name = "save_user", skip_all)]
+pub async fn save_user(
     db_session: &ClientSession,
     identifier: Uuid,
     version: i64,
     name: String,
-) -> Result<(), AppError> {
+) -> Result<bool, AppError> {
+    let stored_user = find_stored_user(db_session, identifier).await?;
+    if !is_newer(stored_user.as_ref(), identifier, version) {
+        return Ok(false);
+    }
+
     let user = Model {
         identifier,
         version,
         name,
+        deleted: false,
     };
 
-    let collection: Collection<Model> = get_collection::<Model>(db_session, "user");
+    replace_user(db_session, user).await?;
+    Ok(true)
+}
 
-    collection
-        .insert_one(user, InsertOneOptions::default())
-        .await?;
+/// Replaces the user with the given identifier by a tombstone if the stored
+/// version is older than the version of the delete event. A tombstone is also
+/// written if the user is not known yet, so that a create event arriving after
+/// the delete event is ignored. Returns whether the tombstone was written.
+#[instrument(name = "delete_user", skip_all)]
+pub async fn delete_user(
+    db_session: &ClientSession,
+    identifier: Uuid,
+    version: i64,
+) -> Result<bool, AppError> {
+    let stored_user = find_stored_user(db_session, identifier).await?;
+    if !is_newer(stored_user.as_ref(), identifier, version) {
+        return Ok(false);
+    }
 
-    Ok(())
+    replace_user(db_session, tombstone(stored_user, identifier, version)).await?;
+    Ok(true)
 }
 
+/// Finds the user with the given identifier. Deleted users are not returned.
 #[instrument(name = "find_user", skip_all)]
 pub async fn find_one_by_identifier(
     db_session: &ClientSession,
     identifier: Uuid,
 ) -> Result<Option<Model>, AppError> {
-    let collection: Collection<Model> = get_collection::<Model>(db_session, "user");
+    let mut filter = id_filter(identifier);
+    filter.insert("deleted", doc! { "$ne": true });
+
+    Ok(get_user_collection(db_session)
+        .find_one(filter, FindOneOptions::default())
+        .await?)
+}
 
-    Ok(collection
+async fn find_stored_user(
+    db_session: &ClientSession,
+    identifier: Uuid,
+) -> Result<Option<Model>, AppError> {
+    Ok(get_user_collection(db_session)
         .find_one(id_filter(identifier), FindOneOptions::default())
         .await?)
 }
 
+async fn replace_user(db_session: &ClientSession, user: Model) -> Result<(), AppError> {
+    get_user_collection(db_session)
+        .replace_one(
+            id_filter(user.identifier),
+            user,
+            ReplaceOptions::builder().upsert(true).build(),
+        )
+        .await?;
+    Ok(())
+}
+
+fn is_newer(stored_user: Option<&Model>, identifier: Uuid, version: i64) -> bool {
+    match stored_user {
+        Some(stored_user) if stored_user.version >= version => {
+            debug!(
+                "Skip outdated user event (identifier: {}, version: {}, stored version: {}, \
+                 deleted: {})",
+                identifier, version, stored_user.version, stored_user.deleted
+            );
+            false
+        }
+        _ => true,
+    }
+}
+
+fn tombstone(stored_user: Option<Model>, identifier: Uuid, version: i64) -> Model {
+    Model {
+        identifier,
+        version,
+        name: stored_user.map(|u| u.name).unwrap_or_default(),
+        deleted: true,
+    }
+}
+
+fn get_user_collection(db_session: &ClientSession) -> Collection<Model> {
+    get_collection::<Model>(db_session, "user")
+}
+
 fn id_filter(id: Uuid) -> Document {
     doc! {
         "identifier": as_bson_uuid(id)
@@ -54,3 +128,41 @@ fn id_filter(id: Uuid) -> Document {
 fn as_bson_uuid(id: Uuid) -> bson::Uuid {
     id.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(identifier: Uuid, version: i64) -> Model {
+        Model {
+            identifier,
+            version,
+            name: "Max Mustermann".to_string(),
+            deleted: false,
+        }
+    }
+
+    #[test]
+    fn stale_create_after_delete_is_rejected() {
+        let identifier = Uuid::new_v4();
+        let deleted = tombstone(Some(user(identifier, 1)), identifier, 2);
+
+        assert!(deleted.deleted);
+        assert_eq!(2, deleted.version);
+        assert!(!is_newer(Some(&deleted), identifier, 0));
+        assert!(!is_newer(Some(&deleted), identifier, 1));
+        assert!(!is_newer(Some(&deleted), identifier, 2));
+        assert!(is_newer(Some(&deleted), identifier, 3));
+    }
+
+    #[test]
+    fn stale_create_after_delete_of_unknown_user_is_rejected() {
+        let identifier = Uuid::new_v4();
+        assert!(is_newer(None, identifier, 2));
+
+        let deleted = tombstone(None, identifier, 2);
+
+        assert!(deleted.deleted);
+        assert!(!is_newer(Some(&deleted), identifier, 0));
+    }
+}
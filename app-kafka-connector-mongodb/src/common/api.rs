@@ -1,6 +1,48 @@
+use axum::extract::Extension;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use mongodb::bson::doc;
 use serde_json::json;
+use tracing::warn;
 
-pub async fn health() -> impl IntoResponse {
-    axum::Json(json!({ "status" : "UP" }))
+use crate::common::context::DynContext;
+
+/// Reports the service as ready when the database and the brokers are
+/// reachable and the relay sends events.
+pub async fn health(Extension(context): Extension<DynContext>) -> impl IntoResponse {
+    let database = context
+        .db_client()
+        .default_database()
+        .expect("No default db specified")
+        .run_command(doc! { "ping": 1 }, None)
+        .await
+        .map_err(|e| warn!("Database isn't reachable: {:?}", e))
+        .is_ok();
+    // The brokers are checked periodically by the lag job
+    let broker = context.broker_status().is_reachable();
+    let consecutive_failures = context.backoff().consecutive_failures();
+    let relay = consecutive_failures < context.max_consecutive_failures();
+
+    let status = |up: bool| if up { "UP" } else { "DOWN" };
+    let up = database && broker && relay;
+    let status_code = if up {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status_code,
+        axum::Json(json!({
+            "status": status(up),
+            "components": {
+                "database": { "status": status(database) },
+                "broker": { "status": status(broker) },
+                "relay": {
+                    "status": status(relay),
+                    "consecutive_failures": consecutive_failures,
+                },
+            }
+        })),
+    )
 }
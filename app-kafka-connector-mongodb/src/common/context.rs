@@ -1,21 +1,47 @@
 use std::sync::Arc;
 
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::BrokerStatus;
+use common_kafka::producer::TransactionalProducer;
 use mongodb::Client;
+use rdkafka::producer::FutureProducer;
 
 pub type DynContext = Arc<dyn Context>;
 
 pub trait Context: Sync + Send {
     fn db_client(&self) -> Arc<Client>;
+    fn producer(&self) -> Arc<FutureProducer>;
+    fn backoff(&self) -> Arc<Backoff>;
+    fn broker_status(&self) -> Arc<BrokerStatus>;
+    /// Number of failed runs of the relay in a row after which the service is
+    /// reported as unavailable.
+    fn max_consecutive_failures(&self) -> u32;
 }
 
 #[derive(Clone)]
 pub struct ContextImpl {
     pub client: Arc<Client>,
+    pub producer: Arc<TransactionalProducer>,
+    pub backoff: Arc<Backoff>,
+    pub broker_status: Arc<BrokerStatus>,
+    pub max_consecutive_failures: u32,
 }
 
 impl ContextImpl {
-    pub fn new_dyn_context(client: Arc<Client>) -> DynContext {
-        let context = ContextImpl { client };
+    pub fn new_dyn_context(
+        client: Arc<Client>,
+        producer: Arc<TransactionalProducer>,
+        backoff: Arc<Backoff>,
+        broker_status: Arc<BrokerStatus>,
+        max_consecutive_failures: u32,
+    ) -> DynContext {
+        let context = ContextImpl {
+            client,
+            producer,
+            backoff,
+            broker_status,
+            max_consecutive_failures,
+        };
         let context: DynContext = Arc::new(context);
         context
     }
@@ -25,4 +51,20 @@ impl Context for ContextImpl {
     fn db_client(&self) -> Arc<Client> {
         self.client.clone()
     }
+
+    fn producer(&self) -> Arc<FutureProducer> {
+        self.producer.get()
+    }
+
+    fn backoff(&self) -> Arc<Backoff> {
+        self.backoff.clone()
+    }
+
+    fn broker_status(&self) -> Arc<BrokerStatus> {
+        self.broker_status.clone()
+    }
+
+    fn max_consecutive_failures(&self) -> u32 {
+        self.max_consecutive_failures
+    }
 }
@@ -1 +1,9 @@
-pub const MAX_PAGE_SIZE: usize = 500;
+/// Collection with the sent events after their retention period.
+pub const EVENT_ARCHIVE_COLLECTION: &str = "event_archive";
+
+/// Collection with the resume token of the change stream.
+pub const RELAY_STATE_COLLECTION: &str = "event_relay";
+pub const RELAY_STATE_ID: &str = "change_stream";
+
+/// Id of the lease of the instance sending events in the relay collection.
+pub const LEASE_ID: &str = "lease";
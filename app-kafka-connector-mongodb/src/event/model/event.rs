@@ -1,4 +1,5 @@
 use mongodb::bson::oid::ObjectId;
+use mongodb::bson::DateTime;
 use serde::Deserialize;
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
@@ -11,4 +12,8 @@ pub struct Event {
     #[serde(with = "serde_bytes")]
     pub payload: Vec<u8>,
     pub trace_id: Option<String>,
+    pub aggregate_id: Option<String>,
+    pub event_type: Option<String>,
+    pub sent_at: Option<DateTime>,
+    pub kafka_offset: Option<i64>,
 }
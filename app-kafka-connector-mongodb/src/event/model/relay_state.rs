@@ -0,0 +1,10 @@
+use mongodb::change_stream::event::ResumeToken;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Position of the change stream of the event collection.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RelayState {
+    pub _id: String,
+    pub resume_token: ResumeToken,
+}
@@ -1,16 +1,29 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
 use anyhow::Result;
 use common_db_mongodb::util::get_collection;
 use common_error::AppError;
+use common_kafka::relay::send_in_order;
+use common_metrics::outbox;
 use common_tracing::B3SpanExt;
-use futures::future;
+use futures::Future;
 use futures::TryStreamExt;
 use mongodb::bson::doc;
 use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Bson;
+use mongodb::bson::DateTime;
+use mongodb::bson::Document;
+use mongodb::change_stream::event::ResumeToken;
 use mongodb::options::DeleteOptions;
+use mongodb::options::FindOneOptions;
 use mongodb::options::FindOptions;
+use mongodb::options::InsertManyOptions;
+use mongodb::options::ReplaceOptions;
+use mongodb::options::UpdateOptions;
 use mongodb::ClientSession;
 use opentelemetry_propagator_b3::propagator::Propagator;
 use opentelemetry_propagator_b3::propagator::B3_SINGLE_HEADER;
@@ -19,6 +32,8 @@ use rdkafka::producer::FutureProducer;
 use rdkafka::producer::FutureRecord;
 use rdkafka::producer::Producer;
 use rdkafka::util::Timeout;
+use serde::Serialize;
+use tracing::error;
 use tracing::info;
 use tracing::instrument;
 use tracing::span;
@@ -26,123 +41,482 @@ use tracing::Instrument;
 use tracing::Level;
 
 use super::super::model::event::Event;
-use crate::common::db::MAX_PAGE_SIZE;
+use super::super::model::relay_state::RelayState;
+use crate::common::db::EVENT_ARCHIVE_COLLECTION;
+use crate::common::db::RELAY_STATE_COLLECTION;
+use crate::common::db::RELAY_STATE_ID;
 
-pub async fn find_next_page(db_session: &ClientSession) -> Result<EventList> {
-    let page_size = MAX_PAGE_SIZE + 1;
-    let filter = doc! {};
+/// Finds the next unsent events in the order of their ids. The ids are
+/// generated by the services when the events are written and are only ordered
+/// per second across processes, so events of an aggregate written by
+/// different instances within the same second may be found in the wrong order.
+/// Use the change stream mode to send the events in commit order.
+pub async fn find_next_page(db_session: &ClientSession, batch_size: usize) -> Result<EventList> {
+    let page_size = batch_size + 1;
+    let filter = doc! {
+        "sent_at": null
+    };
 
     let cursor = get_collection::<Event>(db_session, "event")
         .find(
             filter,
-            FindOptions::builder().limit(Some(page_size as i64)).build(),
+            FindOptions::builder()
+                .sort(doc! { "_id": 1 })
+                .limit(Some(page_size as i64))
+                .build(),
         )
         .await?;
 
     let events: Vec<Event> = cursor.try_collect().await?;
 
-    let event_list = EventList {
-        has_more: events.len() > MAX_PAGE_SIZE,
-        events: events.into_iter().take(MAX_PAGE_SIZE).collect(),
+    Ok(EventList::page(events, batch_size))
+}
+
+/// Counts the events that weren't sent yet and finds the age of the oldest one.
+pub async fn find_lag(db_session: &ClientSession) -> Result<Lag, AppError> {
+    let filter = doc! {
+        "sent_at": null
     };
+    let collection = get_collection::<Event>(db_session, "event");
+
+    let unsent_events = collection.count_documents(filter.clone(), None).await?;
+
+    let oldest_unsent_event = collection
+        .find_one(
+            filter,
+            FindOneOptions::builder().sort(doc! { "_id": 1 }).build(),
+        )
+        .await?;
+    // The id contains the time the event was created
+    let oldest_unsent_event_age = oldest_unsent_event
+        .and_then(|event| {
+            SystemTime::now()
+                .duration_since(event._id.timestamp().to_system_time())
+                .ok()
+        })
+        .unwrap_or_default();
 
-    Ok(event_list)
+    Ok(Lag {
+        unsent_events,
+        oldest_unsent_event_age,
+    })
 }
 
-#[instrument(name = "delete_events", skip_all, level = "trace")]
-pub async fn delete_from_db(
+/// Finds the events with the ids in the order of the ids. Events that were
+/// already sent are skipped.
+pub async fn find_by_ids(
     db_session: &ClientSession,
-    events: &EventList,
+    event_ids: &[ObjectId],
+) -> Result<EventList, AppError> {
+    let filter = doc! {
+        "_id": { "$in" : event_ids.to_vec() },
+        "sent_at": null
+    };
+
+    let cursor = get_collection::<Event>(db_session, "event")
+        .find(filter, FindOptions::default())
+        .await?;
+
+    let mut events: Vec<Event> = cursor.try_collect().await?;
+    events.sort_by_key(|e| event_ids.iter().position(|id| *id == e._id));
+
+    Ok(EventList {
+        has_more: false,
+        events,
+    })
+}
+
+pub async fn find_resume_token(
+    db_session: &ClientSession,
+) -> Result<Option<ResumeToken>, AppError> {
+    let state = get_collection::<RelayState>(db_session, RELAY_STATE_COLLECTION)
+        .find_one(doc! { "_id": RELAY_STATE_ID }, None)
+        .await?;
+
+    Ok(state.map(|s| s.resume_token))
+}
+
+pub async fn save_resume_token(
+    db_session: &ClientSession,
+    resume_token: ResumeToken,
+) -> Result<(), AppError> {
+    let state = RelayState {
+        _id: RELAY_STATE_ID.to_string(),
+        resume_token,
+    };
+
+    get_collection::<RelayState>(db_session, RELAY_STATE_COLLECTION)
+        .replace_one(
+            doc! { "_id": RELAY_STATE_ID },
+            state,
+            ReplaceOptions::builder().upsert(true).build(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Deletes the saved resume token, so that the unsent events are found by
+/// polling when the change stream is opened the next time.
+pub async fn delete_resume_token(db_session: &ClientSession) -> Result<(), AppError> {
+    get_collection::<RelayState>(db_session, RELAY_STATE_COLLECTION)
+        .delete_one(doc! { "_id": RELAY_STATE_ID }, DeleteOptions::default())
+        .await?;
+
+    Ok(())
+}
+
+/// Marks the events as sent with the offset of the record in the partition.
+#[instrument(name = "mark_events_as_sent", skip_all, level = "trace")]
+pub async fn mark_as_sent(
+    db_session: &ClientSession,
+    sent_events: &[SentEvent],
 ) -> Result<(), AppError> {
-    let event_ids: Vec<ObjectId> = events
-        .events
+    let sent_at = DateTime::now();
+
+    for sent_event in sent_events {
+        get_collection::<Event>(db_session, "event")
+            .update_one(
+                doc! { "_id": sent_event.id },
+                doc! { "$set": { "sent_at": sent_at, "kafka_offset": sent_event.offset } },
+                UpdateOptions::default(),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the events that were sent before the time.
+pub async fn purge_sent_before(
+    db_session: &ClientSession,
+    sent_before: DateTime,
+) -> Result<u64, AppError> {
+    let result = get_collection::<Event>(db_session, "event")
+        .delete_many(
+            doc! { "sent_at": { "$lt": sent_before } },
+            DeleteOptions::default(),
+        )
+        .await?;
+
+    Ok(result.deleted_count)
+}
+
+/// Moves the events that were sent before the time to the archive.
+pub async fn archive_sent_before(
+    db_session: &ClientSession,
+    sent_before: DateTime,
+) -> Result<u64, AppError> {
+    let cursor = get_collection::<Document>(db_session, "event")
+        .find(
+            doc! { "sent_at": { "$lt": sent_before } },
+            FindOptions::default(),
+        )
+        .await?;
+    let events: Vec<Document> = cursor.try_collect().await?;
+
+    if events.is_empty() {
+        return Ok(0);
+    }
+
+    let event_ids: Vec<Bson> = events
         .iter()
-        .take(MAX_PAGE_SIZE)
-        .map(|e| e._id)
+        .filter_map(|e| e.get("_id").cloned())
         .collect();
+    get_collection::<Document>(db_session, EVENT_ARCHIVE_COLLECTION)
+        .insert_many(events, InsertManyOptions::default())
+        .await?;
 
-    let filter = doc! {
-        "_id": { "$in" : event_ids }
+    let result = get_collection::<Event>(db_session, "event")
+        .delete_many(
+            doc! { "_id": { "$in": event_ids } },
+            DeleteOptions::default(),
+        )
+        .await?;
+
+    Ok(result.deleted_count)
+}
+
+/// Finds the events of an aggregate in the outbox and the archive.
+pub async fn find_by_aggregate(
+    db_session: &ClientSession,
+    aggregate_id: &str,
+    event_type: Option<&str>,
+) -> Result<Vec<EventResource>, AppError> {
+    let mut filter = doc! {
+        "aggregate_id": aggregate_id
     };
+    if let Some(event_type) = event_type {
+        filter.insert("event_type", event_type);
+    }
 
-    get_collection::<Event>(db_session, "event")
-        .delete_many(filter, DeleteOptions::default())
-        .await?;
+    let mut events: Vec<EventResource> = Vec::new();
+    for (collection, archived) in [(EVENT_ARCHIVE_COLLECTION, true), ("event", false)] {
+        let cursor = get_collection::<Event>(db_session, collection)
+            .find(filter.clone(), FindOptions::default())
+            .await?;
+        let found: Vec<Event> = cursor.try_collect().await?;
+        events.extend(found.into_iter().map(|e| EventResource::new(e, archived)));
+    }
+    events.sort_by(|a, b| a.id.cmp(&b.id));
 
-    Ok(())
+    Ok(events)
 }
 
+/// Sends the events in a kafka transaction. The transaction is only committed
+/// if the `fence` succeeds, which checks that this instance still may send
+/// the events.
 #[instrument(name = "send_events", skip_all, level = "trace")]
-pub async fn send_to_kafka(
+pub async fn send_to_kafka<F>(
     producer: Arc<FutureProducer>,
     tracing_propagator: Arc<Propagator>,
     events: &EventList,
-) -> Result<(), AppError> {
+    transaction_timeout: Duration,
+    fence: F,
+) -> Result<Vec<SentEvent>, AppError>
+where
+    F: Future<Output = Result<(), AppError>>,
+{
     let events_to_send = &events.events;
     let number_of_events = events_to_send.len();
 
-    if number_of_events > 0 {
-        let mut event_ids: Vec<ObjectId> = Vec::new();
+    let mut sent_events = Vec::new();
 
+    if number_of_events > 0 {
         info!("Sending {} events", number_of_events);
+        let started = Instant::now();
 
         // Start kafka transaction
         producer.begin_transaction()?;
 
-        // Send each event individually. Send a span for each message to jaeger.
-        let send_result = future::try_join_all(events_to_send.iter().map(|event| {
-            let trace_id = event.trace_id.clone();
+        // Send the events of a partition one after another to keep the order
+        // of the events of an aggregate. Send a span for each message to jaeger.
+        let send_result = send_in_order(
+            events_to_send,
+            |event| (event.topic.as_str(), event.partition),
+            |event| {
+                let trace_id = event.trace_id.clone();
 
-            // Initialize span
-            let span = span!(Level::TRACE, "send");
-            let _ = span.enter();
+                // Initialize span
+                let span = span!(Level::TRACE, "send");
+                let _ = span.enter();
 
-            if let Some(id) = trace_id.clone() {
-                span.set_parent_from_b3(tracing_propagator.clone(), id);
-            }
+                if let Some(id) = trace_id.clone() {
+                    span.set_parent_from_b3(tracing_propagator.clone(), id);
+                }
 
-            // Create kafka headers with trace_id
-            let headers = match trace_id {
-                Some(id) => OwnedHeaders::new_with_capacity(1).add(B3_SINGLE_HEADER, &id),
-                _ => OwnedHeaders::default(),
-            };
+                // Create kafka headers with trace_id
+                let headers = match trace_id {
+                    Some(id) => OwnedHeaders::new_with_capacity(1).add(B3_SINGLE_HEADER, &id),
+                    _ => OwnedHeaders::default(),
+                };
 
-            // Send message to kafka
-            // Instrumented<OwnedDeliveryResult>
-            let delivery_result = {
-                producer.send(
-                    FutureRecord::to(&event.topic)
-                        .payload(&event.payload)
-                        .partition(event.partition)
-                        .key(&event.key)
-                        .headers(headers),
-                    Duration::from_secs(0),
-                )
-            }
-            .instrument(span);
-
-            // delivery_result.into_inner().expect("Couldn't send event");
-            event_ids.push(event._id);
-
-            delivery_result
-        }))
+                // Send message to kafka
+                producer
+                    .send(
+                        FutureRecord::to(&event.topic)
+                            .payload(&event.payload)
+                            .partition(event.partition)
+                            .key(&event.key)
+                            .headers(headers),
+                        Duration::from_secs(0),
+                    )
+                    .instrument(span)
+            },
+        )
         .await;
 
         match send_result {
-            Ok(_) => (),
-            Err(e) => return Err(e.0.into()),
+            Ok(results) => {
+                sent_events = results
+                    .into_iter()
+                    .map(|(event, (_, offset))| SentEvent {
+                        id: event._id,
+                        offset,
+                    })
+                    .collect()
+            }
+            Err(e) => {
+                abort_transaction(&producer, transaction_timeout);
+                return Err(e.0.into());
+            }
+        }
+
+        // Another instance sends the events if this instance lost the lease
+        if let Err(e) = fence.await {
+            abort_transaction(&producer, transaction_timeout);
+            return Err(e);
         }
 
         // Commit kafka transaction
-        producer.commit_transaction(Timeout::from(Duration::from_secs(30)))?;
+        if let Err(e) = producer.commit_transaction(Timeout::from(transaction_timeout)) {
+            abort_transaction(&producer, transaction_timeout);
+            return Err(e.into());
+        }
+        outbox::record_transaction_committed();
+
+        // Update metrics
+        outbox::record_batch(number_of_events, started.elapsed());
+        let mut events_per_topic: HashMap<&str, u64> = HashMap::new();
+        for event in events_to_send {
+            *events_per_topic.entry(event.topic.as_str()).or_default() += 1;
+        }
+        for (topic, count) in events_per_topic {
+            outbox::record_sent_events(topic, count);
+        }
 
         info!("Sent {} events", number_of_events);
     }
 
-    Ok(())
+    Ok(sent_events)
+}
+
+/// Aborts the kafka transaction, so that the events are sent again in a new
+/// transaction.
+fn abort_transaction(producer: &FutureProducer, transaction_timeout: Duration) {
+    outbox::record_transaction_aborted();
+    if let Err(e) = producer.abort_transaction(Timeout::from(transaction_timeout)) {
+        error!("Error occurred while aborting kafka transaction: {:?}", e);
+    }
+}
+
+/// Limits of the batches of events that are sent in one kafka transaction.
+#[derive(Clone, Copy, Debug)]
+pub struct BatchOptions {
+    pub size: usize,
+    pub transaction_timeout: Duration,
 }
 
 pub struct EventList {
     pub has_more: bool,
     pub events: Vec<Event>,
 }
+
+impl EventList {
+    /// Takes up to `batch_size` of the events in the order of their ids.
+    fn page(mut events: Vec<Event>, batch_size: usize) -> Self {
+        events.sort_by_key(|e| e._id);
+
+        EventList {
+            has_more: events.len() > batch_size,
+            events: events.into_iter().take(batch_size).collect(),
+        }
+    }
+}
+
+pub struct SentEvent {
+    pub id: ObjectId,
+    pub offset: i64,
+}
+
+pub struct Lag {
+    pub unsent_events: u64,
+    pub oldest_unsent_event_age: Duration,
+}
+
+/// Event without key and payload.
+#[derive(Debug, Serialize)]
+pub struct EventResource {
+    pub id: String,
+    pub aggregate_id: Option<String>,
+    pub event_type: Option<String>,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: Option<i64>,
+    pub sent_at: Option<String>,
+    pub trace_id: Option<String>,
+    pub archived: bool,
+}
+
+impl EventResource {
+    fn new(event: Event, archived: bool) -> Self {
+        EventResource {
+            id: event._id.to_hex(),
+            aggregate_id: event.aggregate_id,
+            event_type: event.event_type,
+            topic: event.topic,
+            partition: event.partition,
+            offset: event.kafka_offset,
+            sent_at: event.sent_at.map(|t| t.to_string()),
+            trace_id: event.trace_id,
+            archived,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    fn event(aggregate_id: &str, partition: i32) -> Event {
+        Event {
+            _id: ObjectId::new(),
+            topic: "user".to_string(),
+            partition,
+            key: aggregate_id.as_bytes().to_vec(),
+            payload: Vec::new(),
+            trace_id: None,
+            aggregate_id: Some(aggregate_id.to_string()),
+            event_type: None,
+            sent_at: None,
+            kafka_offset: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn interleaved_events_of_an_aggregate_are_sent_in_insert_order() {
+        // Interleaved writes of three aggregates, two of them in the same
+        // partition
+        let inserted: Vec<Event> = (0..12)
+            .map(|i| match i % 3 {
+                0 => event("a", 0),
+                1 => event("b", 0),
+                _ => event("c", 1),
+            })
+            .collect();
+
+        // The events are stored in any order
+        let mut stored = inserted.clone();
+        stored.reverse();
+        let page = EventList::page(stored, 10);
+        assert!(page.has_more);
+
+        let sent = Mutex::new(Vec::new());
+        send_in_order(
+            &page.events,
+            |event| (event.topic.as_str(), event.partition),
+            |event| {
+                let sent = &sent;
+                let position = inserted.iter().position(|e| e._id == event._id).unwrap();
+                async move {
+                    // Later events would overtake earlier ones if sent
+                    // concurrently
+                    tokio::time::sleep(Duration::from_millis(3 * (12 - position as u64))).await;
+                    sent.lock().unwrap().push(event.clone());
+                    Ok::<(), ()>(())
+                }
+            },
+        )
+        .await
+        .unwrap();
+
+        let sent = sent.into_inner().unwrap();
+        assert_eq!(sent.len(), 10);
+        for aggregate_id in ["a", "b", "c"] {
+            let ids_of = |events: &[Event]| -> Vec<ObjectId> {
+                events
+                    .iter()
+                    .filter(|e| e.aggregate_id.as_deref() == Some(aggregate_id))
+                    .map(|e| e._id)
+                    .collect()
+            };
+            let expected: Vec<ObjectId> = ids_of(&inserted)
+                .into_iter()
+                .filter(|id| page.events.iter().any(|e| e._id == *id))
+                .collect();
+            assert_eq!(ids_of(&sent), expected);
+        }
+    }
+}
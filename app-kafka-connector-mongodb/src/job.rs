@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use common_db_mongodb::transaction::transactional;
 use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::TransactionalProducer;
 use futures::FutureExt;
 use mongodb::Client;
 use opentelemetry_propagator_b3::propagator::Propagator;
@@ -10,65 +12,106 @@ use tokio::sync::Mutex;
 use tracing::error;
 
 use crate::event_service;
+use crate::event_service::BatchOptions;
+use crate::lease::Lease;
 
 pub async fn poll_and_send(
     job_synchronization_mutex: Arc<Mutex<bool>>,
+    backoff: Arc<Backoff>,
+    lease: Arc<Lease>,
     client: Arc<Client>,
-    producer: Arc<FutureProducer>,
+    producer: Arc<TransactionalProducer>,
     tracing_propagator: Arc<Propagator>,
-) -> Result<(), AppError> {
+    batch_options: BatchOptions,
+) {
     let lock = job_synchronization_mutex.try_lock();
 
-    if lock.is_ok() {
-        let mut more_events_to_send = true;
-        while more_events_to_send {
-            more_events_to_send =
-                find_send_delete(client.clone(), producer.clone(), tracing_propagator.clone())
-                    .await?;
+    // Events of a failed page are sent again with the next run
+    if lock.is_ok() && !backoff.is_waiting() {
+        match send_all(
+            lease,
+            client,
+            producer.get(),
+            tracing_propagator,
+            batch_options,
+        )
+        .await
+        {
+            Ok(_) => backoff.succeeded(),
+            Err(e) => {
+                let delay = backoff.failed();
+                error!(
+                    "Error occurred while sending events, retrying in {:?}: {:?}",
+                    delay, e
+                );
+
+                if let Err(e) = producer.recover(&e).await {
+                    error!("Error occurred while re-creating producer: {:?}", e);
+                }
+            }
         }
     }
+}
+
+/// Sends the events while the lease is held. The lease is checked again before
+/// each batch is committed.
+pub async fn send_all(
+    lease: Arc<Lease>,
+    client: Arc<Client>,
+    producer: Arc<FutureProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) -> Result<(), AppError> {
+    let mut more_events_to_send = true;
+    while more_events_to_send && lease.is_held() {
+        more_events_to_send = find_send_mark(
+            lease.clone(),
+            client.clone(),
+            producer.clone(),
+            tracing_propagator.clone(),
+            batch_options,
+        )
+        .await?;
+    }
 
     Ok(())
 }
 
-async fn find_send_delete(
+async fn find_send_mark(
+    lease: Arc<Lease>,
     client: Arc<Client>,
     producer: Arc<FutureProducer>,
     tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
 ) -> Result<bool, AppError> {
     transactional(client, |db_session| {
+        let lease = lease.clone();
         let producer = producer.clone();
         let tracing_propagator = tracing_propagator.clone();
 
         async move {
-            let event_list = event_service::find_next_page(db_session).await;
+            let events = event_service::find_next_page(db_session, batch_options.size).await?;
 
-            match event_list {
-                Ok(events) => {
-                    // Skip further processing if there are no events to send
-                    if events.events.is_empty() {
-                        return Ok(false);
-                    }
+            // Skip further processing if there are no events to send
+            if events.events.is_empty() {
+                return Ok(false);
+            }
 
-                    // Send data
-                    event_service::send_to_kafka(
-                        producer.clone(),
-                        tracing_propagator.clone(),
-                        &events,
-                    )
-                    .await?;
+            // Send data
+            let sent_events = event_service::send_to_kafka(
+                producer.clone(),
+                tracing_propagator.clone(),
+                &events,
+                batch_options.transaction_timeout,
+                lease.fence(db_session),
+            )
+            .await?;
 
-                    // Delete sent events
-                    event_service::delete_from_db(db_session, &events).await?;
+            // Mark events as sent
+            event_service::mark_as_sent(db_session, &sent_events).await?;
 
-                    // Send signal to continue without waiting
-                    Ok(events.has_more)
-                }
-                Err(e) => {
-                    error!("Error occurred while sending events to kafka: {:?}", e);
-                    Ok(false)
-                }
-            }
+            // Send signal to continue without waiting
+            Ok(events.has_more)
         }
         .boxed()
     })
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use common_db_mongodb::util::get_collection;
+use common_error::AppError;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use mongodb::error::ErrorKind;
+use mongodb::error::WriteFailure;
+use mongodb::options::UpdateOptions;
+use mongodb::Client;
+use mongodb::ClientSession;
+use tracing::error;
+use tracing::info;
+
+use crate::common::db::LEASE_ID;
+use crate::common::db::RELAY_STATE_COLLECTION;
+
+const DUPLICATE_KEY_ERROR: i32 = 11000;
+
+/// Lease of the instance of the connector that sends the events. The lease
+/// expires if it isn't renewed (e.g. the instance died) and is taken over by
+/// another instance.
+pub struct Lease {
+    owner: String,
+    ttl: Duration,
+    valid_until: Mutex<Option<Instant>>,
+}
+
+impl Lease {
+    /// Whether this instance holds the lease and may send events.
+    pub fn is_held(&self) -> bool {
+        matches!(*self.valid_until.lock().unwrap(), Some(valid_until) if Instant::now() < valid_until)
+    }
+
+    /// Acquires the lease if it is free or expired, or renews it if it is held
+    /// by this instance. The expiry is computed with the clock of the database
+    /// server, so that the clocks of the instances don't have to be in sync.
+    async fn acquire_or_renew(&self, client: &Client) -> Result<bool, AppError> {
+        let result = client
+            .default_database()
+            .expect("No default db specified")
+            .collection::<Document>(RELAY_STATE_COLLECTION)
+            .update_one(
+                doc! {
+                    "_id": LEASE_ID,
+                    "$expr": {
+                        "$or": [
+                            { "$eq": ["$owner", &self.owner] },
+                            { "$lt": ["$expires_at", "$$NOW"] }
+                        ]
+                    }
+                },
+                vec![doc! {
+                    "$set": {
+                        "owner": &self.owner,
+                        "expires_at": { "$add": ["$$NOW", self.ttl.as_millis() as i64] }
+                    }
+                }],
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            // The lease is held by another instance, so the upsert conflicts
+            Err(e) => match *e.kind {
+                ErrorKind::Write(WriteFailure::WriteError(ref write_error))
+                    if write_error.code == DUPLICATE_KEY_ERROR =>
+                {
+                    Ok(false)
+                }
+                _ => Err(e.into()),
+            },
+        }
+    }
+
+    /// Checks that this instance still holds the lease and increments the
+    /// fencing token of the lease. It is called in the transaction of a batch
+    /// before the kafka transaction is committed, so that a batch that took
+    /// longer than the `ttl` isn't committed after another instance took over
+    /// the lease.
+    pub async fn fence(&self, db_session: &ClientSession) -> Result<(), AppError> {
+        let result = get_collection::<Document>(db_session, RELAY_STATE_COLLECTION)
+            .update_one(
+                doc! {
+                    "_id": LEASE_ID,
+                    "owner": &self.owner,
+                    "$expr": { "$gt": ["$expires_at", "$$NOW"] }
+                },
+                doc! { "$inc": { "fencing_token": 1_i64 } },
+                UpdateOptions::default(),
+            )
+            .await?;
+
+        if result.matched_count == 0 {
+            *self.valid_until.lock().unwrap() = None;
+            return Err(anyhow!("Lease {} was taken over by another instance", self.owner).into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Acquires and renews the lease in the background. The lease is renewed three
+/// times per `ttl`, so that another instance takes over within the `ttl` if
+/// this instance dies.
+pub fn run_lease_renewal(client: Arc<Client>, ttl: Duration) -> Arc<Lease> {
+    let lease = Arc::new(Lease {
+        owner: ObjectId::new().to_hex(),
+        ttl,
+        valid_until: Mutex::new(None),
+    });
+
+    let renewed_lease = lease.clone();
+    tokio::spawn(async move {
+        loop {
+            // Measured before the request, as the lease expires relative to it
+            let requested_at = Instant::now();
+            match renewed_lease.acquire_or_renew(&client).await {
+                Ok(held) => {
+                    let mut valid_until = renewed_lease.valid_until.lock().unwrap();
+                    let was_held = matches!(*valid_until, Some(v) if requested_at < v);
+                    if held && !was_held {
+                        info!("Acquired lease {}", renewed_lease.owner);
+                    } else if !held && was_held {
+                        info!("Lost lease {}", renewed_lease.owner);
+                    }
+                    *valid_until = if held {
+                        Some(requested_at + renewed_lease.ttl)
+                    } else {
+                        None
+                    };
+                }
+                // The lease stays valid until it expires
+                Err(e) => error!("Error occurred while renewing lease: {:?}", e),
+            }
+            tokio::time::sleep(renewed_lease.ttl / 3).await;
+        }
+    });
+
+    lease
+}
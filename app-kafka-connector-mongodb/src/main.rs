@@ -1,29 +1,44 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::routing::get;
+use axum::Extension;
 use axum::Router;
 use axum_tracing_opentelemetry::opentelemetry_tracing_layer;
 use common_db_mongodb::pool;
 use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::init_producer;
+use common_kafka::producer::BrokerStatus;
 use opentelemetry_propagator_b3::propagator::B3Encoding;
 use opentelemetry_propagator_b3::propagator::Propagator;
 
 use crate::common::api::health;
 use crate::common::context::ContextImpl;
-use crate::common::kafka::init_producer;
+use crate::common::context::DynContext;
 use crate::common::server::shutdown_signal;
 use crate::config::configuration::Configuration;
+use crate::config::configuration::RelayMode;
 use crate::config::configuration::ServerConfiguration;
 use crate::config::logging_tracing;
+use crate::event::api::find_events;
+use crate::event::api::find_lag;
 use crate::event::service::event_service;
+use crate::event::service::event_service::BatchOptions;
+use crate::lease::run_lease_renewal;
+use crate::schedule::run_lag_job;
+use crate::schedule::run_retention_job;
 use crate::schedule::run_scheduled_job;
+use crate::watch::run_change_stream_job;
 
 pub mod common;
 pub mod config;
 pub mod event;
 pub mod job;
+pub mod lease;
 pub mod schedule;
+pub mod watch;
 
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
@@ -35,26 +50,81 @@ async fn main() -> Result<(), AppError> {
 
     // Initialize db connection pool
     let db_client = Arc::new(pool::init_db_client(&config.database).await?);
-    let context = ContextImpl::new_dyn_context(db_client);
 
     // Initialize kafka producer
-    let producer = Arc::new(init_producer(&config.kafka)?);
+    let producer = Arc::new(init_producer(
+        &config.kafka.broker.urls,
+        &config.kafka.producer,
+    )?);
+    let backoff = Arc::new(Backoff::new());
+    let context = ContextImpl::new_dyn_context(
+        db_client,
+        producer.clone(),
+        backoff.clone(),
+        Arc::new(BrokerStatus::default()),
+        config.health.max_consecutive_failures,
+    );
 
     // Initialize tracing propagator
     let propagator = Arc::new(Propagator::with_encoding(B3Encoding::SingleHeader));
 
-    // Run scheduled job to poll events from database and send them to kafka
-    run_scheduled_job(context, producer, propagator).await?;
+    // Acquire the lease to send events, only one instance sends events at a time
+    let lease = run_lease_renewal(
+        context.db_client(),
+        Duration::from_secs(config.relay.lease.ttl),
+    );
+
+    // Run job to send the events from database to kafka
+    let batch_options = BatchOptions {
+        size: config.relay.batch_size,
+        transaction_timeout: Duration::from_millis(config.kafka.producer.transaction_timeout_ms),
+    };
+    match config.relay.mode {
+        RelayMode::Polling => {
+            run_scheduled_job(
+                context.clone(),
+                backoff,
+                lease.clone(),
+                producer,
+                propagator,
+                Duration::from_millis(config.relay.polling_interval_ms),
+                batch_options,
+            )
+            .await?
+        }
+        RelayMode::ChangeStream => {
+            run_change_stream_job(
+                context.clone(),
+                backoff,
+                lease.clone(),
+                producer,
+                propagator,
+                batch_options,
+            )
+            .await?
+        }
+    }
+
+    // Run job to purge or archive sent events
+    run_retention_job(&config.retention, context.clone(), lease).await?;
+
+    // Run job to record the lag of the relay
+    run_lag_job(
+        Duration::from_millis(config.relay.lag_interval_ms),
+        Duration::from_millis(config.health.broker_timeout_ms),
+        context.clone(),
+    )
+    .await?;
 
     // Start the web-server
-    start_web_server(&config.server).await;
+    start_web_server(&config.server, context).await;
 
     Ok(())
 }
 
-async fn start_web_server(config: &ServerConfiguration) {
+async fn start_web_server(config: &ServerConfiguration, context: DynContext) {
     // Initialize routing
-    let routing = init_routing();
+    let routing = init_routing(context);
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -70,8 +140,14 @@ async fn start_web_server(config: &ServerConfiguration) {
     opentelemetry::global::shutdown_tracer_provider();
 }
 
-fn init_routing() -> Router {
+fn init_routing(context: DynContext) -> Router {
+    let metrics_router = common_metrics::api::init_routing();
+
     Router::new()
         .route("/health", get(health))
+        .route("/events", get(find_events))
+        .route("/lag", get(find_lag))
         .layer(opentelemetry_tracing_layer())
+        .merge(metrics_router)
+        .layer(Extension(context))
 }
@@ -1,26 +1,44 @@
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::SystemTime;
 
+use common_db_mongodb::transaction::transactional;
 use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::TransactionalProducer;
+use common_metrics::outbox;
 use futures::FutureExt;
+use mongodb::bson::DateTime;
 use opentelemetry_propagator_b3::propagator::Propagator;
-use rdkafka::producer::FutureProducer;
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::Job;
 use tokio_cron_scheduler::JobScheduler;
+use tracing::error;
+use tracing::info;
 
 use crate::common::context::DynContext;
+use crate::config::configuration::RetentionAction;
+use crate::config::configuration::RetentionConfiguration;
+use crate::event_service;
+use crate::event_service::BatchOptions;
 use crate::job;
+use crate::lease::Lease;
 
 pub async fn run_scheduled_job(
     context: DynContext,
-    producer: Arc<FutureProducer>,
+    backoff: Arc<Backoff>,
+    lease: Arc<Lease>,
+    producer: Arc<TransactionalProducer>,
     tracing_propagator: Arc<Propagator>,
+    polling_interval: Duration,
+    batch_options: BatchOptions,
 ) -> Result<(), AppError> {
     let job_synchronization_mutex = Arc::new(Mutex::new(false));
 
-    let job = Job::new_repeated_async(Duration::from_secs(1), move |_job_id, _lock| {
+    let job = Job::new_repeated_async(polling_interval, move |_job_id, _lock| {
         let job_synchronization_mutex = job_synchronization_mutex.clone();
+        let backoff = backoff.clone();
+        let lease = lease.clone();
         let producer = producer.clone();
         let tracing_propagator = tracing_propagator.clone();
         let db_client = context.db_client();
@@ -28,12 +46,14 @@ pub async fn run_scheduled_job(
         async move {
             job::poll_and_send(
                 job_synchronization_mutex,
+                backoff,
+                lease,
                 db_client,
-                producer.clone(),
-                tracing_propagator.clone(),
+                producer,
+                tracing_propagator,
+                batch_options,
             )
-            .await
-            .expect("Scheduled job failed");
+            .await;
         }
         .boxed()
     })?;
@@ -48,3 +68,95 @@ pub async fn run_scheduled_job(
 
     Ok(())
 }
+
+/// Purges or archives the events that were sent before the retention period.
+pub async fn run_retention_job(
+    config: &RetentionConfiguration,
+    context: DynContext,
+    lease: Arc<Lease>,
+) -> Result<(), AppError> {
+    let action = config.action;
+    let period = Duration::from_secs(config.period);
+
+    let job = Job::new_repeated_async(
+        Duration::from_secs(config.interval),
+        move |_job_id, _lock| {
+            let db_client = context.db_client();
+            let lease = lease.clone();
+
+            async move {
+                if !lease.is_held() {
+                    return;
+                }
+
+                let sent_before = DateTime::from_system_time(SystemTime::now() - period);
+                let result = transactional(db_client, |db_session| {
+                    async move {
+                        match action {
+                            RetentionAction::Purge => {
+                                event_service::purge_sent_before(db_session, sent_before).await
+                            }
+                            RetentionAction::Archive => {
+                                event_service::archive_sent_before(db_session, sent_before).await
+                            }
+                        }
+                    }
+                    .boxed()
+                })
+                .await;
+
+                match result {
+                    Ok(count) => info!("Applied retention ({:?}) to {} events", action, count),
+                    Err(e) => error!("Error occurred while applying retention: {:?}", e),
+                }
+            }
+            .boxed()
+        },
+    )?;
+
+    let scheduler = JobScheduler::new().await?;
+    scheduler.add(job).await?;
+    scheduler.start().await?;
+
+    Ok(())
+}
+
+/// Records the number of unsent events and the age of the oldest one and
+/// checks the connectivity to the brokers for the health probes.
+pub async fn run_lag_job(
+    interval: Duration,
+    broker_timeout: Duration,
+    context: DynContext,
+) -> Result<(), AppError> {
+    context
+        .broker_status()
+        .refresh(context.producer(), broker_timeout)
+        .await;
+
+    let job = Job::new_repeated_async(interval, move |_job_id, _lock| {
+        let db_client = context.db_client();
+        let broker_status = context.broker_status();
+        let producer = context.producer();
+
+        async move {
+            broker_status.refresh(producer, broker_timeout).await;
+
+            let lag = match db_client.start_session(None).await {
+                Ok(db_session) => event_service::find_lag(&db_session).await,
+                Err(e) => Err(e.into()),
+            };
+
+            match lag {
+                Ok(lag) => outbox::record_lag(lag.unsent_events, lag.oldest_unsent_event_age),
+                Err(e) => error!("Error occurred while finding the lag: {:?}", e),
+            }
+        }
+        .boxed()
+    })?;
+
+    let scheduler = JobScheduler::new().await?;
+    scheduler.add(job).await?;
+    scheduler.start().await?;
+
+    Ok(())
+}
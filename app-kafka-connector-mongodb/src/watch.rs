@@ -0,0 +1,292 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use common_db_mongodb::transaction::transactional;
+use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::TransactionalProducer;
+use futures::FutureExt;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use mongodb::change_stream::event::ChangeStreamEvent;
+use mongodb::change_stream::event::ResumeToken;
+use mongodb::change_stream::ChangeStream;
+use mongodb::error::CommandError;
+use mongodb::error::ErrorKind;
+use mongodb::options::ChangeStreamOptions;
+use mongodb::Client;
+use opentelemetry_propagator_b3::propagator::Propagator;
+use rdkafka::producer::FutureProducer;
+use tracing::error;
+use tracing::info;
+use tracing::warn;
+
+use crate::common::context::DynContext;
+use crate::event_service;
+use crate::event_service::BatchOptions;
+use crate::job;
+use crate::lease::Lease;
+
+type EventChangeStream = ChangeStream<ChangeStreamEvent<Document>>;
+
+/// Error code of a change stream that can't be resumed because the resume
+/// token is no longer in the oplog.
+const CHANGE_STREAM_HISTORY_LOST: i32 = 286;
+
+/// Tails the inserts into the event collection and sends the events in the
+/// order their transactions were committed. The resume token is saved with
+/// each batch, so that the relay continues where it left off after a restart
+/// or when another instance takes over the lease.
+pub async fn run_change_stream_job(
+    context: DynContext,
+    backoff: Arc<Backoff>,
+    lease: Arc<Lease>,
+    producer: Arc<TransactionalProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) -> Result<(), AppError> {
+    tokio::spawn(watch(
+        context.db_client(),
+        backoff,
+        lease,
+        producer,
+        tracing_propagator,
+        batch_options,
+    ));
+
+    Ok(())
+}
+
+async fn open_change_stream(
+    client: Arc<Client>,
+    lease: Arc<Lease>,
+    producer: Arc<FutureProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) -> Result<EventChangeStream, AppError> {
+    let mut resume_token = transactional(client.clone(), |db_session| {
+        async move { event_service::find_resume_token(db_session).await }.boxed()
+    })
+    .await?;
+
+    let change_stream = match watch_events(&client, resume_token.clone()).await {
+        // The unsent events are found without the resume token instead
+        Err(e) if resume_token.is_some() && is_history_lost(&e) => {
+            warn!("Resume token is no longer in the oplog, sending unsent events");
+            delete_resume_token(client.clone()).await?;
+            resume_token = None;
+            watch_events(&client, None).await?
+        }
+        result => result?,
+    };
+
+    // Events inserted before the change stream was opened the first time
+    if resume_token.is_none() {
+        info!("No resume token found, sending existing events");
+        job::send_all(lease, client, producer, tracing_propagator, batch_options).await?;
+    }
+
+    Ok(change_stream)
+}
+
+async fn watch_events(
+    client: &Client,
+    resume_token: Option<ResumeToken>,
+) -> Result<EventChangeStream, mongodb::error::Error> {
+    client
+        .default_database()
+        .expect("No default db specified")
+        .collection::<Document>("event")
+        .watch(
+            [doc! { "$match": { "operationType": "insert" } }],
+            ChangeStreamOptions::builder()
+                .resume_after(resume_token)
+                .build(),
+        )
+        .await
+}
+
+async fn delete_resume_token(client: Arc<Client>) -> Result<(), AppError> {
+    transactional(client, |db_session| {
+        async move { event_service::delete_resume_token(db_session).await }.boxed()
+    })
+    .await
+}
+
+fn is_history_lost(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Command(CommandError { code, .. }) if *code == CHANGE_STREAM_HISTORY_LOST
+    )
+}
+
+async fn watch(
+    client: Arc<Client>,
+    backoff: Arc<Backoff>,
+    lease: Arc<Lease>,
+    producer: Arc<TransactionalProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) {
+    let mut change_stream = None;
+    loop {
+        // The change stream is opened when the lease is acquired and continues
+        // from the saved resume token
+        let stream = match change_stream.as_mut() {
+            Some(stream) => stream,
+            None => {
+                let delay = backoff.remaining().unwrap_or(Duration::from_secs(1));
+                tokio::time::sleep(delay).await;
+                if lease.is_held() {
+                    match open_change_stream(
+                        client.clone(),
+                        lease.clone(),
+                        producer.get(),
+                        tracing_propagator.clone(),
+                        batch_options,
+                    )
+                    .await
+                    {
+                        Ok(opened_change_stream) => {
+                            backoff.succeeded();
+                            change_stream = Some(opened_change_stream);
+                        }
+                        Err(e) => {
+                            error!("Error occurred while opening change stream: {:?}", e);
+                            recover(&backoff, &producer, e).await;
+                        }
+                    }
+                }
+                continue;
+            }
+        };
+
+        let result = match next_batch(stream, batch_options.size).await {
+            // Another instance took over and sends the batch
+            Ok(_) if !lease.is_held() => {
+                info!("Lease expired, closing change stream");
+                change_stream = None;
+                continue;
+            }
+            Ok((event_ids, resume_token)) => {
+                send(
+                    event_ids,
+                    resume_token,
+                    lease.clone(),
+                    client.clone(),
+                    producer.get(),
+                    tracing_propagator.clone(),
+                    batch_options.transaction_timeout,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        // Reopen the change stream after the backoff, so that the failed batch
+        // is sent again
+        match result {
+            Ok(_) => backoff.succeeded(),
+            Err(e) => {
+                error!("Error occurred while watching events: {:?}", e);
+                change_stream = None;
+
+                // The change stream is reopened without the resume token
+                if matches!(&e, AppError::MongoDbError(e) if is_history_lost(e)) {
+                    warn!("Resume token is no longer in the oplog, deleting it");
+                    if let Err(e) = delete_resume_token(client.clone()).await {
+                        error!("Error occurred while deleting resume token: {:?}", e);
+                    }
+                }
+
+                recover(&backoff, &producer, e).await;
+            }
+        }
+    }
+}
+
+async fn recover(backoff: &Backoff, producer: &TransactionalProducer, error: AppError) {
+    let delay = backoff.failed();
+    info!("Retrying in {:?}", delay);
+
+    if let Err(e) = producer.recover(&error).await {
+        error!("Error occurred while re-creating producer: {:?}", e);
+    }
+}
+
+/// Waits for the next insert and takes the inserts that are already available
+/// up to the batch size.
+async fn next_batch(
+    change_stream: &mut EventChangeStream,
+    batch_size: usize,
+) -> Result<(Vec<ObjectId>, ResumeToken), AppError> {
+    let mut event_ids = Vec::new();
+    let mut resume_token = None;
+
+    let mut change = change_stream.try_next().await?;
+    while let Some(event) = change {
+        if let Some(id) = event
+            .document_key
+            .and_then(|key| key.get_object_id("_id").ok())
+        {
+            event_ids.push(id);
+        }
+        resume_token = Some(event.id);
+
+        if event_ids.len() >= batch_size {
+            break;
+        }
+        change = match change_stream.try_next().now_or_never() {
+            Some(next) => next?,
+            None => None,
+        };
+    }
+
+    match resume_token {
+        Some(resume_token) => Ok((event_ids, resume_token)),
+        None => Err(anyhow!("Change stream was closed").into()),
+    }
+}
+
+async fn send(
+    event_ids: Vec<ObjectId>,
+    resume_token: ResumeToken,
+    lease: Arc<Lease>,
+    client: Arc<Client>,
+    producer: Arc<FutureProducer>,
+    tracing_propagator: Arc<Propagator>,
+    transaction_timeout: Duration,
+) -> Result<(), AppError> {
+    transactional(client, |db_session| {
+        let event_ids = event_ids.clone();
+        let resume_token = resume_token.clone();
+        let lease = lease.clone();
+        let producer = producer.clone();
+        let tracing_propagator = tracing_propagator.clone();
+
+        async move {
+            // Events sent before a restart are skipped
+            let events = event_service::find_by_ids(db_session, &event_ids).await?;
+
+            // Send data
+            let sent_events = event_service::send_to_kafka(
+                producer,
+                tracing_propagator,
+                &events,
+                transaction_timeout,
+                lease.fence(db_session),
+            )
+            .await?;
+
+            // Mark events as sent
+            event_service::mark_as_sent(db_session, &sent_events).await?;
+
+            event_service::save_resume_token(db_session, resume_token).await
+        }
+        .boxed()
+    })
+    .await
+}
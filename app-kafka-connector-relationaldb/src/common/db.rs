@@ -1 +1,2 @@
-pub const MAX_PAGE_SIZE: usize = 500;
+/// Key of the advisory lock that is held by the instance sending events.
+pub const RELAY_LOCK_KEY: i64 = i64::from_be_bytes(*b"outbox\0\0");
@@ -1,5 +1,4 @@
 pub mod api;
 pub mod context;
 pub mod db;
-pub mod kafka;
 pub mod server;
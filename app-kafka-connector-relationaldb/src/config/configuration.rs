@@ -1,6 +1,7 @@
 use std::env;
 
 use common_db_relationaldb::config::DatabaseConfiguration;
+use common_kafka::producer::ProducerProperties;
 use config::Config;
 use config::ConfigError;
 use config::File;
@@ -10,8 +11,11 @@ use serde::Deserialize;
 #[allow(unused)]
 pub struct Configuration {
     pub database: DatabaseConfiguration,
+    pub health: HealthConfiguration,
     pub kafka: KafkaConfiguration,
     pub logging: LoggingConfiguration,
+    pub relay: RelayConfiguration,
+    pub retention: RetentionConfiguration,
     pub server: ServerConfiguration,
 }
 
@@ -36,6 +40,12 @@ impl Configuration {
             );
         }
 
+        // The instance id differs per instance of the connector
+        builder = builder.set_override_option(
+            "kafka.producer.instance_id",
+            env::var("CONNECTOR_INSTANCE_ID").ok(),
+        )?;
+
         let parsed_config: Result<Configuration, ConfigError> = builder.build()?.try_deserialize();
 
         // Return config
@@ -43,6 +53,17 @@ impl Configuration {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct HealthConfiguration {
+    /// Timeout in milliseconds to fetch the cluster metadata from the brokers.
+    /// The brokers are checked with each run of the lag job.
+    pub broker_timeout_ms: u64,
+    /// Number of failed runs of the relay in a row after which the service is
+    /// reported as unavailable.
+    pub max_consecutive_failures: u32,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(unused)]
 pub struct KafkaConfiguration {
@@ -57,13 +78,6 @@ pub struct BrokerProperties {
     pub urls: String,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(unused)]
-pub struct ProducerProperties {
-    pub client_id: String,
-    pub transactional_id: String,
-}
-
 #[derive(Debug, Deserialize)]
 #[allow(unused)]
 pub struct SchemaRegistryProperties {
@@ -90,6 +104,56 @@ pub struct LoggingDirective {
     pub level: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct RelayConfiguration {
+    pub mode: RelayMode,
+    /// Maximum number of events sent in one kafka transaction.
+    pub batch_size: usize,
+    /// Interval in milliseconds.
+    pub polling_interval_ms: u64,
+    /// Interval in milliseconds of the job that records the lag of the relay.
+    pub lag_interval_ms: u64,
+    pub notify: NotifyProperties,
+}
+
+/// How inserted events are detected.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayMode {
+    /// Polls the event table with the `polling_interval_ms`.
+    Polling,
+    /// Listens for notifications of inserted events and polls with the
+    /// `notify.polling_interval_ms` in case notifications were missed.
+    Notify,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct NotifyProperties {
+    /// Interval in milliseconds.
+    pub polling_interval_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct RetentionConfiguration {
+    pub action: RetentionAction,
+    /// Seconds after which sent events are purged or archived.
+    pub period: u64,
+    /// Interval of the retention job in seconds.
+    pub interval: u64,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    /// Deletes the sent events.
+    Purge,
+    /// Moves the sent events to the archive table.
+    Archive,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(unused)]
 pub struct ServerConfiguration {
@@ -0,0 +1,54 @@
+use axum::extract::Extension;
+use axum::extract::Query;
+use axum::Json;
+use common_error::AppError;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::instrument;
+
+use crate::common::context::DynContext;
+use crate::event::service::event_service;
+use crate::event::service::event_service::EventResource;
+
+#[derive(Debug, Deserialize)]
+pub struct EventParams {
+    pub aggregate_id: String,
+    pub event_type: Option<String>,
+}
+
+/// Finds the events of an aggregate, e.g. to check whether and where an event
+/// was published.
+#[instrument(name = "event.api.find_events", skip_all)]
+pub async fn find_events(
+    Query(params): Query<EventParams>,
+    Extension(context): Extension<DynContext>,
+) -> Result<Json<Vec<EventResource>>, AppError> {
+    let events = event_service::find_by_aggregate(
+        &context.db_connection(),
+        &params.aggregate_id,
+        params.event_type.as_deref(),
+    )
+    .await?;
+
+    Ok(Json(events))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LagResource {
+    pub unsent_events: u64,
+    pub oldest_unsent_event_age_seconds: u64,
+}
+
+/// Number of events in the outbox that weren't sent yet and the age of the
+/// oldest one.
+#[instrument(name = "event.api.find_lag", skip_all)]
+pub async fn find_lag(
+    Extension(context): Extension<DynContext>,
+) -> Result<Json<LagResource>, AppError> {
+    let lag = event_service::find_lag(&context.db_connection()).await?;
+
+    Ok(Json(LagResource {
+        unsent_events: lag.unsent_events,
+        oldest_unsent_event_age_seconds: lag.oldest_unsent_event_age.as_secs(),
+    }))
+}
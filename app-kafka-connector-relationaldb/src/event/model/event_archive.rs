@@ -0,0 +1,22 @@
+use sea_orm::entity::prelude::*;
+
+/// Sent events after their retention period.
+#[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel, DeriveActiveModelBehavior)]
+#[sea_orm(table_name = "event_archive_entity")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: i32,
+    pub topic: String,
+    pub partition: i32,
+    pub key: Vec<u8>,
+    pub payload: Vec<u8>,
+    pub trace_id: Option<String>,
+    pub aggregate_id: Option<String>,
+    pub event_type: Option<String>,
+    pub sent_at: Option<DateTimeWithTimeZone>,
+    pub kafka_offset: Option<i64>,
+    pub created_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
@@ -1 +1,2 @@
 pub mod event;
+pub mod event_archive;
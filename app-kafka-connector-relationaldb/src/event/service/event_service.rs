@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
+use std::time::Instant;
 
 use anyhow::Result;
+use chrono::Utc;
 use common_error::AppError;
+use common_kafka::relay::send_in_order;
+use common_metrics::outbox;
 use common_tracing::B3SpanExt;
-use futures::future;
 use opentelemetry_propagator_b3::propagator::Propagator;
 use opentelemetry_propagator_b3::propagator::B3_SINGLE_HEADER;
 use rdkafka::message::OwnedHeaders;
@@ -12,13 +16,22 @@ use rdkafka::producer::FutureProducer;
 use rdkafka::producer::FutureRecord;
 use rdkafka::producer::Producer;
 use rdkafka::util::Timeout;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
 use sea_orm::ColumnTrait;
+use sea_orm::ConnectionTrait;
+use sea_orm::DatabaseConnection;
 use sea_orm::DatabaseTransaction;
+use sea_orm::DbBackend;
 use sea_orm::DeleteResult;
 use sea_orm::EntityTrait;
 use sea_orm::PaginatorTrait;
 use sea_orm::QueryFilter;
 use sea_orm::QueryOrder;
+use sea_orm::Statement;
+use sea_orm::UpdateResult;
+use serde::Serialize;
+use tracing::error;
 use tracing::info;
 use tracing::instrument;
 use tracing::span;
@@ -27,120 +40,330 @@ use tracing::Level;
 
 use super::super::model::event;
 use super::super::model::event::Entity as EventEntity;
-use crate::common::db::MAX_PAGE_SIZE;
+use super::super::model::event_archive;
+use super::super::model::event_archive::Entity as EventArchiveEntity;
+use crate::common::db::RELAY_LOCK_KEY;
 
-pub async fn find_next_page(db_connection: &DatabaseTransaction) -> Result<EventList> {
-    let page_size = MAX_PAGE_SIZE + 1;
+/// Locks the sending of events until the end of the transaction, so that the
+/// instances of the connector send the events one after another. The lock is
+/// released if the instance dies, so another instance continues immediately.
+pub async fn try_lock(db_connection: &DatabaseTransaction) -> Result<bool> {
+    let result = db_connection
+        .query_one(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_try_advisory_xact_lock($1) AS locked",
+            vec![RELAY_LOCK_KEY.into()],
+        ))
+        .await?;
+
+    Ok(match result {
+        Some(row) => row.try_get("", "locked")?,
+        None => false,
+    })
+}
+
+pub async fn find_next_page(
+    db_connection: &DatabaseTransaction,
+    batch_size: usize,
+) -> Result<EventList> {
+    let page_size = batch_size + 1;
 
     let events: Vec<event::Model> = EventEntity::find()
+        .filter(event::Column::SentAt.is_null())
         .order_by_asc(event::Column::Id)
         .paginate(db_connection, page_size)
         .fetch_page(0)
         .await?;
 
     let event_list = EventList {
-        has_more: events.len() > MAX_PAGE_SIZE,
-        events: events.into_iter().take(MAX_PAGE_SIZE).collect(),
+        has_more: events.len() > batch_size,
+        events: events.into_iter().take(batch_size).collect(),
     };
 
     Ok(event_list)
 }
 
-#[instrument(name = "delete_events", skip_all, level = "trace")]
-pub async fn delete_from_db(
+/// Counts the events that weren't sent yet and finds the age of the oldest one.
+pub async fn find_lag(db_connection: &DatabaseConnection) -> Result<Lag> {
+    let unsent_events = EventEntity::find()
+        .filter(event::Column::SentAt.is_null())
+        .count(db_connection)
+        .await?;
+
+    let oldest_unsent_event = EventEntity::find()
+        .filter(event::Column::SentAt.is_null())
+        .order_by_asc(event::Column::Id)
+        .one(db_connection)
+        .await?;
+    let oldest_unsent_event_age = oldest_unsent_event
+        .and_then(|event| {
+            (Utc::now() - event.created_at.with_timezone(&Utc))
+                .to_std()
+                .ok()
+        })
+        .unwrap_or_default();
+
+    Ok(Lag {
+        unsent_events: unsent_events as u64,
+        oldest_unsent_event_age,
+    })
+}
+
+/// Marks the events as sent with the offset of the record in the partition.
+#[instrument(name = "mark_events_as_sent", skip_all, level = "trace")]
+pub async fn mark_as_sent(
     db_connection: &DatabaseTransaction,
-    events: &EventList,
+    sent_events: &[SentEvent],
 ) -> Result<u64> {
-    let event_ids: Vec<i32> = events
-        .events
-        .iter()
-        .take(MAX_PAGE_SIZE)
-        .map(|e| e.id)
-        .collect();
+    let sent_at: DateTimeWithTimeZone = Utc::now().into();
+
+    let mut rows_affected = 0;
+    for sent_event in sent_events {
+        let result: UpdateResult = EventEntity::update_many()
+            .col_expr(event::Column::SentAt, Expr::value(sent_at))
+            .col_expr(event::Column::KafkaOffset, Expr::value(sent_event.offset))
+            .filter(event::Column::Id.eq(sent_event.id))
+            .exec(db_connection)
+            .await?;
+        rows_affected += result.rows_affected;
+    }
 
-    delete(db_connection, event_ids).await
+    Ok(rows_affected)
 }
 
-async fn delete(db_connection: &DatabaseTransaction, event_ids: Vec<i32>) -> Result<u64> {
+/// Deletes the events that were sent before the time.
+pub async fn purge_sent_before(
+    db_connection: &DatabaseTransaction,
+    sent_before: DateTimeWithTimeZone,
+) -> Result<u64> {
     let result: DeleteResult = EventEntity::delete_many()
-        .filter(event::Column::Id.is_in(event_ids))
+        .filter(event::Column::SentAt.lt(sent_before))
         .exec(db_connection)
         .await?;
 
     Ok(result.rows_affected)
 }
 
+/// Moves the events that were sent before the time to the archive.
+pub async fn archive_sent_before(
+    db_connection: &DatabaseTransaction,
+    sent_before: DateTimeWithTimeZone,
+) -> Result<u64> {
+    db_connection
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"INSERT INTO event_archive_entity
+               (id, topic, partition, key, payload, trace_id, aggregate_id, event_type, sent_at, kafka_offset, created_at)
+               SELECT id, topic, partition, key, payload, trace_id, aggregate_id, event_type, sent_at, kafka_offset, created_at
+               FROM event_entity WHERE sent_at < $1
+               ON CONFLICT DO NOTHING"#,
+            vec![sent_before.into()],
+        ))
+        .await?;
+
+    purge_sent_before(db_connection, sent_before).await
+}
+
+/// Finds the events of an aggregate in the outbox and the archive.
+pub async fn find_by_aggregate(
+    db_connection: &DatabaseConnection,
+    aggregate_id: &str,
+    event_type: Option<&str>,
+) -> Result<Vec<EventResource>> {
+    let mut query = EventEntity::find().filter(event::Column::AggregateId.eq(aggregate_id));
+    let mut archive_query =
+        EventArchiveEntity::find().filter(event_archive::Column::AggregateId.eq(aggregate_id));
+    if let Some(event_type) = event_type {
+        query = query.filter(event::Column::EventType.eq(event_type));
+        archive_query = archive_query.filter(event_archive::Column::EventType.eq(event_type));
+    }
+
+    let mut events: Vec<EventResource> = archive_query
+        .all(db_connection)
+        .await?
+        .into_iter()
+        .map(EventResource::from)
+        .collect();
+    events.extend(
+        query
+            .all(db_connection)
+            .await?
+            .into_iter()
+            .map(EventResource::from),
+    );
+    events.sort_by_key(|e| e.id);
+
+    Ok(events)
+}
+
 #[instrument(name = "send_events", skip_all, level = "trace")]
 pub async fn send_to_kafka(
     producer: Arc<FutureProducer>,
     tracing_propagator: Arc<Propagator>,
     events: &EventList,
-) -> Result<(), AppError> {
+    transaction_timeout: Duration,
+) -> Result<Vec<SentEvent>, AppError> {
     let events_to_send = &events.events;
     let number_of_events = events_to_send.len();
 
-    if number_of_events > 0 {
-        let mut event_ids: Vec<i32> = Vec::new();
+    let mut sent_events = Vec::new();
 
+    if number_of_events > 0 {
         info!("Sending {} events", number_of_events);
+        let started = Instant::now();
 
         // Start kafka transaction
         producer.begin_transaction()?;
 
-        // Send each event individually. Send a span for each message to jaeger.
-        let send_result = future::try_join_all(events_to_send.iter().map(|event| {
-            let trace_id = event.trace_id.clone();
+        // Send the events of a partition one after another to keep the order
+        // of the events of an aggregate. Send a span for each message to jaeger.
+        let send_result = send_in_order(
+            events_to_send,
+            |event| (event.topic.as_str(), event.partition),
+            |event| {
+                let trace_id = event.trace_id.clone();
 
-            // Initialize span
-            let span = span!(Level::TRACE, "send");
-            let _ = span.enter();
-
-            if let Some(id) = trace_id.clone() {
-                span.set_parent_from_b3(tracing_propagator.clone(), id);
-            }
+                // Initialize span
+                let span = span!(Level::TRACE, "send");
+                let _ = span.enter();
 
-            // Create kafka headers with trace_id
-            let headers = match trace_id {
-                Some(id) => OwnedHeaders::new_with_capacity(1).add(B3_SINGLE_HEADER, &id),
-                _ => OwnedHeaders::default(),
-            };
-
-            // Send message to kafka
-            // Instrumented<OwnedDeliveryResult>
-            let delivery_result = {
-                producer.send(
-                    FutureRecord::to(&event.topic)
-                        .payload(&event.payload)
-                        .partition(event.partition)
-                        .key(&event.key)
-                        .headers(headers),
-                    Duration::from_secs(0),
-                )
-            }
-            .instrument(span);
+                if let Some(id) = trace_id.clone() {
+                    span.set_parent_from_b3(tracing_propagator.clone(), id);
+                }
 
-            // delivery_result.into_inner().expect("Couldn't send event");
-            event_ids.push(event.id);
+                // Create kafka headers with trace_id
+                let headers = match trace_id {
+                    Some(id) => OwnedHeaders::new_with_capacity(1).add(B3_SINGLE_HEADER, &id),
+                    _ => OwnedHeaders::default(),
+                };
 
-            delivery_result
-        }))
+                // Send message to kafka
+                producer
+                    .send(
+                        FutureRecord::to(&event.topic)
+                            .payload(&event.payload)
+                            .partition(event.partition)
+                            .key(&event.key)
+                            .headers(headers),
+                        Duration::from_secs(0),
+                    )
+                    .instrument(span)
+            },
+        )
         .await;
 
         match send_result {
-            Ok(_) => (),
-            Err(e) => return Err(e.0.into()),
+            Ok(results) => {
+                sent_events = results
+                    .into_iter()
+                    .map(|(event, (_, offset))| SentEvent {
+                        id: event.id,
+                        offset,
+                    })
+                    .collect()
+            }
+            Err(e) => {
+                abort_transaction(&producer, transaction_timeout);
+                return Err(e.0.into());
+            }
         }
 
         // Commit kafka transaction
-        producer.commit_transaction(Timeout::from(Duration::from_secs(30)))?;
+        if let Err(e) = producer.commit_transaction(Timeout::from(transaction_timeout)) {
+            abort_transaction(&producer, transaction_timeout);
+            return Err(e.into());
+        }
+        outbox::record_transaction_committed();
+
+        // Update metrics
+        outbox::record_batch(number_of_events, started.elapsed());
+        let mut events_per_topic: HashMap<&str, u64> = HashMap::new();
+        for event in events_to_send {
+            *events_per_topic.entry(event.topic.as_str()).or_default() += 1;
+        }
+        for (topic, count) in events_per_topic {
+            outbox::record_sent_events(topic, count);
+        }
 
         info!("Sent {} events", number_of_events);
     }
 
-    Ok(())
+    Ok(sent_events)
+}
+
+/// Aborts the kafka transaction, so that the events are sent again in a new
+/// transaction.
+fn abort_transaction(producer: &FutureProducer, transaction_timeout: Duration) {
+    outbox::record_transaction_aborted();
+    if let Err(e) = producer.abort_transaction(Timeout::from(transaction_timeout)) {
+        error!("Error occurred while aborting kafka transaction: {:?}", e);
+    }
+}
+
+/// Limits of the batches of events that are sent in one kafka transaction.
+#[derive(Clone, Copy, Debug)]
+pub struct BatchOptions {
+    pub size: usize,
+    pub transaction_timeout: Duration,
 }
 
 pub struct EventList {
     pub has_more: bool,
     pub events: Vec<event::Model>,
 }
+
+pub struct SentEvent {
+    pub id: i32,
+    pub offset: i64,
+}
+
+pub struct Lag {
+    pub unsent_events: u64,
+    pub oldest_unsent_event_age: Duration,
+}
+
+/// Event without key and payload.
+#[derive(Debug, Serialize)]
+pub struct EventResource {
+    pub id: i32,
+    pub aggregate_id: Option<String>,
+    pub event_type: Option<String>,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: Option<i64>,
+    pub sent_at: Option<DateTimeWithTimeZone>,
+    pub trace_id: Option<String>,
+    pub archived: bool,
+}
+
+impl From<event::Model> for EventResource {
+    fn from(event: event::Model) -> Self {
+        EventResource {
+            id: event.id,
+            aggregate_id: event.aggregate_id,
+            event_type: event.event_type,
+            topic: event.topic,
+            partition: event.partition,
+            offset: event.kafka_offset,
+            sent_at: event.sent_at,
+            trace_id: event.trace_id,
+            archived: false,
+        }
+    }
+}
+
+impl From<event_archive::Model> for EventResource {
+    fn from(event: event_archive::Model) -> Self {
+        EventResource {
+            id: event.id,
+            aggregate_id: event.aggregate_id,
+            event_type: event.event_type,
+            topic: event.topic,
+            partition: event.partition,
+            offset: event.kafka_offset,
+            sent_at: event.sent_at,
+            trace_id: event.trace_id,
+            archived: true,
+        }
+    }
+}
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use common_db_relationaldb::transaction::transactional;
 use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::TransactionalProducer;
 use futures::FutureExt;
 use opentelemetry_propagator_b3::propagator::Propagator;
 use rdkafka::producer::FutureProducer;
@@ -10,68 +12,144 @@ use tokio::sync::Mutex;
 use tracing::error;
 
 use crate::event_service;
+use crate::event_service::BatchOptions;
 
 pub async fn poll_and_send(
     job_synchronization_mutex: Arc<Mutex<bool>>,
+    backoff: Arc<Backoff>,
     connection: Arc<DatabaseConnection>,
-    producer: Arc<FutureProducer>,
+    producer: Arc<TransactionalProducer>,
     tracing_propagator: Arc<Propagator>,
-) -> Result<(), AppError> {
+    batch_options: BatchOptions,
+) {
     let lock = job_synchronization_mutex.try_lock();
 
     if lock.is_ok() {
-        let mut more_events_to_send = true;
-        while more_events_to_send {
-            more_events_to_send = find_send_delete(
-                connection.clone(),
-                producer.clone(),
-                tracing_propagator.clone(),
-            )
-            .await?;
+        send_all_with_backoff(
+            backoff,
+            connection,
+            producer,
+            tracing_propagator,
+            batch_options,
+        )
+        .await;
+    }
+}
+
+/// Waits for a running job to finish, so that events inserted while it was
+/// running aren't missed.
+pub async fn wait_and_send(
+    job_synchronization_mutex: Arc<Mutex<bool>>,
+    backoff: Arc<Backoff>,
+    connection: Arc<DatabaseConnection>,
+    producer: Arc<TransactionalProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) {
+    let _lock = job_synchronization_mutex.lock().await;
+    send_all_with_backoff(
+        backoff,
+        connection,
+        producer,
+        tracing_propagator,
+        batch_options,
+    )
+    .await
+}
+
+/// Sends the events unless a previous run failed recently. The events of a
+/// failed page are sent again with the next run.
+async fn send_all_with_backoff(
+    backoff: Arc<Backoff>,
+    connection: Arc<DatabaseConnection>,
+    producer: Arc<TransactionalProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) {
+    if backoff.is_waiting() {
+        return;
+    }
+
+    match send_all(
+        connection,
+        producer.get(),
+        tracing_propagator,
+        batch_options,
+    )
+    .await
+    {
+        Ok(_) => backoff.succeeded(),
+        Err(e) => {
+            let delay = backoff.failed();
+            error!(
+                "Error occurred while sending events, retrying in {:?}: {:?}",
+                delay, e
+            );
+
+            if let Err(e) = producer.recover(&e).await {
+                error!("Error occurred while re-creating producer: {:?}", e);
+            }
         }
     }
+}
+
+async fn send_all(
+    connection: Arc<DatabaseConnection>,
+    producer: Arc<FutureProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) -> Result<(), AppError> {
+    let mut more_events_to_send = true;
+    while more_events_to_send {
+        more_events_to_send = find_send_mark(
+            connection.clone(),
+            producer.clone(),
+            tracing_propagator.clone(),
+            batch_options,
+        )
+        .await?;
+    }
 
     Ok(())
 }
 
-async fn find_send_delete(
+async fn find_send_mark(
     connection: Arc<DatabaseConnection>,
     producer: Arc<FutureProducer>,
     tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
 ) -> Result<bool, AppError> {
     transactional(connection, |db_connection| {
         let producer = producer.clone();
         let tracing_propagator = tracing_propagator.clone();
 
         async move {
-            let event_list = event_service::find_next_page(db_connection).await;
-
-            match event_list {
-                Ok(events) => {
-                    // Skip further processing if there are no events to send
-                    if events.events.is_empty() {
-                        return Ok(false);
-                    }
-
-                    // Send data
-                    event_service::send_to_kafka(
-                        producer.clone(),
-                        tracing_propagator.clone(),
-                        &events,
-                    )
-                    .await?;
-
-                    // Delete sent events
-                    event_service::delete_from_db(db_connection, &events).await?;
-
-                    // Send signal to continue without waiting
-                    Ok(events.has_more)
-                }
-                Err(e) => {
-                    error!("Error occurred while sending events to kafka: {:?}", e);
-                    Ok(false)
-                }
+            // Another instance is sending events, it continues with the next page
+            if !event_service::try_lock(db_connection).await? {
+                return Ok(false);
             }
+
+            let events = event_service::find_next_page(db_connection, batch_options.size).await?;
+
+            // Skip further processing if there are no events to send
+            if events.events.is_empty() {
+                return Ok(false);
+            }
+
+            // Send data
+            let sent_events = event_service::send_to_kafka(
+                producer.clone(),
+                tracing_propagator.clone(),
+                &events,
+                batch_options.transaction_timeout,
+            )
+            .await?;
+
+            // Mark events as sent
+            event_service::mark_as_sent(db_connection, &sent_events).await?;
+
+            // Send signal to continue without waiting
+            Ok(events.has_more)
         }
         .boxed()
     })
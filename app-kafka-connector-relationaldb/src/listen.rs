@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use common_db_relationaldb::notify::EVENT_NOTIFY_CHANNEL;
+use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::TransactionalProducer;
+use opentelemetry_propagator_b3::propagator::Propagator;
+use sea_orm::DatabaseConnection;
+use sea_orm::DbErr;
+use sqlx::postgres::PgListener;
+use tokio::sync::Mutex;
+use tokio::sync::Notify;
+use tracing::error;
+use tracing::trace;
+use tracing::warn;
+
+use crate::event_service::BatchOptions;
+use crate::job;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Listens for the notifications of inserted events (see the
+/// `m20221015_add_event_notify_trigger` migration of the user-service) and
+/// sends the events right away. The listener and the sender are restarted if
+/// they fail.
+pub async fn run_notified_job(
+    database_url: &str,
+    job_synchronization_mutex: Arc<Mutex<bool>>,
+    backoff: Arc<Backoff>,
+    connection: Arc<DatabaseConnection>,
+    producer: Arc<TransactionalProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) -> Result<(), AppError> {
+    let listener = listen(database_url)
+        .await
+        .map_err(|e| DbErr::Conn(e.to_string()))?;
+
+    // Notifications received while events are sent result in one more run.
+    // The first run sends the events inserted before listening.
+    let send_signal = Arc::new(Notify::new());
+    send_signal.notify_one();
+
+    tokio::spawn(supervise_listener(
+        database_url.to_string(),
+        listener,
+        send_signal.clone(),
+    ));
+
+    tokio::spawn(async move {
+        loop {
+            let sender = tokio::spawn(send_notified_events(
+                send_signal.clone(),
+                job_synchronization_mutex.clone(),
+                backoff.clone(),
+                connection.clone(),
+                producer.clone(),
+                tracing_propagator.clone(),
+                batch_options,
+            ));
+            if let Err(e) = sender.await {
+                error!("Sending of notified events failed, restarting: {}", e);
+            }
+            send_signal.notify_one();
+        }
+    });
+
+    Ok(())
+}
+
+async fn listen(database_url: &str) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect(database_url).await?;
+    listener.listen(EVENT_NOTIFY_CHANNEL).await?;
+    Ok(listener)
+}
+
+/// Restarts the receiver of the notifications with a new listener, when it
+/// failed. Events inserted in the meantime are sent by the next run.
+async fn supervise_listener(database_url: String, listener: PgListener, send_signal: Arc<Notify>) {
+    let mut listener = Some(listener);
+    loop {
+        let listener = match listener.take() {
+            Some(listener) => listener,
+            None => match listen(&database_url).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Couldn't listen for events: {}", e);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            },
+        };
+
+        let receiver = tokio::spawn(receive_notifications(listener, send_signal.clone()));
+        match receiver.await {
+            Ok(e) => error!("Listening for events failed, restarting: {}", e),
+            Err(e) => error!("Listener of events failed, restarting: {}", e),
+        }
+        send_signal.notify_one();
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Receives notifications until an error occurs.
+async fn receive_notifications(mut listener: PgListener, send_signal: Arc<Notify>) -> sqlx::Error {
+    loop {
+        match listener.try_recv().await {
+            Ok(Some(notification)) => {
+                trace!("Received notification of event {}", notification.payload());
+                send_signal.notify_one();
+            }
+            // The listener reconnects with the next call. Events inserted while
+            // it is disconnected are sent by the next run.
+            Ok(None) => {
+                warn!("Connection of the event listener lost");
+                send_signal.notify_one();
+            }
+            Err(e) => return e,
+        }
+    }
+}
+
+async fn send_notified_events(
+    send_signal: Arc<Notify>,
+    job_synchronization_mutex: Arc<Mutex<bool>>,
+    backoff: Arc<Backoff>,
+    connection: Arc<DatabaseConnection>,
+    producer: Arc<TransactionalProducer>,
+    tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
+) {
+    loop {
+        send_signal.notified().await;
+
+        job::wait_and_send(
+            job_synchronization_mutex.clone(),
+            backoff.clone(),
+            connection.clone(),
+            producer.clone(),
+            tracing_propagator.clone(),
+            batch_options,
+        )
+        .await;
+
+        // Send the events of a failed run again after the backoff
+        if let Some(delay) = backoff.remaining() {
+            tokio::time::sleep(delay).await;
+            send_signal.notify_one();
+        }
+    }
+}
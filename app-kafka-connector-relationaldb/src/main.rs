@@ -1,27 +1,42 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::routing::get;
+use axum::Extension;
 use axum::Router;
 use axum_tracing_opentelemetry::opentelemetry_tracing_layer;
 use common_db_relationaldb::pool;
 use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::init_producer;
+use common_kafka::producer::BrokerStatus;
 use opentelemetry_propagator_b3::propagator::B3Encoding;
 use opentelemetry_propagator_b3::propagator::Propagator;
+use tokio::sync::Mutex;
 
 use crate::common::api::health;
-use crate::common::kafka::init_producer;
+use crate::common::context::ContextImpl;
+use crate::common::context::DynContext;
 use crate::common::server::shutdown_signal;
 use crate::config::configuration::Configuration;
+use crate::config::configuration::RelayMode;
 use crate::config::configuration::ServerConfiguration;
 use crate::config::logging_tracing;
+use crate::event::api::find_events;
+use crate::event::api::find_lag;
 use crate::event::service::event_service;
+use crate::event::service::event_service::BatchOptions;
+use crate::listen::run_notified_job;
+use crate::schedule::run_lag_job;
+use crate::schedule::run_retention_job;
 use crate::schedule::run_scheduled_job;
 
 pub mod common;
 pub mod config;
 pub mod event;
 pub mod job;
+pub mod listen;
 pub mod schedule;
 
 #[tokio::main]
@@ -36,23 +51,78 @@ async fn main() -> Result<(), AppError> {
     let connection_pool = Arc::new(pool::init(&config.database).await?);
 
     // Initialize kafka producer
-    let producer = Arc::new(init_producer(&config.kafka)?);
+    let producer = Arc::new(init_producer(
+        &config.kafka.broker.urls,
+        &config.kafka.producer,
+    )?);
 
     // Initialize tracing propagator
     let propagator = Arc::new(Propagator::with_encoding(B3Encoding::SingleHeader));
 
-    // Run scheduled job to poll events from database and send them to kafka
-    run_scheduled_job(connection_pool, producer, propagator).await?;
+    // Run jobs to send the events from database to kafka
+    let job_synchronization_mutex = Arc::new(Mutex::new(false));
+    let backoff = Arc::new(Backoff::new());
+    let batch_options = BatchOptions {
+        size: config.relay.batch_size,
+        transaction_timeout: Duration::from_millis(config.kafka.producer.transaction_timeout_ms),
+    };
+    let polling_interval = match config.relay.mode {
+        RelayMode::Polling => Duration::from_millis(config.relay.polling_interval_ms),
+        RelayMode::Notify => {
+            run_notified_job(
+                &config.database.url,
+                job_synchronization_mutex.clone(),
+                backoff.clone(),
+                connection_pool.clone(),
+                producer.clone(),
+                propagator.clone(),
+                batch_options,
+            )
+            .await?;
+            Duration::from_millis(config.relay.notify.polling_interval_ms)
+        }
+    };
+    run_scheduled_job(
+        polling_interval,
+        job_synchronization_mutex,
+        backoff.clone(),
+        connection_pool.clone(),
+        producer.clone(),
+        propagator,
+        batch_options,
+    )
+    .await?;
+
+    // Run job to purge or archive sent events
+    run_retention_job(&config.retention, connection_pool.clone()).await?;
+
+    // Run job to record the lag of the relay
+    let broker_status = Arc::new(BrokerStatus::default());
+    run_lag_job(
+        Duration::from_millis(config.relay.lag_interval_ms),
+        Duration::from_millis(config.health.broker_timeout_ms),
+        connection_pool.clone(),
+        producer.clone(),
+        broker_status.clone(),
+    )
+    .await?;
 
     // Start the web-server
-    start_web_server(&config.server).await;
+    let context = ContextImpl::new_dyn_context(
+        connection_pool,
+        producer,
+        backoff,
+        broker_status,
+        config.health.max_consecutive_failures,
+    );
+    start_web_server(&config.server, context).await;
 
     Ok(())
 }
 
-async fn start_web_server(config: &ServerConfiguration) {
+async fn start_web_server(config: &ServerConfiguration, context: DynContext) {
     // Initialize routing
-    let routing = init_routing();
+    let routing = init_routing(context);
 
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
@@ -68,8 +138,14 @@ async fn start_web_server(config: &ServerConfiguration) {
     opentelemetry::global::shutdown_tracer_provider();
 }
 
-fn init_routing() -> Router {
+fn init_routing(context: DynContext) -> Router {
+    let metrics_router = common_metrics::api::init_routing();
+
     Router::new()
         .route("/health", get(health))
+        .route("/events", get(find_events))
+        .route("/lag", get(find_lag))
         .layer(opentelemetry_tracing_layer())
+        .merge(metrics_router)
+        .layer(Extension(context))
 }
@@ -1,26 +1,41 @@
 use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::Utc;
+use common_db_relationaldb::transaction::transactional;
 use common_error::AppError;
+use common_kafka::backoff::Backoff;
+use common_kafka::producer::BrokerStatus;
+use common_kafka::producer::TransactionalProducer;
+use common_metrics::outbox;
 use futures::FutureExt;
 use opentelemetry_propagator_b3::propagator::Propagator;
-use rdkafka::producer::FutureProducer;
+use sea_orm::prelude::DateTimeWithTimeZone;
 use sea_orm::DatabaseConnection;
 use tokio::sync::Mutex;
 use tokio_cron_scheduler::Job;
 use tokio_cron_scheduler::JobScheduler;
+use tracing::error;
+use tracing::info;
 
+use crate::config::configuration::RetentionAction;
+use crate::config::configuration::RetentionConfiguration;
+use crate::event_service;
+use crate::event_service::BatchOptions;
 use crate::job;
 
 pub async fn run_scheduled_job(
+    interval: Duration,
+    job_synchronization_mutex: Arc<Mutex<bool>>,
+    backoff: Arc<Backoff>,
     connection: Arc<DatabaseConnection>,
-    producer: Arc<FutureProducer>,
+    producer: Arc<TransactionalProducer>,
     tracing_propagator: Arc<Propagator>,
+    batch_options: BatchOptions,
 ) -> Result<(), AppError> {
-    let job_synchronization_mutex = Arc::new(Mutex::new(false));
-
-    let job = Job::new_repeated_async(Duration::from_secs(1), move |_job_id, _lock| {
+    let job = Job::new_repeated_async(interval, move |_job_id, _lock| {
         let job_synchronization_mutex = job_synchronization_mutex.clone();
+        let backoff = backoff.clone();
         let connection = connection.clone();
         let producer = producer.clone();
         let tracing_propagator = tracing_propagator.clone();
@@ -28,12 +43,13 @@ pub async fn run_scheduled_job(
         async move {
             job::poll_and_send(
                 job_synchronization_mutex,
-                connection.clone(),
-                producer.clone(),
-                tracing_propagator.clone(),
+                backoff,
+                connection,
+                producer,
+                tracing_propagator,
+                batch_options,
             )
             .await
-            .expect("Scheduled job failed")
         }
         .boxed()
     })?;
@@ -48,3 +64,84 @@ pub async fn run_scheduled_job(
 
     Ok(())
 }
+
+/// Purges or archives the events that were sent before the retention period.
+pub async fn run_retention_job(
+    config: &RetentionConfiguration,
+    connection: Arc<DatabaseConnection>,
+) -> Result<(), AppError> {
+    let action = config.action;
+    let period = chrono::Duration::seconds(config.period as i64);
+
+    let job = Job::new_repeated_async(
+        Duration::from_secs(config.interval),
+        move |_job_id, _lock| {
+            let connection = connection.clone();
+
+            async move {
+                let sent_before: DateTimeWithTimeZone = (Utc::now() - period).into();
+                let result = transactional(connection, |db_connection| {
+                    async move {
+                        Ok(match action {
+                            RetentionAction::Purge => {
+                                event_service::purge_sent_before(db_connection, sent_before).await?
+                            }
+                            RetentionAction::Archive => {
+                                event_service::archive_sent_before(db_connection, sent_before)
+                                    .await?
+                            }
+                        })
+                    }
+                    .boxed()
+                })
+                .await;
+
+                match result {
+                    Ok(count) => info!("Applied retention ({:?}) to {} events", action, count),
+                    Err(e) => error!("Error occurred while applying retention: {:?}", e),
+                }
+            }
+            .boxed()
+        },
+    )?;
+
+    let scheduler = JobScheduler::new().await?;
+    scheduler.add(job).await?;
+    scheduler.start().await?;
+
+    Ok(())
+}
+
+/// Records the number of unsent events and the age of the oldest one and
+/// checks the connectivity to the brokers for the health probes.
+pub async fn run_lag_job(
+    interval: Duration,
+    broker_timeout: Duration,
+    connection: Arc<DatabaseConnection>,
+    producer: Arc<TransactionalProducer>,
+    broker_status: Arc<BrokerStatus>,
+) -> Result<(), AppError> {
+    broker_status.refresh(producer.get(), broker_timeout).await;
+
+    let job = Job::new_repeated_async(interval, move |_job_id, _lock| {
+        let connection = connection.clone();
+        let producer = producer.get();
+        let broker_status = broker_status.clone();
+
+        async move {
+            broker_status.refresh(producer, broker_timeout).await;
+
+            match event_service::find_lag(&connection).await {
+                Ok(lag) => outbox::record_lag(lag.unsent_events, lag.oldest_unsent_event_age),
+                Err(e) => error!("Error occurred while finding the lag: {:?}", e),
+            }
+        }
+        .boxed()
+    })?;
+
+    let scheduler = JobScheduler::new().await?;
+    scheduler.add(job).await?;
+    scheduler.start().await?;
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use std::env;
+
+use config::Config;
+use config::ConfigError;
+use config::File;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct Configuration {
+    pub kafka: KafkaConfiguration,
+}
+
+impl Configuration {
+    /// Loads the configuration of the profiles, e.g. `local`. Defaults to the
+    /// profiles of the `RUST_PROFILES_ACTIVE` environment variable.
+    pub fn load(profiles: Option<String>) -> Result<Self, ConfigError> {
+        let profiles_raw_string =
+            profiles.unwrap_or_else(|| env::var("RUST_PROFILES_ACTIVE").unwrap_or_default());
+        let active_profiles: Vec<&str> = profiles_raw_string
+            .split(',')
+            .into_iter()
+            .map(|p| p.trim())
+            .filter(|p| !(*p).is_empty())
+            .collect();
+
+        // Load always properties of application.yml
+        let mut builder =
+            Config::builder().add_source(File::with_name("resources/application.yml"));
+
+        // Load property files for profiles
+        for profile in active_profiles {
+            builder = builder.add_source(
+                File::with_name(&format!("resources/application-{}.yml", profile)).required(false),
+            );
+        }
+
+        builder.build()?.try_deserialize()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct KafkaConfiguration {
+    pub broker: BrokerProperties,
+    pub consumer: ConsumerProperties,
+    pub schema_registry: SchemaRegistryProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct BrokerProperties {
+    pub urls: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct ConsumerProperties {
+    pub group_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct SchemaRegistryProperties {
+    pub url: String,
+}
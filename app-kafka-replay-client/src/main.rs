@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Parser;
+use common_kafka::consumer::retry::HEADER_ORIGINAL_PARTITION;
+use common_kafka::consumer::retry::HEADER_ORIGINAL_TOPIC;
+use common_kafka::decoder::DecodedValue;
+use common_kafka::decoder::RecordDecoder;
+use common_kafka::decoder::SchemaRegistryRecordDecoder;
+use common_kafka::schema_cache::SchemaCache;
+use kafka_schema_common::schema_key::KeyAvro;
+use opentelemetry_propagator_b3::propagator::B3_SINGLE_HEADER;
+use rdkafka::consumer::Consumer;
+use rdkafka::consumer::StreamConsumer;
+use rdkafka::error::KafkaError;
+use rdkafka::message::BorrowedMessage;
+use rdkafka::message::Headers;
+use rdkafka::message::OwnedHeaders;
+use rdkafka::producer::FutureProducer;
+use rdkafka::producer::FutureRecord;
+use rdkafka::util::Timeout;
+use rdkafka::ClientConfig;
+use rdkafka::Message;
+use rdkafka::Offset;
+use rdkafka::TopicPartitionList;
+use schema_registry_converter::async_impl::schema_registry::SrSettings;
+use schema_registry_converter::error::SRCError;
+use serde_json::json;
+use tracing::warn;
+
+use crate::config::configuration::Configuration;
+
+mod config;
+
+/// Time to wait for further records before the end of the selection is
+/// assumed. Transaction markers occupy offsets without delivering a record,
+/// so the end offset isn't necessarily received.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Action to execute
+    #[clap(value_enum)]
+    action: ReplayAction,
+
+    /// Profiles of the configuration to load (e.g. local). Defaults to the
+    /// profiles of the RUST_PROFILES_ACTIVE environment variable.
+    #[clap(long)]
+    profile: Option<String>,
+
+    /// Topic to read the records from (e.g. a dead letter topic)
+    #[clap(long)]
+    topic: String,
+
+    /// Only select records from this partition
+    #[clap(long)]
+    partition: Option<i32>,
+
+    /// Only select records with an offset greater or equal to this offset
+    #[clap(long)]
+    from_offset: Option<i64>,
+
+    /// Only select records with a timestamp (epoch milliseconds) greater or
+    /// equal to this timestamp
+    #[clap(long)]
+    from_timestamp: Option<i64>,
+
+    /// Only select records with an offset less or equal to this offset
+    #[clap(long)]
+    to_offset: Option<i64>,
+
+    /// Only select records with this record name (e.g. CreateUserAvroV1)
+    #[clap(long)]
+    record_name: Option<String>,
+
+    /// Only select records with this identifier in the key
+    #[clap(long)]
+    key_identifier: Option<String>,
+
+    /// Topic to re-publish the records to. Defaults to the original topic of
+    /// the record.
+    #[clap(long)]
+    target_topic: Option<String>,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum ReplayAction {
+    /// Print the selected records
+    Browse,
+    /// Re-publish the selected records to their original topic
+    Replay,
+    /// Print new records continuously. Starts at the end of the topic if
+    /// neither an offset nor a timestamp is given.
+    Tail,
+}
+
+struct DecodedRecord {
+    record_name: Option<String>,
+    key: DecodedValue,
+    payload: DecodedValue,
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args = Args::parse();
+
+    // Load configuration files
+    let config =
+        Configuration::load(args.profile.clone()).expect("Loading of configuration failed");
+    let bootstrap_servers = config.kafka.broker.urls;
+    let schema_registry_url = config.kafka.schema_registry.url;
+
+    let sr_settings = SrSettings::new_builder(schema_registry_url.clone())
+        .build()
+        .expect("Schema registry settings invalid");
+    // Records of all topics and serialization formats can be decoded
+    let schemas = [
+        kafka_schema_common::SCHEMAS,
+        kafka_schema_user::SCHEMAS,
+        kafka_schema_accommodation::SCHEMAS,
+    ]
+    .concat();
+    let schema_cache = SchemaCache::init(&schema_registry_url, &schemas, None)
+        .await
+        .expect("Invalid record schemas");
+    let decoder = SchemaRegistryRecordDecoder::new(sr_settings, Arc::new(schema_cache));
+
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("bootstrap.servers", bootstrap_servers.clone())
+        .set("group.id", config.kafka.consumer.group_id)
+        .set("enable.auto.commit", "false")
+        .set("isolation.level", "read_committed")
+        .create()
+        .expect("Consumer creation failed");
+
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", bootstrap_servers)
+        .set("enable.idempotence", "true")
+        .set("request.required.acks", "all")
+        .create()
+        .expect("Producer creation failed");
+
+    // Assign the partitions and the offsets to start reading from
+    let mut end_offsets = assign_partitions(&consumer, &args);
+
+    let tail = matches!(args.action, ReplayAction::Tail);
+
+    // Records are only decoded if required to select or print them. Records
+    // that can't be decoded (e.g. in a dead letter topic) are printed and
+    // re-published as raw bytes unless a filter requires the decoded record.
+    let has_filter = args.record_name.is_some() || args.key_identifier.is_some();
+    let needs_decoding = has_filter || !matches!(args.action, ReplayAction::Replay);
+
+    let mut selected = 0;
+    while !end_offsets.is_empty() {
+        let message = if tail {
+            consumer.recv().await.expect("Couldn't read record")
+        } else {
+            match tokio::time::timeout(IDLE_TIMEOUT, consumer.recv()).await {
+                Ok(message) => message.expect("Couldn't read record"),
+                Err(_) => break,
+            }
+        };
+
+        // Stop reading partitions that reached the end of the selected range
+        let partition = message.partition();
+        if let Some(end_offset) = end_offsets.get(&partition) {
+            if message.offset() >= *end_offset - 1 {
+                end_offsets.remove(&partition);
+            }
+            if message.offset() >= *end_offset {
+                continue;
+            }
+        } else {
+            continue;
+        }
+
+        let record = if needs_decoding {
+            Some(decode(&decoder, &message).await)
+        } else {
+            None
+        };
+
+        if has_filter {
+            match &record {
+                Some(Ok(record)) if is_selected(&args, record) => {}
+                Some(Err(e)) => {
+                    warn!(
+                        "Skipped record that couldn't be decoded (partition: {}, offset: {}): {:?}",
+                        message.partition(),
+                        message.offset(),
+                        e
+                    );
+                    continue;
+                }
+                _ => continue,
+            }
+        }
+        selected += 1;
+
+        match args.action {
+            ReplayAction::Browse | ReplayAction::Tail => {
+                if let Some(record) = record {
+                    print_record(&message, record);
+                }
+            }
+            ReplayAction::Replay => {
+                if let Err(e) = replay(&producer, &message, args.target_topic.as_deref()).await {
+                    eprintln!(
+                        "Replay of record (partition: {}, offset: {}) failed: {}",
+                        message.partition(),
+                        message.offset(),
+                        e
+                    );
+                    eprintln!("Replayed records: {}", selected - 1);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    eprintln!("Selected records: {}", selected);
+}
+
+/// Assigns the partitions of the topic to the consumer and returns the offset
+/// after the last selected record per partition. Tailed partitions have no
+/// end.
+fn assign_partitions(consumer: &StreamConsumer, args: &Args) -> HashMap<i32, i64> {
+    let timeout = Timeout::After(Duration::from_secs(10));
+
+    let metadata = consumer
+        .fetch_metadata(Some(&args.topic), timeout)
+        .expect("Couldn't fetch metadata");
+
+    let partitions: Vec<i32> = metadata
+        .topics()
+        .iter()
+        .filter(|t| t.name() == args.topic)
+        .flat_map(|t| t.partitions().iter().map(|p| p.id()))
+        .filter(|p| args.partition.map_or(true, |selected| selected == *p))
+        .collect();
+
+    let tail = matches!(args.action, ReplayAction::Tail);
+    let timestamp_offsets = args
+        .from_timestamp
+        .map(|timestamp| offsets_for_timestamp(consumer, &args.topic, &partitions, timestamp));
+
+    let mut assignment = TopicPartitionList::new();
+    let mut end_offsets = HashMap::new();
+    for partition in partitions {
+        let (low, high) = consumer
+            .fetch_watermarks(&args.topic, partition, timeout)
+            .expect("Couldn't fetch watermarks");
+
+        // Start at the given offset or timestamp, whatever comes later
+        let default_start = if tail { high } else { low };
+        let mut start = args.from_offset.map_or(default_start, |o| o.max(low));
+        if let Some(timestamp_offsets) = &timestamp_offsets {
+            let timestamp_offset = timestamp_offsets.get(&partition).copied().unwrap_or(high);
+            start = if args.from_offset.is_some() {
+                start.max(timestamp_offset)
+            } else {
+                timestamp_offset
+            };
+        }
+
+        let end = match args.to_offset {
+            Some(o) if tail => o + 1,
+            Some(o) => (o + 1).min(high),
+            None if tail => i64::MAX,
+            None => high,
+        };
+
+        if start < end {
+            assignment
+                .add_partition_offset(&args.topic, partition, Offset::Offset(start))
+                .expect("Invalid offset");
+            end_offsets.insert(partition, end);
+        }
+    }
+
+    consumer
+        .assign(&assignment)
+        .expect("Assignment of partitions failed");
+
+    end_offsets
+}
+
+/// Returns the offset of the first record with a timestamp greater or equal to
+/// the given timestamp per partition.
+fn offsets_for_timestamp(
+    consumer: &StreamConsumer,
+    topic: &str,
+    partitions: &[i32],
+    timestamp: i64,
+) -> HashMap<i32, i64> {
+    let mut timestamps = TopicPartitionList::new();
+    for partition in partitions {
+        timestamps
+            .add_partition_offset(topic, *partition, Offset::Offset(timestamp))
+            .expect("Invalid timestamp");
+    }
+
+    consumer
+        .offsets_for_times(timestamps, Timeout::After(Duration::from_secs(10)))
+        .expect("Couldn't fetch offsets for timestamp")
+        .elements()
+        .iter()
+        .filter_map(|e| match e.offset() {
+            Offset::Offset(offset) => Some((e.partition(), offset)),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn decode(
+    decoder: &SchemaRegistryRecordDecoder<'_>,
+    message: &BorrowedMessage<'_>,
+) -> Result<DecodedRecord, SRCError> {
+    let key = decoder.decode(message.key()).await?;
+    let payload = decoder.decode(message.payload()).await?;
+
+    Ok(DecodedRecord {
+        record_name: payload.name,
+        key: key.value,
+        payload: payload.value,
+    })
+}
+
+fn is_selected(args: &Args, record: &DecodedRecord) -> bool {
+    if let Some(record_name) = &args.record_name {
+        if record.record_name.as_ref() != Some(record_name) {
+            return false;
+        }
+    }
+
+    if let Some(key_identifier) = &args.key_identifier {
+        match record.key.clone().deserialize::<KeyAvro>() {
+            Ok(key) if key.identifier.identifier == *key_identifier => {}
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Prints the record as a single line of JSON. Records that couldn't be
+/// decoded are printed with the hex encoded key and payload.
+fn print_record(message: &BorrowedMessage<'_>, record: Result<DecodedRecord, SRCError>) {
+    let headers: HashMap<String, String> = get_headers(message).into_iter().collect();
+
+    // The b3 header has the format {trace_id}-{span_id}-{sampling_state}
+    let trace_id = headers
+        .get(B3_SINGLE_HEADER)
+        .and_then(|b3| b3.split('-').next())
+        .map(|t| t.to_string());
+
+    let output = match record {
+        Ok(record) => {
+            // Print the key as KeyAvro if possible and fall back to the generic
+            // format
+            let key = record
+                .key
+                .clone()
+                .deserialize::<KeyAvro>()
+                .ok()
+                .and_then(|key| serde_json::to_value(key).ok())
+                .unwrap_or_else(|| record.key.into_json());
+
+            json!({
+                "topic": message.topic(),
+                "partition": message.partition(),
+                "offset": message.offset(),
+                "schema_name": record.record_name,
+                "trace_id": trace_id,
+                "headers": headers,
+                "key": key,
+                "value": record.payload.into_json(),
+            })
+        }
+        Err(e) => json!({
+            "topic": message.topic(),
+            "partition": message.partition(),
+            "offset": message.offset(),
+            "trace_id": trace_id,
+            "headers": headers,
+            "decode_error": e.to_string(),
+            "raw_key": message.key().map(to_hex),
+            "raw_value": message.payload().map(to_hex),
+        }),
+    };
+
+    println!("{}", output);
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Re-publishes the raw key and payload of the record with its tracing header
+/// to the target topic or the topic it was consumed from initially.
+async fn replay(
+    producer: &FutureProducer,
+    message: &BorrowedMessage<'_>,
+    target: Option<&str>,
+) -> Result<(), KafkaError> {
+    let headers = get_headers(message);
+    let get_header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+    };
+
+    let original_topic = get_header(HEADER_ORIGINAL_TOPIC);
+    let topic = target
+        .or(original_topic.as_deref())
+        .unwrap_or_else(|| message.topic());
+
+    // Keep the partition of the original record to retain the ordering
+    let original_partition = if original_topic.is_some() {
+        get_header(HEADER_ORIGINAL_PARTITION).and_then(|p| p.parse::<i32>().ok())
+    } else {
+        Some(message.partition())
+    };
+
+    let mut owned_headers = OwnedHeaders::new();
+    if let Some(b3) = get_header(B3_SINGLE_HEADER) {
+        owned_headers = owned_headers.add(B3_SINGLE_HEADER, &b3);
+    }
+
+    let mut record: FutureRecord<[u8], [u8]> = FutureRecord::to(topic).headers(owned_headers);
+    if target.is_none() || target == original_topic.as_deref() {
+        if let Some(partition) = original_partition {
+            record = record.partition(partition);
+        }
+    }
+    if let Some(key) = message.key() {
+        record = record.key(key);
+    }
+    if let Some(payload) = message.payload() {
+        record = record.payload(payload);
+    }
+
+    let (partition, offset) = producer
+        .send(record, Timeout::After(Duration::from_secs(30)))
+        .await
+        .map_err(|(e, _)| e)?;
+
+    println!(
+        "Replayed record (topic: {}, partition: {}, offset: {}) to {} (partition: {}, offset: {})",
+        message.topic(),
+        message.partition(),
+        message.offset(),
+        topic,
+        partition,
+        offset
+    );
+    Ok(())
+}
+
+fn get_headers(message: &BorrowedMessage<'_>) -> Vec<(String, String)> {
+    message
+        .headers()
+        .map(|headers| {
+            (0..headers.count())
+                .filter_map(|i| headers.get(i))
+                .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value).to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
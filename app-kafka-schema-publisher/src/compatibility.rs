@@ -0,0 +1,69 @@
+use kafka_schema_common::format::SerializationFormat;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::configuration::SchemaRegistryProperties;
+
+const CONTENT_TYPE_SCHEMA_REGISTRY: &str = "application/vnd.schemaregistry.v1+json";
+
+/// Result of the compatibility check of a schema with the latest version of
+/// its subject.
+#[derive(Debug)]
+pub enum Compatibility {
+    Compatible,
+    Incompatible(Vec<String>),
+    /// The subject doesn't exist yet, so there is nothing to be compatible to.
+    NewSubject,
+}
+
+#[derive(Deserialize)]
+struct CompatibilityResponse {
+    is_compatible: bool,
+    #[serde(default)]
+    messages: Vec<String>,
+}
+
+/// Asks the schema registry whether the schema is compatible with the latest
+/// registered version of the subject according to the compatibility level
+/// configured for the subject.
+pub fn check_compatibility(
+    properties: &SchemaRegistryProperties,
+    subject: &str,
+    schema_definition: &str,
+    format: SerializationFormat,
+) -> Result<Compatibility, reqwest::Error> {
+    let url = format!(
+        "{}/compatibility/subjects/{}/versions/latest?verbose=true",
+        properties.url.trim_end_matches('/'),
+        subject
+    );
+
+    let mut request = Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE_SCHEMA_REGISTRY)
+        .json(&json!({
+            "schema": schema_definition,
+            "schemaType": match format {
+                SerializationFormat::Avro => "AVRO",
+                SerializationFormat::JsonSchema => "JSON",
+                SerializationFormat::Protobuf => "PROTOBUF",
+            },
+        }));
+    if let Some(username) = &properties.username {
+        request = request.basic_auth(username, properties.password.as_ref());
+    }
+
+    let response = request.send()?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(Compatibility::NewSubject);
+    }
+
+    let response: CompatibilityResponse = response.error_for_status()?.json()?;
+    if response.is_compatible {
+        Ok(Compatibility::Compatible)
+    } else {
+        Ok(Compatibility::Incompatible(response.messages))
+    }
+}
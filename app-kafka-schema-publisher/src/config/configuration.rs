@@ -0,0 +1,66 @@
+use std::env;
+
+use config::Config;
+use config::ConfigError;
+use config::File;
+use kafka_schema_common::format::SerializationFormat;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct Configuration {
+    /// Formats the schemas are registered in. JSON and Protobuf schemas are
+    /// derived from the avro schemas.
+    #[serde(default = "default_formats")]
+    pub formats: Vec<SerializationFormat>,
+    pub schema_registry: SchemaRegistryProperties,
+}
+
+fn default_formats() -> Vec<SerializationFormat> {
+    vec![SerializationFormat::Avro]
+}
+
+impl Configuration {
+    pub fn load() -> Result<Self, ConfigError> {
+        let profiles_raw_string = env::var("RUST_PROFILES_ACTIVE").unwrap_or_default();
+        let active_profiles: Vec<&str> = profiles_raw_string
+            .split(',')
+            .into_iter()
+            .map(|p| p.trim())
+            .filter(|p| !(*p).is_empty())
+            .collect();
+
+        // Load always properties of application.yml
+        let mut builder =
+            Config::builder().add_source(File::with_name("resources/application.yml"));
+
+        // Load property files for profiles
+        for profile in active_profiles {
+            builder = builder.add_source(
+                File::with_name(&format!("resources/application-{}.yml", profile)).required(false),
+            );
+        }
+
+        // Environment variables take precedence over the property files
+        builder = builder
+            .set_override_option("schema_registry.url", env::var("SCHEMA_REGISTRY_URL").ok())?
+            .set_override_option(
+                "schema_registry.username",
+                env::var("SCHEMA_REGISTRY_USERNAME").ok(),
+            )?
+            .set_override_option(
+                "schema_registry.password",
+                env::var("SCHEMA_REGISTRY_PASSWORD").ok(),
+            )?;
+
+        builder.build()?.try_deserialize()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct SchemaRegistryProperties {
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
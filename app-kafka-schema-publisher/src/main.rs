@@ -1,28 +1,63 @@
+use std::process;
 use std::vec;
 
 use clap::Parser;
 use kafka_schema_accommodation::schema_create_accommodation::RAW_SCHEMA_CREATE_ACCOMMODATION_V1;
-use kafka_schema_accommodation::schema_create_accommodation::SCHEMA_NAME_CREATE_ACCOMMODATION;
-use kafka_schema_accommodation::schema_create_room_type::RAW_SCHEMA_CREATE_ROOM_TPE_V1;
-use kafka_schema_accommodation::schema_create_room_type::SCHEMA_NAME_CREATE_ROOM_TYPE;
-use kafka_schema_accommodation::schema_delete_room_type::RAW_SCHEMA_DELETE_ROOM_TPE_V1;
-use kafka_schema_accommodation::schema_delete_room_type::SCHEMA_NAME_DELETE_ROOM_TYPE;
+use kafka_schema_accommodation::schema_create_accommodation::REFERENCES_CREATE_ACCOMMODATION_V1;
+use kafka_schema_accommodation::schema_create_accommodation::SCHEMA_NAME_CREATE_ACCOMMODATION_V1;
+use kafka_schema_accommodation::schema_create_room_type::RAW_SCHEMA_CREATE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_create_room_type::REFERENCES_CREATE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_create_room_type::SCHEMA_NAME_CREATE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_delete_room_type::RAW_SCHEMA_DELETE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_delete_room_type::REFERENCES_DELETE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_delete_room_type::SCHEMA_NAME_DELETE_ROOM_TYPE_V1;
 use kafka_schema_accommodation::schema_update_accommodation::RAW_SCHEMA_UPDATE_ACCOMMODATION_V1;
-use kafka_schema_accommodation::schema_update_accommodation::SCHEMA_NAME_UPDATE_ACCOMMODATION;
-use kafka_schema_accommodation::schema_update_room_type::RAW_SCHEMA_UPDATE_ROOM_TPE_V1;
-use kafka_schema_accommodation::schema_update_room_type::SCHEMA_NAME_UPDATE_ROOM_TYPE;
+use kafka_schema_accommodation::schema_update_accommodation::REFERENCES_UPDATE_ACCOMMODATION_V1;
+use kafka_schema_accommodation::schema_update_accommodation::SCHEMA_NAME_UPDATE_ACCOMMODATION_V1;
+use kafka_schema_accommodation::schema_update_room_type::RAW_SCHEMA_UPDATE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_update_room_type::REFERENCES_UPDATE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::schema_update_room_type::SCHEMA_NAME_UPDATE_ROOM_TYPE_V1;
+use kafka_schema_accommodation::REFERENCE_ACCOMMODATION_ADDRESS;
+use kafka_schema_common::format::FormatError;
+use kafka_schema_common::format::SerializationFormat;
+use kafka_schema_common::reference::resolve_references;
 use kafka_schema_common::schema_key::RAW_SCHEMA_KEY;
+use kafka_schema_common::schema_key::REFERENCES_KEY;
 use kafka_schema_common::schema_key::SCHEMA_NAME_KEY;
+use kafka_schema_common::RecordSchema;
+use kafka_schema_common::SchemaReference;
+use kafka_schema_common::REFERENCE_IDENTIFIER;
+use kafka_schema_common::REFERENCE_ISO_COUNTRY_CODE;
 use kafka_schema_user::schema_create_user::RAW_SCHEMA_CREATE_USER_V1;
-use kafka_schema_user::schema_create_user::SCHEMA_NAME_CREATE_USER;
+use kafka_schema_user::schema_create_user::RAW_SCHEMA_CREATE_USER_V2;
+use kafka_schema_user::schema_create_user::REFERENCES_CREATE_USER_V1;
+use kafka_schema_user::schema_create_user::REFERENCES_CREATE_USER_V2;
+use kafka_schema_user::schema_create_user::SCHEMA_NAME_CREATE_USER_V1;
+use kafka_schema_user::schema_create_user::SCHEMA_NAME_CREATE_USER_V2;
+use kafka_schema_user::schema_delete_user::RAW_SCHEMA_DELETE_USER_V1;
+use kafka_schema_user::schema_delete_user::REFERENCES_DELETE_USER_V1;
+use kafka_schema_user::schema_delete_user::SCHEMA_NAME_DELETE_USER_V1;
+use kafka_schema_user::schema_update_user::RAW_SCHEMA_UPDATE_USER_V1;
+use kafka_schema_user::schema_update_user::REFERENCES_UPDATE_USER_V1;
+use kafka_schema_user::schema_update_user::SCHEMA_NAME_UPDATE_USER_V1;
+use kafka_schema_user::REFERENCE_PHONE_NUMBER;
 use schema_registry_converter::blocking::schema_registry::post_schema;
 use schema_registry_converter::blocking::schema_registry::SrSettings;
 use schema_registry_converter::error::SRCError;
 use schema_registry_converter::schema_registry_common::RegisteredSchema;
 use schema_registry_converter::schema_registry_common::SchemaType;
+use schema_registry_converter::schema_registry_common::SuppliedReference;
 use schema_registry_converter::schema_registry_common::SuppliedSchema;
 use tracing::error;
 
+use crate::compatibility::check_compatibility;
+use crate::compatibility::Compatibility;
+use crate::config::configuration::Configuration;
+use crate::config::configuration::SchemaRegistryProperties;
+
+mod compatibility;
+mod config;
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
@@ -30,110 +65,291 @@ struct Args {
     #[clap(value_enum)]
     action: PublisherAction,
 
-    /// Environment where to apply
-    #[clap(value_enum)]
-    env: Environment,
+    /// Only print what would be registered
+    #[clap(long)]
+    dry_run: bool,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum PublisherAction {
+    /// Check the compatibility of the schemas with the registered versions
+    Check,
+    /// Check the compatibility of the schemas and register them
     Register,
 }
 
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum Environment {
-    Local,
-}
-
 fn main() {
     tracing_subscriber::fmt::init();
 
     let args = Args::parse();
 
-    let schema_registry_url = match args.env {
-        Environment::Local => "http://localhost:8081".to_owned(),
-    };
+    let config = Configuration::load().expect("Loading of configuration failed");
+
+    // Every version of an event is registered, as records of previous versions
+    // remain in the topics. Shared types are registered before the schemas
+    // referencing them.
+    let mut schemas = vec![
+        // Shared types
+        SchemaToRegister::from_reference(&REFERENCE_IDENTIFIER),
+        SchemaToRegister::from_reference(&REFERENCE_ISO_COUNTRY_CODE),
+        SchemaToRegister::from_reference(&REFERENCE_PHONE_NUMBER),
+        SchemaToRegister::from_reference(&REFERENCE_ACCOMMODATION_ADDRESS),
+        SchemaToRegister::new(SCHEMA_NAME_KEY, RAW_SCHEMA_KEY, REFERENCES_KEY),
+        // User - Context
+        SchemaToRegister::new(
+            SCHEMA_NAME_CREATE_USER_V1,
+            RAW_SCHEMA_CREATE_USER_V1,
+            REFERENCES_CREATE_USER_V1,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_CREATE_USER_V2,
+            RAW_SCHEMA_CREATE_USER_V2,
+            REFERENCES_CREATE_USER_V2,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_UPDATE_USER_V1,
+            RAW_SCHEMA_UPDATE_USER_V1,
+            REFERENCES_UPDATE_USER_V1,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_DELETE_USER_V1,
+            RAW_SCHEMA_DELETE_USER_V1,
+            REFERENCES_DELETE_USER_V1,
+        ),
+        // Accommodation - Context
+        SchemaToRegister::new(
+            SCHEMA_NAME_CREATE_ACCOMMODATION_V1,
+            RAW_SCHEMA_CREATE_ACCOMMODATION_V1,
+            REFERENCES_CREATE_ACCOMMODATION_V1,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_UPDATE_ACCOMMODATION_V1,
+            RAW_SCHEMA_UPDATE_ACCOMMODATION_V1,
+            REFERENCES_UPDATE_ACCOMMODATION_V1,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_CREATE_ROOM_TYPE_V1,
+            RAW_SCHEMA_CREATE_ROOM_TYPE_V1,
+            REFERENCES_CREATE_ROOM_TYPE_V1,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_DELETE_ROOM_TYPE_V1,
+            RAW_SCHEMA_DELETE_ROOM_TYPE_V1,
+            REFERENCES_DELETE_ROOM_TYPE_V1,
+        ),
+        SchemaToRegister::new(
+            SCHEMA_NAME_UPDATE_ROOM_TYPE_V1,
+            RAW_SCHEMA_UPDATE_ROOM_TYPE_V1,
+            REFERENCES_UPDATE_ROOM_TYPE_V1,
+        ),
+    ];
+
+    // The JSON and Protobuf schemas are derived from the avro schemas. The
+    // referenced types are inlined.
+    let record_schemas = [
+        kafka_schema_common::SCHEMAS,
+        kafka_schema_user::SCHEMAS,
+        kafka_schema_accommodation::SCHEMAS,
+    ]
+    .concat();
+    for format in [
+        SerializationFormat::JsonSchema,
+        SerializationFormat::Protobuf,
+    ] {
+        for record_schema in &record_schemas {
+            match SchemaToRegister::derive(format, record_schema) {
+                Ok(schema) => schemas.push(schema),
+                Err(e) => {
+                    error!(
+                        "Failed to derive {:?} schema of \"{}\": \n{}",
+                        format, record_schema.name, e
+                    );
+                    process::exit(1);
+                }
+            }
+        }
+    }
+    schemas.retain(|s| config.formats.contains(&s.format));
+
+    // Check all schemas before anything is registered
+    if !check_schemas(&config.schema_registry, &schemas) {
+        error!("Incompatible schema changes detected");
+        process::exit(1);
+    }
 
     match args.action {
+        PublisherAction::Check => {}
         PublisherAction::Register => {
-            let schemas = vec![
-                SchemaToRegister::new(SCHEMA_NAME_KEY, RAW_SCHEMA_KEY),
-                // User - Context
-                SchemaToRegister::new(SCHEMA_NAME_CREATE_USER, RAW_SCHEMA_CREATE_USER_V1),
-                // Accommodation - Context
-                SchemaToRegister::new(
-                    SCHEMA_NAME_CREATE_ACCOMMODATION,
-                    RAW_SCHEMA_CREATE_ACCOMMODATION_V1,
-                ),
-                SchemaToRegister::new(
-                    SCHEMA_NAME_UPDATE_ACCOMMODATION,
-                    RAW_SCHEMA_UPDATE_ACCOMMODATION_V1,
-                ),
-                SchemaToRegister::new(SCHEMA_NAME_CREATE_ROOM_TYPE, RAW_SCHEMA_CREATE_ROOM_TPE_V1),
-                SchemaToRegister::new(SCHEMA_NAME_DELETE_ROOM_TYPE, RAW_SCHEMA_DELETE_ROOM_TPE_V1),
-                SchemaToRegister::new(SCHEMA_NAME_UPDATE_ROOM_TYPE, RAW_SCHEMA_UPDATE_ROOM_TPE_V1),
-            ];
+            let sr_settings = init_sr_settings(&config.schema_registry);
 
+            let mut failed = false;
             for schema in schemas {
-                register_schema(
-                    &schema_registry_url,
+                if args.dry_run {
+                    println!(
+                        "Would register {:?} schema \"{}\"",
+                        schema.format, schema.subject_name
+                    );
+                } else {
+                    failed |= !register_schema(&sr_settings, &schema);
+                }
+            }
+
+            if failed {
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Checks the compatibility of the schemas with the latest registered versions.
+/// Returns false if at least one schema is incompatible or couldn't be checked.
+///
+/// The referenced types are inlined into the checked schemas, so that the
+/// check doesn't depend on the registered versions of the referenced subjects.
+fn check_schemas(properties: &SchemaRegistryProperties, schemas: &[SchemaToRegister]) -> bool {
+    let mut compatible = true;
+    for schema in schemas {
+        let schema_definition =
+            match resolve_references(&schema.schema_definition, schema.references) {
+                Ok(schema_definition) => schema_definition,
+                Err(e) => {
+                    error!(
+                        "Failed to resolve references of schema \"{}\": \n{}",
+                        schema.subject_name, e
+                    );
+                    compatible = false;
+                    continue;
+                }
+            };
+
+        match check_compatibility(
+            properties,
+            &schema.subject_name,
+            &schema_definition,
+            schema.format,
+        ) {
+            Ok(Compatibility::Compatible) => {
+                println!("Schema \"{}\" is compatible", schema.subject_name)
+            }
+            Ok(Compatibility::NewSubject) => {
+                println!("Schema \"{}\" is not registered yet", schema.subject_name)
+            }
+            Ok(Compatibility::Incompatible(messages)) => {
+                error!(
+                    "Schema \"{}\" is incompatible: \n{}",
                     schema.subject_name,
-                    schema.schema_definition,
+                    messages.join("\n")
+                );
+                compatible = false;
+            }
+            Err(e) => {
+                error!(
+                    "Failed to check compatibility of schema \"{}\": \n{}",
+                    schema.subject_name, e
                 );
+                compatible = false;
             }
         }
     }
+    compatible
 }
 
-fn register_schema(schema_registry_url: &str, subject_name: &str, schema_definition: &str) {
-    let schema = get_schema(subject_name, schema_definition);
+fn init_sr_settings(properties: &SchemaRegistryProperties) -> SrSettings {
+    let mut builder = SrSettings::new_builder(properties.url.clone());
+    if let Some(username) = &properties.username {
+        builder.set_basic_authorization(username, properties.password.as_deref());
+    }
+    builder
+        .build()
+        .expect("Initialization of schema registry configuration failed")
+}
+
+/// Registers the schema. Returns false if the registration failed.
+fn register_schema(sr_settings: &SrSettings, schema: &SchemaToRegister) -> bool {
     print_registration_result(
-        subject_name,
-        register_schema_as_subject(schema_registry_url, subject_name, schema),
-    );
+        &schema.subject_name,
+        post_schema(sr_settings, schema.subject_name.clone(), get_schema(schema)),
+    )
 }
 
-fn get_schema(name: &str, schema_definition: &str) -> SuppliedSchema {
+fn get_schema(schema: &SchemaToRegister) -> SuppliedSchema {
+    let schema_type = match schema.format {
+        SerializationFormat::Avro => SchemaType::Avro,
+        SerializationFormat::JsonSchema => SchemaType::Json,
+        SerializationFormat::Protobuf => SchemaType::Protobuf,
+    };
+
     SuppliedSchema {
-        name: Some(name.to_owned()),
-        schema_type: SchemaType::Avro,
-        schema: schema_definition.to_owned(),
-        references: vec![],
+        name: Some(schema.subject_name.clone()),
+        schema_type,
+        schema: schema.schema_definition.clone(),
+        references: get_references(schema.references),
     }
 }
 
-fn register_schema_as_subject(
-    registry_url: &str,
-    subject: &str,
-    schema: SuppliedSchema,
-) -> Result<RegisteredSchema, SRCError> {
-    let sr_settings = SrSettings::new_builder(registry_url.to_owned())
-        .build()
-        .expect("Initialization of schema registry configuration failed");
-
-    post_schema(&sr_settings, subject.to_owned(), schema)
+/// Referenced types are registered with their name as subject.
+fn get_references(references: &[SchemaReference]) -> Vec<SuppliedReference> {
+    references
+        .iter()
+        .map(|reference| SuppliedReference {
+            name: reference.name.to_owned(),
+            subject: reference.name.to_owned(),
+            schema: reference.raw_schema.to_owned(),
+            references: get_references(reference.references),
+        })
+        .collect()
 }
 
-fn print_registration_result(subject_name: &str, result: Result<RegisteredSchema, SRCError>) {
+fn print_registration_result(
+    subject_name: &str,
+    result: Result<RegisteredSchema, SRCError>,
+) -> bool {
     match result {
-        Ok(registered_schema) => println!(
-            "Registered schema \"{}\" with id: {}",
-            subject_name, registered_schema.id
-        ),
-        Err(e) => error!("Failed to register schema \"{}\": \n{}", subject_name, e),
+        Ok(registered_schema) => {
+            println!(
+                "Registered schema \"{}\" with id: {}",
+                subject_name, registered_schema.id
+            );
+            true
+        }
+        Err(e) => {
+            error!("Failed to register schema \"{}\": \n{}", subject_name, e);
+            false
+        }
     }
 }
 
 struct SchemaToRegister<'a> {
-    subject_name: &'a str,
-    schema_definition: &'a str,
+    subject_name: String,
+    schema_definition: String,
+    references: &'a [SchemaReference],
+    format: SerializationFormat,
 }
 
 impl<'a> SchemaToRegister<'a> {
-    fn new(name: &'a str, schema: &'a str) -> SchemaToRegister<'a> {
+    fn new(name: &str, schema: &str, references: &'a [SchemaReference]) -> SchemaToRegister<'a> {
         Self {
-            subject_name: name,
-            schema_definition: schema,
+            subject_name: name.to_owned(),
+            schema_definition: schema.to_owned(),
+            references,
+            format: SerializationFormat::Avro,
         }
     }
+
+    fn from_reference(reference: &'a SchemaReference) -> SchemaToRegister<'a> {
+        Self::new(reference.name, reference.raw_schema, reference.references)
+    }
+
+    /// Derives the schema of the record in the format from the avro schema.
+    fn derive(
+        format: SerializationFormat,
+        record_schema: &RecordSchema,
+    ) -> Result<SchemaToRegister<'a>, FormatError> {
+        Ok(Self {
+            subject_name: format.subject_name(record_schema.name),
+            schema_definition: format.schema_of(record_schema)?,
+            references: &[],
+            format,
+        })
+    }
 }
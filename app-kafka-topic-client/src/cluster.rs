@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rdkafka::admin::AdminClient;
+use rdkafka::admin::AdminOptions;
+use rdkafka::admin::ConfigSource;
+use rdkafka::admin::OwnedResourceSpecifier;
+use rdkafka::admin::ResourceSpecifier;
+use rdkafka::client::DefaultClientContext;
+use rdkafka::error::KafkaError;
+use rdkafka::error::KafkaResult;
+use rdkafka::util::Timeout;
+
+/// Current state of a topic in the cluster.
+#[derive(Clone, Debug)]
+pub struct ClusterTopic {
+    pub name: String,
+    pub partitions: i32,
+    pub replication: i32,
+    /// Configs set explicitly for the topic (without broker defaults).
+    pub config: BTreeMap<String, String>,
+}
+
+/// Fetches the state of the topics with the given names. Topics that don't
+/// exist are not contained in the result. Fails if the configs of a topic
+/// couldn't be loaded, so that no changes are planned for an unknown state.
+pub async fn fetch_topics(
+    admin_client: &AdminClient<DefaultClientContext>,
+    names: &[&str],
+) -> KafkaResult<HashMap<String, ClusterTopic>> {
+    let metadata = admin_client
+        .inner()
+        .fetch_metadata(None, Timeout::After(Duration::from_secs(10)))?;
+
+    let mut topics: HashMap<String, ClusterTopic> = metadata
+        .topics()
+        .iter()
+        .filter(|t| names.contains(&t.name()))
+        .map(|t| {
+            let topic = ClusterTopic {
+                name: t.name().to_string(),
+                partitions: t.partitions().len() as i32,
+                replication: t
+                    .partitions()
+                    .first()
+                    .map(|p| p.replicas().len() as i32)
+                    .unwrap_or_default(),
+                config: BTreeMap::new(),
+            };
+            (topic.name.clone(), topic)
+        })
+        .collect();
+
+    if topics.is_empty() {
+        return Ok(topics);
+    }
+
+    // Load the configs of the existing topics
+    let resources: Vec<ResourceSpecifier> = topics
+        .keys()
+        .map(|name| ResourceSpecifier::Topic(name))
+        .collect();
+
+    let config_results = admin_client
+        .describe_configs(&resources, &AdminOptions::new())
+        .await?;
+
+    for result in config_results {
+        match result {
+            Ok(resource) => {
+                if let OwnedResourceSpecifier::Topic(name) = resource.specifier {
+                    if let Some(topic) = topics.get_mut(&name) {
+                        topic.config = resource
+                            .entries
+                            .into_iter()
+                            .filter(|e| matches!(e.source, ConfigSource::DynamicTopic))
+                            .filter_map(|e| e.value.map(|v| (e.name, v)))
+                            .collect();
+                    }
+                }
+            }
+            Err(e) => return Err(KafkaError::AdminOp(e)),
+        }
+    }
+
+    Ok(topics)
+}
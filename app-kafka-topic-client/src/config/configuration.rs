@@ -0,0 +1,57 @@
+use std::env;
+
+use config::Config;
+use config::ConfigError;
+use config::File;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct Configuration {
+    pub kafka: KafkaConfiguration,
+    #[serde(default)]
+    pub manifest: ManifestConfiguration,
+}
+
+impl Configuration {
+    pub fn load() -> Result<Self, ConfigError> {
+        let profiles_raw_string = env::var("RUST_PROFILES_ACTIVE").unwrap_or_default();
+        let active_profiles: Vec<&str> = profiles_raw_string
+            .split(',')
+            .into_iter()
+            .map(|p| p.trim())
+            .filter(|p| !(*p).is_empty())
+            .collect();
+
+        // Load always properties of application.yml
+        let mut builder =
+            Config::builder().add_source(File::with_name("resources/application.yml"));
+
+        // Load property files for profiles
+        for profile in active_profiles {
+            builder = builder.add_source(
+                File::with_name(&format!("resources/application-{}.yml", profile)).required(false),
+            );
+        }
+
+        builder.build()?.try_deserialize()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct KafkaConfiguration {
+    pub broker: BrokerProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(unused)]
+pub struct BrokerProperties {
+    pub urls: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[allow(unused)]
+pub struct ManifestConfiguration {
+    pub path: Option<String>,
+}
@@ -1,16 +1,20 @@
-extern crate core;
-
-use std::vec;
+use std::process;
 
 use clap::Parser;
+use common_kafka::topic::TopicManifest;
 use rdkafka::admin::AdminClient;
 use rdkafka::admin::AdminOptions;
-use rdkafka::admin::NewTopic;
-use rdkafka::admin::TopicReplication;
 use rdkafka::client::DefaultClientContext;
 use rdkafka::ClientConfig;
+use tracing::error;
 use tracing::warn;
 
+use crate::config::configuration::Configuration;
+
+mod cluster;
+mod config;
+mod reconcile;
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
@@ -18,19 +22,21 @@ struct Args {
     #[clap(value_enum)]
     action: TopicAction,
 
-    /// Environment where to apply
-    #[clap(value_enum)]
-    env: Environment,
+    /// Only apply the action to this topic of the manifest
+    #[clap(long)]
+    topic: Option<String>,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum TopicAction {
-    CreateTopics,
-}
-
-#[derive(clap::ValueEnum, Clone, Debug)]
-enum Environment {
-    Local,
+    /// Print the changes required to reconcile the cluster with the manifest
+    Plan,
+    /// Reconcile the cluster with the manifest
+    Apply,
+    /// Print the state of the topics of the manifest in the cluster
+    Describe,
+    /// Delete the topics of the manifest
+    Delete,
 }
 
 #[tokio::main]
@@ -39,69 +45,88 @@ async fn main() {
 
     let args = Args::parse();
 
-    let bootstrap_servers = match args.env {
-        Environment::Local => "localhost:9092".to_owned(),
-    };
+    // Load configuration files and topic manifest
+    let config = Configuration::load().expect("Loading of configuration failed");
+    let mut manifest = match &config.manifest.path {
+        Some(path) => TopicManifest::load(path),
+        None => TopicManifest::load_shared(),
+    }
+    .expect("Loading of topic manifest failed");
+
+    if let Some(topic) = &args.topic {
+        manifest.topics.retain(|t| t.name == *topic);
+    }
 
     let admin_client: AdminClient<DefaultClientContext> = ClientConfig::new()
-        .set("bootstrap.servers", bootstrap_servers)
+        .set("bootstrap.servers", config.kafka.broker.urls)
         .create()
         .expect("Admin client creation failed");
 
+    let names: Vec<&str> = manifest.topics.iter().map(|t| t.name.as_str()).collect();
+    let cluster = cluster::fetch_topics(&admin_client, &names)
+        .await
+        .expect("Loading of topics failed");
+
     match args.action {
-        TopicAction::CreateTopics => {
-            create_user_topic(&admin_client, 2, 1).await;
-            create_accommodation_topic(&admin_client, 2, 1).await;
+        TopicAction::Plan => {
+            let changes = reconcile::plan(&manifest, &cluster);
+            if changes.is_empty() {
+                println!("No changes");
+            }
+            for change in changes {
+                println!("{}", change);
+            }
         }
-    }
-}
-
-async fn create_user_topic(
-    admin_client: &AdminClient<DefaultClientContext>,
-    partitions: i32,
-    replication_factor: i32,
-) {
-    create_topic(admin_client, "user", partitions, replication_factor).await;
-}
-
-async fn create_accommodation_topic(
-    admin_client: &AdminClient<DefaultClientContext>,
-    partitions: i32,
-    replication_factor: i32,
-) {
-    create_topic(
-        admin_client,
-        "accommodation",
-        partitions,
-        replication_factor,
-    )
-    .await;
-}
-
-async fn create_topic(
-    admin_client: &AdminClient<DefaultClientContext>,
-    topic: &str,
-    partitions: i32,
-    replication_factor: i32,
-) {
-    let topic_replication = TopicReplication::Fixed(replication_factor);
-    let user_topic = NewTopic::new(topic, partitions, topic_replication);
-    let topics = vec![&user_topic];
-
-    let topic_result = admin_client
-        .create_topics(topics, &AdminOptions::new())
-        .await
-        .unwrap_or_else(|_| panic!("Creation of topic \"{}\" failed", topic));
-
-    topic_result.into_iter().for_each(|r| match r {
-        Ok(t) => {
-            println!(
-                "Created topic: {} (partitions: {}, replication: {})",
-                t, partitions, replication_factor
-            );
+        TopicAction::Apply => {
+            let changes = reconcile::plan(&manifest, &cluster);
+            if changes.is_empty() {
+                println!("No changes");
+            }
+            if let Err(e) = reconcile::apply(&admin_client, &changes).await {
+                error!("Applying changes failed: {}", e);
+                process::exit(1);
+            }
         }
-        Err(f) => {
-            warn!("Creation of topic \"{}\" failed with reason: {}", f.0, f.1);
+        TopicAction::Describe => {
+            for definition in &manifest.topics {
+                match cluster.get(&definition.name) {
+                    Some(topic) => println!(
+                        "{} (partitions: {}, replication: {}, config: {:?})",
+                        topic.name, topic.partitions, topic.replication, topic.config
+                    ),
+                    None => println!("{} (missing)", definition.name),
+                }
+            }
         }
-    });
+        TopicAction::Delete => {
+            let existing: Vec<&str> = names
+                .into_iter()
+                .filter(|name| cluster.contains_key(*name))
+                .collect();
+            if existing.is_empty() {
+                println!("No topics to delete");
+                return;
+            }
+
+            let results = admin_client
+                .delete_topics(&existing, &AdminOptions::new())
+                .await
+                .expect("Deletion of topics failed");
+
+            let mut failed = false;
+            for result in results {
+                match result {
+                    Ok(t) => println!("Deleted topic: {}", t),
+                    Err(f) => {
+                        warn!("Deletion of topic \"{}\" failed with reason: {}", f.0, f.1);
+                        failed = true;
+                    }
+                }
+            }
+
+            if failed {
+                process::exit(1);
+            }
+        }
+    }
 }
@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use common_kafka::topic::TopicDefinition;
+use common_kafka::topic::TopicManifest;
+use rdkafka::admin::AdminClient;
+use rdkafka::admin::AdminOptions;
+use rdkafka::admin::AlterConfig;
+use rdkafka::admin::NewPartitions;
+use rdkafka::admin::NewTopic;
+use rdkafka::admin::ResourceSpecifier;
+use rdkafka::admin::TopicReplication;
+use rdkafka::admin::TopicResult;
+use rdkafka::client::DefaultClientContext;
+use rdkafka::error::KafkaError;
+use tracing::warn;
+
+use crate::cluster::ClusterTopic;
+
+/// Change required to reconcile a topic of the cluster with the manifest.
+#[derive(Clone, Debug)]
+pub enum TopicChange {
+    Create(TopicDefinition),
+    IncreasePartitions {
+        topic: String,
+        from: i32,
+        to: i32,
+    },
+    /// Replaces the topic configs with the configs of the manifest.
+    AlterConfig {
+        topic: String,
+        config: BTreeMap<String, String>,
+        changes: Vec<ConfigChange>,
+    },
+    /// Differences that can't be applied automatically.
+    Unsupported {
+        topic: String,
+        reason: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct ConfigChange {
+    pub key: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+impl Display for TopicChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopicChange::Create(t) => write!(
+                f,
+                "+ create topic {} (partitions: {}, replication: {}, config: {:?})",
+                t.name, t.partitions, t.replication, t.config
+            ),
+            TopicChange::IncreasePartitions { topic, from, to } => {
+                write!(f, "~ topic {}: partitions {} -> {}", topic, from, to)
+            }
+            TopicChange::AlterConfig { topic, changes, .. } => {
+                write!(f, "~ topic {}: config", topic)?;
+                for change in changes {
+                    write!(
+                        f,
+                        "\n    {}: {} -> {}",
+                        change.key,
+                        change.from.as_deref().unwrap_or("(default)"),
+                        change.to.as_deref().unwrap_or("(default)")
+                    )?;
+                }
+                Ok(())
+            }
+            TopicChange::Unsupported { topic, reason } => {
+                write!(f, "! topic {}: {}", topic, reason)
+            }
+        }
+    }
+}
+
+/// Error of [`apply`].
+#[derive(Debug)]
+pub enum ApplyError {
+    /// A request to the cluster failed.
+    Kafka(KafkaError),
+    /// Changes that failed or can't be applied automatically. The other
+    /// changes were applied.
+    Failed(Vec<String>),
+}
+
+impl Display for ApplyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplyError::Kafka(e) => write!(f, "Kafka error: {}", e),
+            ApplyError::Failed(failures) => {
+                write!(f, "{} changes failed", failures.len())?;
+                for failure in failures {
+                    write!(f, "\n    {}", failure)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<KafkaError> for ApplyError {
+    fn from(e: KafkaError) -> Self {
+        ApplyError::Kafka(e)
+    }
+}
+
+/// Compares the manifest with the state of the cluster and returns the changes
+/// to apply.
+pub fn plan(manifest: &TopicManifest, cluster: &HashMap<String, ClusterTopic>) -> Vec<TopicChange> {
+    let mut changes = Vec::new();
+
+    for definition in &manifest.topics {
+        let existing = match cluster.get(&definition.name) {
+            Some(existing) => existing,
+            None => {
+                changes.push(TopicChange::Create(definition.clone()));
+                continue;
+            }
+        };
+
+        // Partitions can only be increased
+        if definition.partitions > existing.partitions {
+            changes.push(TopicChange::IncreasePartitions {
+                topic: definition.name.clone(),
+                from: existing.partitions,
+                to: definition.partitions,
+            });
+        } else if definition.partitions < existing.partitions {
+            changes.push(TopicChange::Unsupported {
+                topic: definition.name.clone(),
+                reason: format!(
+                    "partitions can't be decreased from {} to {}",
+                    existing.partitions, definition.partitions
+                ),
+            });
+        }
+
+        if definition.replication != existing.replication {
+            changes.push(TopicChange::Unsupported {
+                topic: definition.name.clone(),
+                reason: format!(
+                    "replication can't be changed from {} to {}",
+                    existing.replication, definition.replication
+                ),
+            });
+        }
+
+        let config_changes = diff_config(&existing.config, &definition.config);
+        if !config_changes.is_empty() {
+            changes.push(TopicChange::AlterConfig {
+                topic: definition.name.clone(),
+                config: definition.config.clone(),
+                changes: config_changes,
+            });
+        }
+    }
+
+    changes
+}
+
+fn diff_config(
+    current: &BTreeMap<String, String>,
+    desired: &BTreeMap<String, String>,
+) -> Vec<ConfigChange> {
+    let mut keys: Vec<&String> = current.keys().chain(desired.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| current.get(*key) != desired.get(*key))
+        .map(|key| ConfigChange {
+            key: key.clone(),
+            from: current.get(key).cloned(),
+            to: desired.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Applies the changes to the cluster. Unsupported changes are skipped. Fails
+/// if a change failed or is unsupported, after the other changes were applied.
+pub async fn apply(
+    admin_client: &AdminClient<DefaultClientContext>,
+    changes: &[TopicChange],
+) -> Result<(), ApplyError> {
+    let options = AdminOptions::new();
+    let mut failures = Vec::new();
+
+    for change in changes {
+        println!("{}", change);
+
+        let results: Vec<TopicResult> = match change {
+            TopicChange::Create(definition) => {
+                let mut new_topic = NewTopic::new(
+                    &definition.name,
+                    definition.partitions,
+                    TopicReplication::Fixed(definition.replication),
+                );
+                for (key, value) in &definition.config {
+                    new_topic = new_topic.set(key, value);
+                }
+                admin_client.create_topics(&[new_topic], &options).await?
+            }
+            TopicChange::IncreasePartitions { topic, to, .. } => {
+                let new_partitions = NewPartitions::new(topic, *to as usize);
+                admin_client
+                    .create_partitions(&[new_partitions], &options)
+                    .await?
+            }
+            TopicChange::AlterConfig { topic, config, .. } => {
+                let mut alter_config = AlterConfig::new(ResourceSpecifier::Topic(topic));
+                for (key, value) in config {
+                    alter_config = alter_config.set(key, value);
+                }
+                admin_client
+                    .alter_configs(&[alter_config], &options)
+                    .await?
+                    .into_iter()
+                    .map(|r| {
+                        r.map(|_| topic.clone())
+                            .map_err(|(_, e)| (topic.clone(), e))
+                    })
+                    .collect()
+            }
+            TopicChange::Unsupported { topic, reason } => {
+                failures.push(format!("{}: {}", topic, reason));
+                continue;
+            }
+        };
+
+        for result in results {
+            if let Err((topic, reason)) = result {
+                warn!(
+                    "Change of topic \"{}\" failed with reason: {}",
+                    topic, reason
+                );
+                failures.push(format!("{}: {}", topic, reason));
+            }
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(ApplyError::Failed(failures))
+    }
+}
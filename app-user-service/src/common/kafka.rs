@@ -1,16 +1,36 @@
+use std::sync::Arc;
+
 use common_error::AppError;
-use schema_registry_converter::async_impl::avro::AvroEncoder;
-use schema_registry_converter::async_impl::schema_registry::SrSettings;
+use common_kafka::encoder::RecordEncoder;
+use common_kafka::schema_cache::SchemaCache;
+use common_kafka::topic::verify_partitions;
 
 use crate::config::configuration::KafkaConfiguration;
 
-pub fn init_avro_encoder<'a, 'b>(
-    config: &'a KafkaConfiguration,
-) -> Result<AvroEncoder<'b>, AppError> {
-    let sr_settings = resolve_sr_settings(config)?;
-    Ok(AvroEncoder::new(sr_settings))
+/// Initializes the cache of the ids of the schemas of the records produced by
+/// the service.
+pub async fn init_schema_cache(config: &KafkaConfiguration) -> Result<SchemaCache, AppError> {
+    let schemas = [kafka_schema_common::SCHEMAS, kafka_schema_user::SCHEMAS].concat();
+    Ok(SchemaCache::init(
+        &config.schema_registry.url,
+        &schemas,
+        config.schema_registry.cache_file.clone(),
+    )
+    .await?)
+}
+
+pub fn init_record_encoder(schema_cache: Arc<SchemaCache>) -> RecordEncoder {
+    RecordEncoder::new(schema_cache)
 }
 
-fn resolve_sr_settings(config: &KafkaConfiguration) -> Result<SrSettings, AppError> {
-    Ok(SrSettings::new_builder(config.schema_registry.url.clone()).build()?)
+/// Checks that the partitions of the mapped topics match the cluster.
+pub fn verify_topic_partitions(config: &KafkaConfiguration) -> Result<(), AppError> {
+    let topics: Vec<(String, i32)> = config
+        .topic
+        .mappings
+        .iter()
+        .map(|t| (t.topic_name.clone(), t.partitions))
+        .collect();
+
+    verify_partitions(&config.broker.urls, &topics, config.topic.partition_check)
 }
@@ -1,11 +1,15 @@
 use std::env;
+use std::path::PathBuf;
 use std::sync::atomic::Ordering::SeqCst;
 
 use common_db_relationaldb::config::DatabaseConfiguration;
+use common_kafka::topic::PartitionCheck;
+use common_kafka::topic::TopicManifest;
 use common_security::config::SecurityConfiguration;
 use config::Config;
 use config::ConfigError;
 use config::File;
+use kafka_schema_common::format::SerializationFormat;
 use serde::Deserialize;
 
 use crate::SERVER_PORT;
@@ -41,7 +45,17 @@ impl Configuration {
             );
         }
 
-        let parsed_config: Result<Configuration, ConfigError> = builder.build()?.try_deserialize();
+        let parsed_config: Result<Configuration, ConfigError> = builder
+            .build()?
+            .try_deserialize()
+            .and_then(|mut config: Configuration| {
+                // Take the partitions of the topics from the shared topic manifest
+                config
+                    .kafka
+                    .topic
+                    .resolve_partitions(&TopicManifest::load_shared()?)?;
+                Ok(config)
+            });
 
         // Set server port statically
         if let Ok(config) = &parsed_config {
@@ -71,15 +85,28 @@ pub struct BrokerProperties {
 #[allow(unused)]
 pub struct SchemaRegistryProperties {
     pub url: String,
+    /// File to persist the ids of the schemas in, so that records can be
+    /// produced if the schema registry isn't available on startup.
+    pub cache_file: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 #[allow(unused)]
 pub struct TopicConfiguration {
     pub mappings: Vec<TopicProperties>,
+    #[serde(default)]
+    pub partition_check: PartitionCheck,
 }
 
 impl TopicConfiguration {
+    /// Sets the partitions of the mapped topics from the topic manifest.
+    pub fn resolve_partitions(&mut self, manifest: &TopicManifest) -> Result<(), ConfigError> {
+        for mapping in &mut self.mappings {
+            mapping.partitions = manifest.partitions_of(&mapping.topic_name)?;
+        }
+        Ok(())
+    }
+
     pub fn get_mapping(&self, id: &str) -> TopicProperties {
         let mapping: Vec<TopicProperties> = self
             .mappings
@@ -100,7 +127,11 @@ impl TopicConfiguration {
 pub struct TopicProperties {
     pub id: String,
     pub topic_name: String,
+    #[serde(skip)]
     pub partitions: i32,
+    /// Serialization format of the records of the topic. Defaults to avro.
+    #[serde(default)]
+    pub format: SerializationFormat,
 }
 
 #[derive(Debug, Deserialize)]
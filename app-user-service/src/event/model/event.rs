@@ -11,6 +11,11 @@ pub struct Model {
     pub key: Vec<u8>,
     pub payload: Vec<u8>,
     pub trace_id: Option<String>,
+    pub aggregate_id: Option<String>,
+    pub event_type: Option<String>,
+    pub sent_at: Option<DateTimeWithTimeZone>,
+    pub kafka_offset: Option<i64>,
+    pub created_at: DateTimeWithTimeZone,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
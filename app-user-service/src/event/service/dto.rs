@@ -7,6 +7,9 @@ pub trait SerializableEventDto: Send + Sync {
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct EventDto {
+    /// Identifier of the aggregate the event belongs to.
+    pub aggregate_id: String,
+    pub event_type: String,
     pub topic: String,
     pub partition: i32,
     pub key: Vec<u8>,
@@ -14,6 +14,8 @@ pub async fn save(db_connection: &DatabaseTransaction, event: &EventDto) -> Resu
 
     // Build the entity from dto
     let e = event::ActiveModel {
+        aggregate_id: Set(Some(event.aggregate_id.clone())),
+        event_type: Set(Some(event.event_type.clone())),
         key: Set(event.key.clone()),
         payload: Set(event.payload.clone()),
         partition: Set(event.partition),
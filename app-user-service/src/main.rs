@@ -44,13 +44,20 @@ async fn main() -> Result<(), AppError> {
     // Initialize logging and tracing
     logging_tracing::init(&config)?;
 
+    // Check that records are partitioned consistently
+    kafka::verify_topic_partitions(&config.kafka)?;
+
     // Initialize db connection pool and migrate database
     let connection_pool = Arc::new(pool::init(&config.database).await?);
     db::migrate(connection_pool.clone()).await?;
 
+    // Look up the ids of the schemas, falls back to the persisted ids if the
+    // schema registry isn't available
+    let schema_cache = Arc::new(kafka::init_schema_cache(&config.kafka).await?);
+
     // Initialize user schema encoder
     let user_event_converter: Arc<DynEventConverter> =
-        Arc::new(Box::new(UserEventEncoder::new(&config.kafka)?));
+        Arc::new(Box::new(UserEventEncoder::new(&config.kafka, schema_cache)));
 
     // Initialize event_dispatcher
     let event_dispatcher = EventDispatcher::new(vec![user_event_converter]);
@@ -0,0 +1,60 @@
+use common_db_relationaldb::notify::EVENT_NOTIFY_CHANNEL;
+use sea_orm::ConnectionTrait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221015_add_event_notify_trigger"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Notify the kafka connector about inserted events. Notifications are
+        // only sent when the transaction is committed.
+        execute(
+            manager,
+            &format!(
+                r#"CREATE OR REPLACE FUNCTION notify_event_entity() RETURNS trigger AS $$
+            BEGIN
+                PERFORM pg_notify('{}', NEW.id::text);
+                RETURN NEW;
+            END;
+            $$ LANGUAGE plpgsql"#,
+                EVENT_NOTIFY_CHANNEL
+            ),
+        )
+        .await?;
+        execute(
+            manager,
+            "CREATE TRIGGER event_entity_notify AFTER INSERT ON event_entity FOR EACH ROW EXECUTE \
+             FUNCTION notify_event_entity()",
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop trigger and function
+        execute(
+            manager,
+            "DROP TRIGGER IF EXISTS event_entity_notify ON event_entity",
+        )
+        .await?;
+        execute(manager, "DROP FUNCTION IF EXISTS notify_event_entity()").await
+    }
+}
+
+async fn execute<'m>(manager: &'m SchemaManager<'m>, sql: &str) -> Result<(), DbErr> {
+    manager
+        .get_connection()
+        .execute(Statement::from_string(
+            manager.get_database_backend(),
+            sql.to_owned(),
+        ))
+        .await?;
+    Ok(())
+}
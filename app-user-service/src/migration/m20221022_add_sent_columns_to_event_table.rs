@@ -0,0 +1,158 @@
+use sea_orm::ConnectionTrait;
+use sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+use crate::event::model::event;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221022_add_sent_columns_to_event_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add columns to find the events of an aggregate and to mark events as
+        // sent by the kafka connector
+        for mut column in sent_columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(event::Entity)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // The kafka connector only reads events that weren't sent yet
+        execute(
+            manager,
+            "CREATE INDEX event_entity_unsent_idx ON event_entity (id) WHERE sent_at IS NULL",
+        )
+        .await?;
+        create_aggregate_id_index(manager, event::Entity, "event_entity_aggregate_id_idx").await?;
+
+        create_event_archive_table(manager).await?;
+        create_aggregate_id_index(
+            manager,
+            Alias::new("event_archive_entity"),
+            "event_archive_entity_aggregate_id_idx",
+        )
+        .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop event archive table
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(Alias::new("event_archive_entity"))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Drop indexes
+        execute(manager, "DROP INDEX IF EXISTS event_entity_unsent_idx").await?;
+        execute(
+            manager,
+            "DROP INDEX IF EXISTS event_entity_aggregate_id_idx",
+        )
+        .await?;
+
+        // Drop columns
+        for column in [
+            event::Column::AggregateId,
+            event::Column::EventType,
+            event::Column::SentAt,
+            event::Column::KafkaOffset,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(event::Entity)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn sent_columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef::new(event::Column::AggregateId)
+            .string()
+            .to_owned(),
+        ColumnDef::new(event::Column::EventType).string().to_owned(),
+        ColumnDef::new(event::Column::SentAt)
+            .timestamp_with_time_zone()
+            .to_owned(),
+        ColumnDef::new(event::Column::KafkaOffset)
+            .big_integer()
+            .to_owned(),
+    ]
+}
+
+/// Table with the sent events after their retention period (see the `retention`
+/// configuration of the kafka connector).
+async fn create_event_archive_table<'m>(manager: &'m SchemaManager<'m>) -> Result<(), DbErr> {
+    let mut table = Table::create();
+    table
+        .table(Alias::new("event_archive_entity"))
+        .if_not_exists()
+        .col(
+            ColumnDef::new(event::Column::Id)
+                .primary_key()
+                .integer()
+                .not_null(),
+        )
+        .col(ColumnDef::new(event::Column::Topic).string().not_null())
+        .col(
+            ColumnDef::new(event::Column::Partition)
+                .integer()
+                .not_null(),
+        )
+        .col(ColumnDef::new(event::Column::Key).binary().not_null())
+        .col(ColumnDef::new(event::Column::Payload).binary().not_null())
+        .col(ColumnDef::new(event::Column::TraceId).string());
+    for mut column in sent_columns() {
+        table.col(&mut column);
+    }
+
+    manager.create_table(table.to_owned()).await
+}
+
+async fn create_aggregate_id_index<'m, T>(
+    manager: &'m SchemaManager<'m>,
+    table: T,
+    name: &str,
+) -> Result<(), DbErr>
+where
+    T: IntoIden,
+{
+    manager
+        .create_index(
+            Index::create()
+                .name(name)
+                .table(table)
+                .col(event::Column::AggregateId)
+                .to_owned(),
+        )
+        .await
+}
+
+async fn execute<'m>(manager: &'m SchemaManager<'m>, sql: &str) -> Result<(), DbErr> {
+    manager
+        .get_connection()
+        .execute(Statement::from_string(
+            manager.get_database_backend(),
+            sql.to_owned(),
+        ))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+use crate::event::model::event;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221029_add_created_at_to_event_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add created_at column to measure the lag of the kafka connector
+        for table in [
+            event::Entity.into_iden(),
+            Alias::new("event_archive_entity").into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .add_column(
+                            ColumnDef::new(event::Column::CreatedAt)
+                                .timestamp_with_time_zone()
+                                .not_null()
+                                .extra("DEFAULT now()".to_owned()),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Drop created_at column
+        for table in [
+            event::Entity.into_iden(),
+            Alias::new("event_archive_entity").into_iden(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .drop_column(event::Column::CreatedAt)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
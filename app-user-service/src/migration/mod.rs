@@ -5,6 +5,9 @@ use sea_orm_migration::MigratorTrait;
 mod m20220403_initial_migration;
 mod m20220703_add_event_table;
 mod m20220805_add_trace_id_to_event_table;
+mod m20221015_add_event_notify_trigger;
+mod m20221022_add_sent_columns_to_event_table;
+mod m20221029_add_created_at_to_event_table;
 
 #[derive(Clone, Debug, Eq, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "seaql_migrations")]
@@ -28,6 +31,9 @@ impl MigratorTrait for Migrator {
             Box::new(m20220403_initial_migration::Migration),
             Box::new(m20220703_add_event_table::Migration),
             Box::new(m20220805_add_trace_id_to_event_table::Migration),
+            Box::new(m20221015_add_event_notify_trigger::Migration),
+            Box::new(m20221022_add_sent_columns_to_event_table::Migration),
+            Box::new(m20221029_add_created_at_to_event_table::Migration),
         ]
     }
 }
@@ -1,8 +1,9 @@
 use async_graphql::MergedObject;
 
+use crate::user::api::graphql::mutation::types::phone_number::PhoneNumberInput;
 use crate::user::api::graphql::mutation::types::user::UserInput;
 
 pub mod types;
 
 #[derive(MergedObject, Default)]
-pub struct Mutation(UserInput);
+pub struct Mutation(UserInput, PhoneNumberInput);
@@ -1 +1,2 @@
+pub mod phone_number;
 pub mod user;
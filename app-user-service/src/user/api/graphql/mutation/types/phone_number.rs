@@ -0,0 +1,175 @@
+use async_graphql::Context;
+use async_graphql::InputObject;
+use async_graphql::Object;
+use common_db_relationaldb::transaction::transactional;
+use common_error::AppError;
+use common_security::authentication::DynAuthenticationHolder;
+use futures::FutureExt;
+use sea_orm::ActiveValue::NotSet;
+use sea_orm::ActiveValue::Set;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::user::api::add_phone_number_and_create_events;
+use crate::user::api::graphql::query::types::user::UserPayload;
+use crate::user::api::graphql::shared::types::PhoneNumberType;
+use crate::user::api::remove_phone_number_and_create_events;
+use crate::user::api::update_phone_number_and_create_events;
+use crate::user::api::PhoneNumberUpdate;
+use crate::user::model::phone_number;
+use crate::DynContext;
+
+#[derive(Default)]
+pub struct PhoneNumberInput;
+
+/// Phone numbers of an user.
+#[Object]
+impl PhoneNumberInput {
+    #[instrument(name = "phone_number_input.add_phone_number", skip_all)]
+    pub async fn add_phone_number(
+        &self,
+        ctx: &Context<'_>,
+        input: AddPhoneNumberInput,
+    ) -> Result<UserPayload, AppError> {
+        // Check authentication
+        ctx.data_unchecked::<DynAuthenticationHolder>()
+            .user_authenticated()?;
+
+        // Get Context
+        let context = ctx.data_unchecked::<DynContext>();
+
+        // Start transaction and add phone number
+        let updated_user = transactional(context.db_connection(), |db_connection| {
+            let event_dispatcher = context.event_dispatcher();
+            let input = input.clone();
+
+            async move {
+                add_phone_number_and_create_events(
+                    db_connection,
+                    event_dispatcher,
+                    input.user_id,
+                    input.version,
+                    input.into(),
+                )
+                .await
+            }
+            .boxed()
+        })
+        .await?;
+
+        Ok(UserPayload(updated_user))
+    }
+
+    #[instrument(name = "phone_number_input.update_phone_number", skip_all)]
+    pub async fn update_phone_number(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdatePhoneNumberInput,
+    ) -> Result<UserPayload, AppError> {
+        // Check authentication
+        ctx.data_unchecked::<DynAuthenticationHolder>()
+            .user_authenticated()?;
+
+        // Get Context
+        let context = ctx.data_unchecked::<DynContext>();
+
+        // Start transaction and update phone number
+        let updated_user = transactional(context.db_connection(), |db_connection| {
+            let event_dispatcher = context.event_dispatcher();
+            let update = input.clone();
+
+            async move {
+                update_phone_number_and_create_events(
+                    db_connection,
+                    event_dispatcher,
+                    update.user_id,
+                    update.version,
+                    update.id,
+                    update.into(),
+                )
+                .await
+            }
+            .boxed()
+        })
+        .await?;
+
+        Ok(UserPayload(updated_user))
+    }
+
+    #[instrument(name = "phone_number_input.remove_phone_number", skip_all)]
+    pub async fn remove_phone_number(
+        &self,
+        ctx: &Context<'_>,
+        user_id: Uuid,
+        version: i64,
+        phone_number_id: i64,
+    ) -> Result<UserPayload, AppError> {
+        // Check authentication
+        ctx.data_unchecked::<DynAuthenticationHolder>()
+            .user_authenticated()?;
+
+        // Get Context
+        let context = ctx.data_unchecked::<DynContext>();
+
+        // Start transaction and remove phone number
+        let updated_user = transactional(context.db_connection(), |db_connection| {
+            let event_dispatcher = context.event_dispatcher();
+
+            async move {
+                remove_phone_number_and_create_events(
+                    db_connection,
+                    event_dispatcher,
+                    user_id,
+                    version,
+                    phone_number_id,
+                )
+                .await
+            }
+            .boxed()
+        })
+        .await?;
+
+        Ok(UserPayload(updated_user))
+    }
+}
+
+#[derive(Clone, InputObject)]
+pub struct AddPhoneNumberInput {
+    user_id: Uuid,
+    version: i64,
+    country_code: String,
+    phone_number_type: PhoneNumberType,
+    call_number: String,
+}
+
+impl From<AddPhoneNumberInput> for phone_number::ActiveModel {
+    fn from(input: AddPhoneNumberInput) -> Self {
+        phone_number::ActiveModel {
+            id: NotSet,
+            user_id: NotSet,
+            country_code: Set(input.country_code),
+            phone_number_type: Set(input.phone_number_type.into()),
+            call_number: Set(input.call_number),
+        }
+    }
+}
+
+#[derive(Clone, InputObject)]
+pub struct UpdatePhoneNumberInput {
+    id: i64,
+    user_id: Uuid,
+    version: i64,
+    country_code: Option<String>,
+    phone_number_type: Option<PhoneNumberType>,
+    call_number: Option<String>,
+}
+
+impl From<UpdatePhoneNumberInput> for PhoneNumberUpdate {
+    fn from(input: UpdatePhoneNumberInput) -> Self {
+        PhoneNumberUpdate {
+            country_code: input.country_code,
+            phone_number_type: input.phone_number_type.map(|p| p.into()),
+            call_number: input.call_number,
+        }
+    }
+}
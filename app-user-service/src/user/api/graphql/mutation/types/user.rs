@@ -12,8 +12,11 @@ use uuid::Uuid;
 
 use crate::event::service::dto::SerializableEventDto;
 use crate::user::api::create_kafka_events;
+use crate::user::api::delete_user_and_create_events;
 use crate::user::api::graphql::query::types::user::UserPayload;
 use crate::user::api::graphql::shared::types::CountryCode;
+use crate::user::api::update_user_and_create_events;
+use crate::user::api::UserUpdate;
 use crate::user::event::dto::UserWithPhoneNumbersDto;
 use crate::user::model::user;
 use crate::user::service::user_service::create_user;
@@ -71,6 +74,70 @@ impl UserInput {
 
         Ok(UserPayload(saved_user))
     }
+
+    #[instrument(name = "user_input.update_user", skip_all)]
+    pub async fn update_user(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateUserInput,
+    ) -> Result<UserPayload, AppError> {
+        // Check authentication
+        ctx.data_unchecked::<DynAuthenticationHolder>()
+            .user_authenticated()?;
+
+        // Get Context
+        let context = ctx.data_unchecked::<DynContext>();
+
+        // Start transaction and update user
+        let updated_user = transactional(context.db_connection(), |db_connection| {
+            let event_dispatcher = context.event_dispatcher();
+            let update = input.clone();
+
+            async move {
+                update_user_and_create_events(
+                    db_connection,
+                    event_dispatcher,
+                    update.id,
+                    update.version,
+                    update.into(),
+                )
+                .await
+            }
+            .boxed()
+        })
+        .await?;
+
+        Ok(UserPayload(updated_user))
+    }
+
+    #[instrument(name = "user_input.delete_user", skip_all)]
+    pub async fn delete_user(
+        &self,
+        ctx: &Context<'_>,
+        user_id: Uuid,
+        version: i64,
+    ) -> Result<bool, AppError> {
+        // Check authentication
+        ctx.data_unchecked::<DynAuthenticationHolder>()
+            .user_authenticated()?;
+
+        // Get Context
+        let context = ctx.data_unchecked::<DynContext>();
+
+        // Start transaction and delete user
+        let deleted_user = transactional(context.db_connection(), |db_connection| {
+            let event_dispatcher = context.event_dispatcher();
+
+            async move {
+                delete_user_and_create_events(db_connection, event_dispatcher, user_id, version)
+                    .await
+            }
+            .boxed()
+        })
+        .await?;
+
+        Ok(deleted_user)
+    }
 }
 
 #[derive(Clone, InputObject)]
@@ -92,3 +159,22 @@ impl From<AddUserInput> for user::ActiveModel {
         }
     }
 }
+
+#[derive(Clone, InputObject)]
+pub struct UpdateUserInput {
+    id: Uuid,
+    version: i64,
+    name: Option<String>,
+    email: Option<String>,
+    country: Option<CountryCode>,
+}
+
+impl From<UpdateUserInput> for UserUpdate {
+    fn from(input: UpdateUserInput) -> Self {
+        UserUpdate {
+            name: input.name,
+            email: input.email,
+            country: input.country.map(|c| c.into()),
+        }
+    }
+}
@@ -9,6 +9,11 @@ pub struct PhoneNumberPayload(pub PhoneNumberUserIdentifierProjection);
 
 #[Object]
 impl PhoneNumberPayload {
+    /// Technical identifier of the phone number
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
     /// Country code
     async fn country_code(&self) -> String {
         self.0.country_code.clone()
@@ -1,15 +1,44 @@
 use std::sync::Arc;
 
 use common_error::AppError;
+use common_error::DbError;
+use kafka_schema_user::schema_delete_user::SCHEMA_NAME_DELETE_USER;
+use kafka_schema_user::schema_update_user::SCHEMA_NAME_UPDATE_USER;
+use sea_orm::ActiveValue::Set;
 use sea_orm::DatabaseTransaction;
+use uuid::Uuid;
 
+use crate::common::model::IsoCountryCodeEnum;
 use crate::event::service::dto::SerializableEventDto;
 use crate::event::service::event_dispatcher::EventDispatcher;
 use crate::event::service::event_service;
+use crate::user::event::dto::UserWithPhoneNumbersDto;
+use crate::user::model::phone_number;
+use crate::user::model::phone_number::PhoneNumberTypeEnum;
+use crate::user::model::user;
+use crate::user::service::phone_number_service;
+use crate::user::service::user_service;
 
 pub mod graphql;
 pub mod rest;
 
+/// Changes to apply to an existing user. Properties without a value are kept.
+#[derive(Clone, Debug, Default)]
+pub struct UserUpdate {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub country: Option<IsoCountryCodeEnum>,
+}
+
+/// Changes to apply to an existing phone number. Properties without a value
+/// are kept.
+#[derive(Clone, Debug, Default)]
+pub struct PhoneNumberUpdate {
+    pub country_code: Option<String>,
+    pub phone_number_type: Option<PhoneNumberTypeEnum>,
+    pub call_number: Option<String>,
+}
+
 async fn create_kafka_events(
     db_connection: &DatabaseTransaction,
     event_dispatcher: Arc<EventDispatcher>,
@@ -27,3 +56,239 @@ async fn create_kafka_events(
     }
     Ok(())
 }
+
+/// Creates the kafka events for a changed user including its current phone
+/// numbers.
+async fn create_user_changed_events(
+    db_connection: &DatabaseTransaction,
+    event_dispatcher: Arc<EventDispatcher>,
+    user: user::Model,
+    event_type: &str,
+) -> Result<(), AppError> {
+    let phone_numbers: Vec<phone_number::ActiveModel> =
+        phone_number_service::find_all_by_user_id(db_connection, user.id)
+            .await?
+            .into_iter()
+            .map(|p| p.into())
+            .collect();
+
+    let dto: Box<dyn SerializableEventDto> = Box::new(UserWithPhoneNumbersDto {
+        user,
+        phone_numbers: Some(phone_numbers),
+    });
+
+    create_kafka_events(db_connection, event_dispatcher, dto, event_type).await
+}
+
+async fn update_user_and_create_events(
+    db_connection: &DatabaseTransaction,
+    event_dispatcher: Arc<EventDispatcher>,
+    identifier: Uuid,
+    version: i64,
+    update: UserUpdate,
+) -> Result<user::Model, AppError> {
+    let mut user = find_user_with_version(db_connection, identifier, version).await?;
+
+    // Apply changes
+    if let Some(name) = update.name {
+        user.name = name;
+    }
+    if let Some(email) = update.email {
+        user.email = email;
+    }
+    if let Some(country) = update.country {
+        user.country = country;
+    }
+
+    // Save entity to database
+    let user = increment_version(db_connection, user).await?;
+
+    // Create kafka events
+    create_user_changed_events(
+        db_connection,
+        event_dispatcher,
+        user.clone(),
+        SCHEMA_NAME_UPDATE_USER,
+    )
+    .await?;
+
+    Ok(user)
+}
+
+async fn add_phone_number_and_create_events(
+    db_connection: &DatabaseTransaction,
+    event_dispatcher: Arc<EventDispatcher>,
+    identifier: Uuid,
+    version: i64,
+    mut phone_number: phone_number::ActiveModel,
+) -> Result<user::Model, AppError> {
+    let user = find_user_with_version(db_connection, identifier, version).await?;
+
+    // Save phone number in database
+    phone_number.user_id = Set(user.id);
+    phone_number_service::save(db_connection, vec![phone_number]).await?;
+
+    // Increment version of the user
+    let user = increment_version(db_connection, user).await?;
+
+    // Create kafka events
+    create_user_changed_events(
+        db_connection,
+        event_dispatcher,
+        user.clone(),
+        SCHEMA_NAME_UPDATE_USER,
+    )
+    .await?;
+
+    Ok(user)
+}
+
+async fn update_phone_number_and_create_events(
+    db_connection: &DatabaseTransaction,
+    event_dispatcher: Arc<EventDispatcher>,
+    identifier: Uuid,
+    version: i64,
+    phone_number_id: i64,
+    update: PhoneNumberUpdate,
+) -> Result<user::Model, AppError> {
+    let user = find_user_with_version(db_connection, identifier, version).await?;
+
+    let phone_number =
+        phone_number_service::find_one_by_id_and_user_id(db_connection, phone_number_id, user.id)
+            .await?;
+
+    if let Some(phone_number) = phone_number {
+        // Apply changes
+        let mut phone_number: phone_number::ActiveModel = phone_number.into();
+        if let Some(country_code) = update.country_code {
+            phone_number.country_code = Set(country_code);
+        }
+        if let Some(phone_number_type) = update.phone_number_type {
+            phone_number.phone_number_type = Set(phone_number_type);
+        }
+        if let Some(call_number) = update.call_number {
+            phone_number.call_number = Set(call_number);
+        }
+
+        // Save phone number in database
+        phone_number_service::update(db_connection, phone_number).await?;
+    } else {
+        return Err(AppError::DbError(DbError::NotFound));
+    }
+
+    // Increment version of the user
+    let user = increment_version(db_connection, user).await?;
+
+    // Create kafka events
+    create_user_changed_events(
+        db_connection,
+        event_dispatcher,
+        user.clone(),
+        SCHEMA_NAME_UPDATE_USER,
+    )
+    .await?;
+
+    Ok(user)
+}
+
+async fn remove_phone_number_and_create_events(
+    db_connection: &DatabaseTransaction,
+    event_dispatcher: Arc<EventDispatcher>,
+    identifier: Uuid,
+    version: i64,
+    phone_number_id: i64,
+) -> Result<user::Model, AppError> {
+    let user = find_user_with_version(db_connection, identifier, version).await?;
+
+    // Delete phone number from database
+    let deleted = phone_number_service::delete(db_connection, phone_number_id, user.id).await?;
+    if deleted == 0 {
+        return Err(AppError::DbError(DbError::NotFound));
+    }
+
+    // Increment version of the user
+    let user = increment_version(db_connection, user).await?;
+
+    // Create kafka events
+    create_user_changed_events(
+        db_connection,
+        event_dispatcher,
+        user.clone(),
+        SCHEMA_NAME_UPDATE_USER,
+    )
+    .await?;
+
+    Ok(user)
+}
+
+/// Deletes the user with its phone numbers. Returns false if the user doesn't
+/// exist.
+async fn delete_user_and_create_events(
+    db_connection: &DatabaseTransaction,
+    event_dispatcher: Arc<EventDispatcher>,
+    identifier: Uuid,
+    version: i64,
+) -> Result<bool, AppError> {
+    let user = match user_service::find_one_by_identifier(db_connection, identifier).await? {
+        Some(user) => user,
+        None => return Ok(false),
+    };
+
+    // Check version
+    if user.version != version {
+        return Err(AppError::DbError(DbError::Conflict));
+    }
+
+    // Delete from database
+    phone_number_service::delete_all_by_user_id(db_connection, user.id).await?;
+    user_service::delete_user(db_connection, &user).await?;
+
+    // Create kafka events. The version is incremented to let consumers order the
+    // deletion after the last update.
+    let mut deleted_user = user;
+    deleted_user.version += 1;
+
+    let dto: Box<dyn SerializableEventDto> = Box::new(UserWithPhoneNumbersDto {
+        user: deleted_user,
+        phone_numbers: None,
+    });
+    create_kafka_events(
+        db_connection,
+        event_dispatcher,
+        dto,
+        SCHEMA_NAME_DELETE_USER,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Loads the user and checks that the given version is the current one.
+async fn find_user_with_version(
+    db_connection: &DatabaseTransaction,
+    identifier: Uuid,
+    version: i64,
+) -> Result<user::Model, AppError> {
+    let user = match user_service::find_one_by_identifier(db_connection, identifier).await? {
+        Some(user) => user,
+        None => return Err(AppError::DbError(DbError::NotFound)),
+    };
+
+    // Check version
+    if user.version != version {
+        return Err(AppError::DbError(DbError::Conflict));
+    }
+
+    Ok(user)
+}
+
+/// Saves the user with the next version.
+async fn increment_version(
+    db_connection: &DatabaseTransaction,
+    mut user: user::Model,
+) -> Result<user::Model, AppError> {
+    let expected_version = user.version;
+    user.version += 1;
+    user_service::update_user(db_connection, &user, expected_version).await?;
+    Ok(user)
+}
@@ -1,6 +1,7 @@
 use axum::extract::Extension;
 use axum::extract::Path;
 use axum::extract::Query;
+use axum::http::StatusCode;
 use axum::Json;
 use common_db_relationaldb::transaction::transactional;
 use common_error::AppError;
@@ -16,13 +17,22 @@ use crate::common::context::DynContext;
 use crate::common::paging::Page;
 use crate::common::paging::PageParams;
 use crate::event::service::dto::SerializableEventDto;
+use crate::user::api::add_phone_number_and_create_events;
 use crate::user::api::create_kafka_events;
+use crate::user::api::delete_user_and_create_events;
+use crate::user::api::remove_phone_number_and_create_events;
 use crate::user::api::rest::resources::request::create_user_resource::CreateUserResource;
+use crate::user::api::rest::resources::request::patch_user_resource::PatchUserResource;
+use crate::user::api::rest::resources::request::save_phone_number_resource::SavePhoneNumberResource;
+use crate::user::api::rest::resources::request::update_user_resource::UpdateUserResource;
+use crate::user::api::rest::resources::request::version_params::VersionParams;
 use crate::user::api::rest::resources::response::build_user_resource;
 use crate::user::api::rest::resources::response::build_user_resource_page_from_page;
 use crate::user::api::rest::resources::response::build_user_resource_page_from_vec;
 use crate::user::api::rest::resources::response::build_user_resources;
 use crate::user::api::rest::resources::response::user_resource::UserResource;
+use crate::user::api::update_phone_number_and_create_events;
+use crate::user::api::update_user_and_create_events;
 use crate::user::event::dto::UserWithPhoneNumbersDto;
 use crate::user::model::phone_number;
 use crate::user::model::user;
@@ -85,6 +95,180 @@ pub async fn create_user<'a>(
     .await
 }
 
+#[instrument(name = "user.api.update_user", skip_all)]
+pub async fn update_user(
+    Path(identifier): Path<Uuid>,
+    Json(update_user_resource): Json<UpdateUserResource>,
+    Extension(context): Extension<DynContext>,
+    Extension(authentication): Extension<DynAuthenticationHolder>,
+) -> Result<Json<UserResource>, AppError> {
+    authentication.user_authenticated()?;
+    transactional(context.db_connection(), |db_connection| {
+        let resource = update_user_resource.clone();
+        let event_dispatcher = context.event_dispatcher();
+
+        async move {
+            let user = update_user_and_create_events(
+                db_connection,
+                event_dispatcher,
+                identifier,
+                resource.version,
+                resource.into(),
+            )
+            .await?;
+
+            Ok(build_user_resource(db_connection, user).await?.into())
+        }
+        .boxed()
+    })
+    .await
+}
+
+#[instrument(name = "user.api.patch_user", skip_all)]
+pub async fn patch_user(
+    Path(identifier): Path<Uuid>,
+    Json(patch_user_resource): Json<PatchUserResource>,
+    Extension(context): Extension<DynContext>,
+    Extension(authentication): Extension<DynAuthenticationHolder>,
+) -> Result<Json<UserResource>, AppError> {
+    authentication.user_authenticated()?;
+    transactional(context.db_connection(), |db_connection| {
+        let resource = patch_user_resource.clone();
+        let event_dispatcher = context.event_dispatcher();
+
+        async move {
+            let user = update_user_and_create_events(
+                db_connection,
+                event_dispatcher,
+                identifier,
+                resource.version,
+                resource.into(),
+            )
+            .await?;
+
+            Ok(build_user_resource(db_connection, user).await?.into())
+        }
+        .boxed()
+    })
+    .await
+}
+
+#[instrument(name = "user.api.delete_user", skip(context, authentication))]
+pub async fn delete_user(
+    Path(identifier): Path<Uuid>,
+    Query(version_params): Query<VersionParams>,
+    Extension(context): Extension<DynContext>,
+    Extension(authentication): Extension<DynAuthenticationHolder>,
+) -> Result<StatusCode, AppError> {
+    authentication.user_authenticated()?;
+    transactional(context.db_connection(), |db_connection| {
+        let version = version_params.version;
+        let event_dispatcher = context.event_dispatcher();
+
+        async move {
+            let deleted =
+                delete_user_and_create_events(db_connection, event_dispatcher, identifier, version)
+                    .await?;
+
+            if deleted {
+                Ok(StatusCode::NO_CONTENT)
+            } else {
+                Err(AppError::DbError(DbError::NotFound))
+            }
+        }
+        .boxed()
+    })
+    .await
+}
+
+#[instrument(name = "user.api.add_phone_number", skip_all)]
+pub async fn add_phone_number(
+    Path(identifier): Path<Uuid>,
+    Json(save_phone_number_resource): Json<SavePhoneNumberResource>,
+    Extension(context): Extension<DynContext>,
+    Extension(authentication): Extension<DynAuthenticationHolder>,
+) -> Result<Json<UserResource>, AppError> {
+    authentication.user_authenticated()?;
+    transactional(context.db_connection(), |db_connection| {
+        let resource = save_phone_number_resource.clone();
+        let event_dispatcher = context.event_dispatcher();
+
+        async move {
+            let user = add_phone_number_and_create_events(
+                db_connection,
+                event_dispatcher,
+                identifier,
+                resource.version,
+                resource.into(),
+            )
+            .await?;
+
+            Ok(build_user_resource(db_connection, user).await?.into())
+        }
+        .boxed()
+    })
+    .await
+}
+
+#[instrument(name = "user.api.update_phone_number", skip_all)]
+pub async fn update_phone_number(
+    Path((identifier, phone_number_id)): Path<(Uuid, i64)>,
+    Json(save_phone_number_resource): Json<SavePhoneNumberResource>,
+    Extension(context): Extension<DynContext>,
+    Extension(authentication): Extension<DynAuthenticationHolder>,
+) -> Result<Json<UserResource>, AppError> {
+    authentication.user_authenticated()?;
+    transactional(context.db_connection(), |db_connection| {
+        let resource = save_phone_number_resource.clone();
+        let event_dispatcher = context.event_dispatcher();
+
+        async move {
+            let user = update_phone_number_and_create_events(
+                db_connection,
+                event_dispatcher,
+                identifier,
+                resource.version,
+                phone_number_id,
+                resource.into(),
+            )
+            .await?;
+
+            Ok(build_user_resource(db_connection, user).await?.into())
+        }
+        .boxed()
+    })
+    .await
+}
+
+#[instrument(name = "user.api.remove_phone_number", skip(context, authentication))]
+pub async fn remove_phone_number(
+    Path((identifier, phone_number_id)): Path<(Uuid, i64)>,
+    Query(version_params): Query<VersionParams>,
+    Extension(context): Extension<DynContext>,
+    Extension(authentication): Extension<DynAuthenticationHolder>,
+) -> Result<Json<UserResource>, AppError> {
+    authentication.user_authenticated()?;
+    transactional(context.db_connection(), |db_connection| {
+        let version = version_params.version;
+        let event_dispatcher = context.event_dispatcher();
+
+        async move {
+            let user = remove_phone_number_and_create_events(
+                db_connection,
+                event_dispatcher,
+                identifier,
+                version,
+                phone_number_id,
+            )
+            .await?;
+
+            Ok(build_user_resource(db_connection, user).await?.into())
+        }
+        .boxed()
+    })
+    .await
+}
+
 #[instrument(
     name = "user.api.find_one_by_identifier",
     skip(context, authentication)
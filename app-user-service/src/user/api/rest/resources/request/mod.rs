@@ -1,2 +1,6 @@
 pub mod create_phone_number_resource;
 pub mod create_user_resource;
+pub mod patch_user_resource;
+pub mod save_phone_number_resource;
+pub mod update_user_resource;
+pub mod version_params;
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+use crate::common::model::IsoCountryCodeEnum;
+use crate::user::api::UserUpdate;
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchUserResource {
+    pub version: i64,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub country: Option<IsoCountryCodeEnum>,
+}
+
+impl From<PatchUserResource> for UserUpdate {
+    fn from(r: PatchUserResource) -> UserUpdate {
+        UserUpdate {
+            name: r.name,
+            email: r.email,
+            country: r.country,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use sea_orm::ActiveValue;
+use sea_orm::ActiveValue::NotSet;
+use serde::Deserialize;
+
+use crate::user::api::PhoneNumberUpdate;
+use crate::user::model::phone_number;
+use crate::user::model::phone_number::PhoneNumberTypeEnum;
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavePhoneNumberResource {
+    pub version: i64,
+    pub country_code: String,
+    pub phone_number_type: PhoneNumberTypeEnum,
+    pub call_number: String,
+}
+
+impl From<SavePhoneNumberResource> for phone_number::ActiveModel {
+    fn from(resource: SavePhoneNumberResource) -> Self {
+        phone_number::ActiveModel {
+            id: NotSet,
+            user_id: NotSet,
+            country_code: ActiveValue::set(resource.country_code),
+            phone_number_type: ActiveValue::set(resource.phone_number_type),
+            call_number: ActiveValue::set(resource.call_number),
+        }
+    }
+}
+
+impl From<SavePhoneNumberResource> for PhoneNumberUpdate {
+    fn from(resource: SavePhoneNumberResource) -> Self {
+        PhoneNumberUpdate {
+            country_code: Some(resource.country_code),
+            phone_number_type: Some(resource.phone_number_type),
+            call_number: Some(resource.call_number),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+use crate::common::model::IsoCountryCodeEnum;
+use crate::user::api::UserUpdate;
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateUserResource {
+    pub version: i64,
+    pub name: String,
+    pub email: String,
+    pub country: IsoCountryCodeEnum,
+}
+
+impl From<UpdateUserResource> for UserUpdate {
+    fn from(r: UpdateUserResource) -> UserUpdate {
+        UserUpdate {
+            name: Some(r.name),
+            email: Some(r.email),
+            country: Some(r.country),
+        }
+    }
+}
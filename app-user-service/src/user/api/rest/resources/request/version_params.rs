@@ -0,0 +1,6 @@
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+pub struct VersionParams {
+    pub version: i64,
+}
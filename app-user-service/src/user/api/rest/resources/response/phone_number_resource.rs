@@ -6,6 +6,7 @@ use crate::user::model::projections::PhoneNumberUserIdentifierProjection;
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PhoneNumberResource {
+    pub id: i64,
     pub country_code: String,
     pub phone_number_type: PhoneNumberTypeEnum,
     pub call_number: String,
@@ -14,6 +15,7 @@ pub struct PhoneNumberResource {
 impl From<PhoneNumberUserIdentifierProjection> for PhoneNumberResource {
     fn from(p: PhoneNumberUserIdentifierProjection) -> Self {
         PhoneNumberResource {
+            id: p.id,
             country_code: p.country_code,
             phone_number_type: p.phone_number_type,
             call_number: p.call_number,
@@ -1,16 +1,37 @@
+use axum::routing::delete;
 use axum::routing::get;
+use axum::routing::patch;
 use axum::routing::post;
+use axum::routing::put;
 use axum::Router;
 
+use crate::user::api::rest::endpoints::add_phone_number;
 use crate::user::api::rest::endpoints::create_user;
+use crate::user::api::rest::endpoints::delete_user;
 use crate::user::api::rest::endpoints::find_all;
 use crate::user::api::rest::endpoints::find_all_by_identifiers;
 use crate::user::api::rest::endpoints::find_one_by_identifier;
+use crate::user::api::rest::endpoints::patch_user;
+use crate::user::api::rest::endpoints::remove_phone_number;
+use crate::user::api::rest::endpoints::update_phone_number;
+use crate::user::api::rest::endpoints::update_user;
 
 pub fn init() -> Router {
     Router::new()
         .route("/users", get(find_all))
         .route("/users", post(create_user))
         .route("/users/:identifier", get(find_one_by_identifier))
+        .route("/users/:identifier", put(update_user))
+        .route("/users/:identifier", patch(patch_user))
+        .route("/users/:identifier", delete(delete_user))
+        .route("/users/:identifier/phone-numbers", post(add_phone_number))
+        .route(
+            "/users/:identifier/phone-numbers/:phone_number_id",
+            put(update_phone_number),
+        )
+        .route(
+            "/users/:identifier/phone-numbers/:phone_number_id",
+            delete(remove_phone_number),
+        )
         .route("/users/search", post(find_all_by_identifiers))
 }
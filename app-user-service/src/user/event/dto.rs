@@ -1,7 +1,9 @@
 use std::any::Any;
 
+use kafka_schema_common::IsoCountryCodeEnumAvro;
 use kafka_schema_user::schema_create_user::CreateUserAvro;
-use kafka_schema_user::IsoCountryCodeEnumAvro;
+use kafka_schema_user::schema_delete_user::DeleteUserAvro;
+use kafka_schema_user::schema_update_user::UpdateUserAvro;
 use kafka_schema_user::PhoneNumberAvro;
 use kafka_schema_user::PhoneNumberTypeEnumAvro;
 
@@ -44,13 +46,40 @@ impl From<UserWithPhoneNumbersDto> for CreateUserAvro {
             name: dto.user.name,
             email: dto.user.email,
             country: match dto.user.country {
-                IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::DE,
-                IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::US,
+                IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::De,
+                IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::Us,
             },
             phone_numbers: match dto.phone_numbers {
                 Some(phone_numbers) => phone_numbers.into_iter().map(|p| p.into()).collect(),
                 None => Vec::with_capacity(0),
             },
+            locale: None,
+        }
+    }
+}
+
+impl From<UserWithPhoneNumbersDto> for UpdateUserAvro {
+    fn from(dto: UserWithPhoneNumbersDto) -> UpdateUserAvro {
+        UpdateUserAvro {
+            identifier: format!("{}", dto.user.identifier),
+            name: dto.user.name,
+            email: dto.user.email,
+            country: match dto.user.country {
+                IsoCountryCodeEnum::DE => IsoCountryCodeEnumAvro::De,
+                IsoCountryCodeEnum::US => IsoCountryCodeEnumAvro::Us,
+            },
+            phone_numbers: match dto.phone_numbers {
+                Some(phone_numbers) => phone_numbers.into_iter().map(|p| p.into()).collect(),
+                None => Vec::with_capacity(0),
+            },
+        }
+    }
+}
+
+impl From<UserWithPhoneNumbersDto> for DeleteUserAvro {
+    fn from(dto: UserWithPhoneNumbersDto) -> DeleteUserAvro {
+        DeleteUserAvro {
+            identifier: format!("{}", dto.user.identifier),
         }
     }
 }
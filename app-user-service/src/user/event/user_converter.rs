@@ -2,15 +2,19 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use common_error::AppError;
+use common_kafka::encoder::RecordEncoder;
 use common_kafka::partition_of;
+use common_kafka::schema_cache::SchemaCache;
 use kafka_schema_common::schema_key::KeyAvro;
 use kafka_schema_common::schema_key::SCHEMA_NAME_KEY;
 use kafka_schema_common::IdentifierAvro;
 use kafka_schema_user::schema_create_user::CreateUserAvro;
 use kafka_schema_user::schema_create_user::SCHEMA_NAME_CREATE_USER;
+use kafka_schema_user::schema_delete_user::DeleteUserAvro;
+use kafka_schema_user::schema_delete_user::SCHEMA_NAME_DELETE_USER;
+use kafka_schema_user::schema_update_user::UpdateUserAvro;
+use kafka_schema_user::schema_update_user::SCHEMA_NAME_UPDATE_USER;
 use kafka_schema_user::DATA_TYPE_USER;
-use schema_registry_converter::async_impl::avro::AvroEncoder;
-use schema_registry_converter::schema_registry_common::SubjectNameStrategy;
 use tracing::instrument;
 
 use crate::config::configuration::KafkaConfiguration;
@@ -22,24 +26,27 @@ use crate::kafka;
 use crate::user::event::dto::UserWithPhoneNumbersDto;
 
 #[derive(Clone)]
-pub struct UserEventEncoder<'a> {
-    pub(crate) avro_encoder: Arc<AvroEncoder<'a>>,
+pub struct UserEventEncoder {
+    pub(crate) record_encoder: Arc<RecordEncoder>,
     pub(crate) topic_configuration: TopicProperties,
 }
 
-impl<'a> UserEventEncoder<'a> {
-    pub fn new<'b>(config: &'b KafkaConfiguration) -> Result<UserEventEncoder<'a>, AppError> {
-        Ok(UserEventEncoder {
-            avro_encoder: Arc::new(kafka::init_avro_encoder(config)?),
+impl UserEventEncoder {
+    pub fn new(config: &KafkaConfiguration, schema_cache: Arc<SchemaCache>) -> UserEventEncoder {
+        UserEventEncoder {
+            record_encoder: Arc::new(kafka::init_record_encoder(schema_cache)),
             topic_configuration: config.topic.get_mapping("user"),
-        })
+        }
     }
 }
 
 #[async_trait]
-impl<'a> EventConverter for UserEventEncoder<'a> {
+impl EventConverter for UserEventEncoder {
     fn handles(&self, event_type: String) -> bool {
-        matches!(event_type.as_str(), SCHEMA_NAME_CREATE_USER)
+        matches!(
+            event_type.as_str(),
+            SCHEMA_NAME_CREATE_USER | SCHEMA_NAME_UPDATE_USER | SCHEMA_NAME_DELETE_USER
+        )
     }
 
     #[instrument(name = "user_event_converter.handle", skip_all)]
@@ -60,12 +67,22 @@ impl<'a> EventConverter for UserEventEncoder<'a> {
         )
         .expect("Invalid partition number detected");
 
-        // Serialize value
-        let value_sns = SubjectNameStrategy::RecordNameStrategy(event_type.clone());
+        // Serialize value in the format of the topic
+        let format = self.topic_configuration.format;
         let serialized_value: Vec<u8> = if event_type == *SCHEMA_NAME_CREATE_USER {
             let create_user_avro: CreateUserAvro = user_event.clone().into();
-            self.avro_encoder
-                .encode_struct(create_user_avro, &value_sns)
+            self.record_encoder
+                .encode(create_user_avro, &event_type, format)
+                .await?
+        } else if event_type == *SCHEMA_NAME_UPDATE_USER {
+            let update_user_avro: UpdateUserAvro = user_event.clone().into();
+            self.record_encoder
+                .encode(update_user_avro, &event_type, format)
+                .await?
+        } else if event_type == *SCHEMA_NAME_DELETE_USER {
+            let delete_user_avro: DeleteUserAvro = user_event.clone().into();
+            self.record_encoder
+                .encode(delete_user_avro, &event_type, format)
                 .await?
         } else {
             panic!("Unhandled event type: {:?}", event_type);
@@ -80,15 +97,18 @@ impl<'a> EventConverter for UserEventEncoder<'a> {
                 version: user_event.user.version,
             },
         };
-        let key_sns = SubjectNameStrategy::RecordNameStrategy(SCHEMA_NAME_KEY.to_string());
-
-        let serialized_key = self.avro_encoder.encode_struct(key_avro, &key_sns).await?;
+        let serialized_key = self
+            .record_encoder
+            .encode(key_avro, SCHEMA_NAME_KEY, format)
+            .await?;
 
         // Get topic
         let topic = self.topic_configuration.topic_name.clone();
 
         // Return dto with required parameters to send it with kafka
         Ok(EventDto {
+            aggregate_id: format!("{}", user_event.user.identifier),
+            event_type,
             topic,
             partition,
             key: serialized_key,
@@ -11,6 +11,7 @@ use sea_orm::EntityTrait;
 use sea_orm::EnumIter;
 use sea_orm::JoinType;
 use sea_orm::QueryFilter;
+use sea_orm::QueryOrder;
 use sea_orm::QuerySelect;
 use sea_orm::RelationTrait;
 use tracing::instrument;
@@ -35,6 +36,73 @@ pub async fn save(
     Ok(())
 }
 
+#[instrument(name = "phone_number_service.update", skip_all)]
+pub async fn update(
+    db_connection: &DatabaseTransaction,
+    phone_number: phone_number::ActiveModel,
+) -> Result<phone_number::Model, AppError> {
+    Ok(phone_number.update(db_connection).await?)
+}
+
+#[instrument(name = "phone_number_service.delete", skip(db_connection))]
+pub async fn delete(
+    db_connection: &DatabaseTransaction,
+    id: i64,
+    user_id: i64,
+) -> Result<u64, AppError> {
+    let result = PhoneNumberEntity::delete_many()
+        .filter(phone_number::Column::Id.eq(id))
+        .filter(phone_number::Column::UserId.eq(user_id))
+        .exec(db_connection)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
+#[instrument(
+    name = "phone_number_service.find_one_by_id_and_user_id",
+    skip(db_connection)
+)]
+pub async fn find_one_by_id_and_user_id(
+    db_connection: &DatabaseTransaction,
+    id: i64,
+    user_id: i64,
+) -> Result<Option<phone_number::Model>> {
+    Ok(PhoneNumberEntity::find()
+        .filter(phone_number::Column::Id.eq(id))
+        .filter(phone_number::Column::UserId.eq(user_id))
+        .one(db_connection)
+        .await?)
+}
+
+#[instrument(name = "phone_number_service.find_all_by_user_id", skip(db_connection))]
+pub async fn find_all_by_user_id(
+    db_connection: &DatabaseTransaction,
+    user_id: i64,
+) -> Result<Vec<phone_number::Model>> {
+    Ok(PhoneNumberEntity::find()
+        .filter(phone_number::Column::UserId.eq(user_id))
+        .order_by_asc(phone_number::Column::Id)
+        .all(db_connection)
+        .await?)
+}
+
+#[instrument(
+    name = "phone_number_service.delete_all_by_user_id",
+    skip(db_connection)
+)]
+pub async fn delete_all_by_user_id(
+    db_connection: &DatabaseTransaction,
+    user_id: i64,
+) -> Result<u64, AppError> {
+    let result = PhoneNumberEntity::delete_many()
+        .filter(phone_number::Column::UserId.eq(user_id))
+        .exec(db_connection)
+        .await?;
+
+    Ok(result.rows_affected)
+}
+
 #[instrument(name = "phone_number_service.find_all_by_user_identifiers", skip_all)]
 pub async fn find_all_by_user_identifiers(
     db_connection: &DatabaseTransaction,
@@ -1,6 +1,8 @@
 use anyhow::Result;
 use common_error::AppError;
+use common_error::DbError;
 use sea_orm::entity::prelude::*;
+use sea_orm::ActiveValue::Set;
 use sea_orm::DatabaseTransaction;
 use sea_orm::QueryOrder;
 use tracing::instrument;
@@ -19,6 +21,57 @@ pub async fn create_user(
     Ok(user.clone().insert(db_connection).await?)
 }
 
+#[instrument(name = "user_service.update_user", skip_all)]
+pub async fn update_user(
+    db_connection: &DatabaseTransaction,
+    user: &user::Model,
+    expected_version: i64,
+) -> Result<(), AppError> {
+    tracing::debug!("Update user with identifier: {:?}", user.identifier);
+    let changes = user::ActiveModel {
+        version: Set(user.version),
+        name: Set(user.name.clone()),
+        email: Set(user.email.clone()),
+        country: Set(user.country.clone()),
+        ..Default::default()
+    };
+
+    // Only update the row if it wasn't modified concurrently
+    let result = UserEntity::update_many()
+        .set(changes)
+        .filter(user::Column::Id.eq(user.id))
+        .filter(user::Column::Version.eq(expected_version))
+        .exec(db_connection)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::DbError(DbError::Conflict));
+    }
+
+    Ok(())
+}
+
+#[instrument(name = "user_service.delete_user", skip_all)]
+pub async fn delete_user(
+    db_connection: &DatabaseTransaction,
+    user: &user::Model,
+) -> Result<(), AppError> {
+    tracing::debug!("Delete user with identifier: {:?}", user.identifier);
+
+    // Only delete the row if it wasn't modified concurrently
+    let result = UserEntity::delete_many()
+        .filter(user::Column::Id.eq(user.id))
+        .filter(user::Column::Version.eq(user.version))
+        .exec(db_connection)
+        .await?;
+
+    if result.rows_affected == 0 {
+        return Err(AppError::DbError(DbError::Conflict));
+    }
+
+    Ok(())
+}
+
 #[instrument(name = "user_service.find_one_by_identifier", skip(db_connection))]
 pub async fn find_one_by_identifier(
     db_connection: &DatabaseTransaction,
@@ -79,3 +132,70 @@ pub async fn find_all_by_identifiers(
         .all(db_connection)
         .await?)
 }
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::DatabaseBackend;
+    use sea_orm::MockDatabase;
+    use sea_orm::MockExecResult;
+    use sea_orm::TransactionTrait;
+
+    use super::*;
+    use crate::common::model::IsoCountryCodeEnum;
+
+    fn user(version: i64) -> user::Model {
+        user::Model {
+            id: 1,
+            version,
+            identifier: Uuid::new_v4(),
+            name: "Max Mustermann".to_string(),
+            email: "max@example.com".to_string(),
+            country: IsoCountryCodeEnum::DE,
+        }
+    }
+
+    fn exec_result(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    #[tokio::test]
+    async fn update_user_with_expected_version() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(vec![exec_result(1)])
+            .into_connection();
+        let tx = db.begin().await.unwrap();
+
+        let result = update_user(&tx, &user(2), 1).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn update_user_modified_concurrently_is_conflict() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(vec![exec_result(0)])
+            .into_connection();
+        let tx = db.begin().await.unwrap();
+
+        let result = update_user(&tx, &user(2), 1).await;
+
+        assert!(matches!(result, Err(AppError::DbError(DbError::Conflict))));
+    }
+
+    #[tokio::test]
+    async fn delete_user_modified_concurrently_is_conflict() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results(vec![exec_result(1), exec_result(0)])
+            .into_connection();
+        let tx = db.begin().await.unwrap();
+
+        assert!(delete_user(&tx, &user(1)).await.is_ok());
+        assert!(matches!(
+            delete_user(&tx, &user(1)).await,
+            Err(AppError::DbError(DbError::Conflict))
+        ));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod config;
+pub mod notify;
 pub mod pool;
 pub mod transaction;
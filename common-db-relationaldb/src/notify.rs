@@ -0,0 +1,3 @@
+/// Channel of the notifications of inserted outbox events. The trigger of the
+/// event table sends them, the kafka connector listens for them.
+pub const EVENT_NOTIFY_CHANNEL: &str = "event_entity";
@@ -147,6 +147,31 @@ pub fn match_error(error: &AppError) -> (&str, String, StatusCode) {
     }
 }
 
+impl AppError {
+    /// Returns whether the failed operation might succeed if it is repeated.
+    /// Only I/O errors of the database, Kafka or the file system as well as
+    /// conflicting database updates are transient. Validation, parse and
+    /// configuration errors fail the same way on every attempt.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AppError::DbError(e) => matches!(e, DbError::Conflict),
+            AppError::IoError(_) => true,
+            #[cfg(feature = "kafka")]
+            AppError::KafkaError(_) => true,
+            #[cfg(feature = "mongodb")]
+            AppError::MongoDbError(_) => true,
+            #[cfg(feature = "relationaldb")]
+            AppError::RelDbUnhandledDbError(e) => matches!(
+                e,
+                sea_orm::DbErr::Conn(_) | sea_orm::DbErr::Exec(_) | sea_orm::DbErr::Query(_)
+            ),
+            #[cfg(feature = "kafka")]
+            AppError::SerializationError(e) => e.retriable,
+            _ => false,
+        }
+    }
+}
+
 #[cfg(feature = "relationaldb")]
 fn handle_sea_orm_db_error(e: &sea_orm::DbErr) -> (&str, String, StatusCode) {
     match e {
@@ -0,0 +1,98 @@
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use common_metrics::outbox;
+use rand::Rng;
+
+const INITIAL_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Delays the next run of the relay exponentially after failed runs, so that
+/// an unavailable broker or database isn't hammered. The delay is randomized
+/// (between half and the full delay), so that multiple instances don't retry
+/// at the same time.
+#[derive(Default)]
+pub struct Backoff {
+    consecutive_failures: AtomicU32,
+    retry_at: Mutex<Option<Instant>>,
+}
+
+impl Backoff {
+    pub fn new() -> Backoff {
+        Backoff::default()
+    }
+
+    /// Time until the next run is allowed or `None` if it is allowed now.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.retry_at
+            .lock()
+            .expect("Backoff lock poisoned")
+            .and_then(|retry_at| retry_at.checked_duration_since(Instant::now()))
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.remaining().is_some()
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// Records a failed run and returns the delay until the next run.
+    pub fn failed(&self) -> Duration {
+        let consecutive_failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        outbox::record_relay_failure(consecutive_failures);
+
+        let delay = delay(consecutive_failures);
+        *self.retry_at.lock().expect("Backoff lock poisoned") = Some(Instant::now() + delay);
+        delay
+    }
+
+    pub fn succeeded(&self) {
+        if self.consecutive_failures.swap(0, Ordering::Relaxed) > 0 {
+            outbox::record_relay_recovered();
+        }
+        *self.retry_at.lock().expect("Backoff lock poisoned") = None;
+    }
+}
+
+fn delay(consecutive_failures: u32) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    let max_delay = INITIAL_DELAY.saturating_mul(1 << exponent).min(MAX_DELAY);
+    rand::thread_rng().gen_range(max_delay / 2..=max_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_exponentially_up_to_max_delay() {
+        for _ in 0..100 {
+            let first = delay(1);
+            assert!(first >= INITIAL_DELAY / 2 && first <= INITIAL_DELAY);
+
+            let fourth = delay(4);
+            assert!(fourth >= INITIAL_DELAY * 4 && fourth <= INITIAL_DELAY * 8);
+
+            let hundredth = delay(100);
+            assert!(hundredth >= MAX_DELAY / 2 && hundredth <= MAX_DELAY);
+        }
+    }
+
+    #[test]
+    fn success_resets_the_backoff() {
+        let backoff = Backoff::new();
+        backoff.failed();
+        backoff.failed();
+        assert_eq!(backoff.consecutive_failures(), 2);
+        assert!(backoff.is_waiting());
+
+        backoff.succeeded();
+        assert_eq!(backoff.consecutive_failures(), 0);
+        assert!(!backoff.is_waiting());
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use async_trait::async_trait;
+use common_error::AppError;
+use rdkafka::error::KafkaError;
+use rdkafka::message::BorrowedMessage;
+use schema_registry_converter::error::SRCError;
+use tracing::error;
+use tracing::warn;
+
+#[derive(Debug)]
+pub enum ConsumerError {
+    /// The key or payload couldn't be decoded with the schema registry.
+    /// Decoding is retriable if the schema registry couldn't be reached.
+    DecodeError {
+        error: SRCError,
+        retriable: bool,
+    },
+    /// The decoded key or payload couldn't be deserialized into the type
+    /// expected by the handler.
+    DeserializationError(apache_avro::Error),
+    /// The decoded JSON or Protobuf key or payload couldn't be deserialized
+    /// into the type expected by the handler.
+    JsonDeserializationError(serde_json::Error),
+    /// The handler failed to process the record. Processing is retriable if
+    /// the error is transient, e.g. the database couldn't be reached.
+    HandlerError(AppError),
+    KafkaError(KafkaError),
+    /// The payload has no record name.
+    MissingRecordName,
+    /// No handler is registered for the record name.
+    UnknownRecord(String),
+    /// The record was consumed from a topic the consumer isn't subscribed
+    /// to.
+    UnexpectedTopic(String),
+}
+
+impl Display for ConsumerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsumerError::DecodeError { error, .. } => {
+                write!(f, "Couldn't decode record: {:?}", error)
+            }
+            ConsumerError::DeserializationError(e) => {
+                write!(f, "Couldn't deserialize record: {}", e)
+            }
+            ConsumerError::JsonDeserializationError(e) => {
+                write!(f, "Couldn't deserialize record: {}", e)
+            }
+            ConsumerError::HandlerError(e) => write!(f, "Handler failed: {:?}", e),
+            ConsumerError::KafkaError(e) => write!(f, "Kafka error: {}", e),
+            ConsumerError::MissingRecordName => write!(f, "Record without name"),
+            ConsumerError::UnknownRecord(name) => write!(f, "No handler for record: {}", name),
+            ConsumerError::UnexpectedTopic(topic) => {
+                write!(f, "Record from unexpected topic: {}", topic)
+            }
+        }
+    }
+}
+
+impl ConsumerError {
+    /// Returns whether processing the record again might succeed.
+    pub fn is_retriable(&self) -> bool {
+        match self {
+            ConsumerError::DecodeError { retriable, .. } => *retriable,
+            ConsumerError::HandlerError(e) => e.is_transient(),
+            ConsumerError::KafkaError(_) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<SRCError> for ConsumerError {
+    fn from(e: SRCError) -> Self {
+        ConsumerError::DecodeError {
+            retriable: e.retriable,
+            error: e,
+        }
+    }
+}
+
+impl From<apache_avro::Error> for ConsumerError {
+    fn from(e: apache_avro::Error) -> Self {
+        ConsumerError::DeserializationError(e)
+    }
+}
+
+impl From<serde_json::Error> for ConsumerError {
+    fn from(e: serde_json::Error) -> Self {
+        ConsumerError::JsonDeserializationError(e)
+    }
+}
+
+impl From<AppError> for ConsumerError {
+    fn from(e: AppError) -> Self {
+        ConsumerError::HandlerError(e)
+    }
+}
+
+impl From<KafkaError> for ConsumerError {
+    fn from(e: KafkaError) -> Self {
+        ConsumerError::KafkaError(e)
+    }
+}
+
+/// Decides how the consumer continues after a record couldn't be processed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorAction {
+    /// Forward the record to the next retry topic or the dead letter topic
+    /// of the [`crate::consumer::retry::RetryPolicy`]. Stops if the policy
+    /// has no topic to forward the record to.
+    Forward,
+    /// Store the offset of the record and continue with the next one.
+    Skip,
+    /// Stop consuming without storing the offset of the record.
+    Stop,
+}
+
+#[async_trait]
+pub trait ErrorHandler: Send + Sync {
+    async fn handle_error(
+        &self,
+        message: &BorrowedMessage<'_>,
+        error: &ConsumerError,
+    ) -> ErrorAction;
+}
+
+/// Skips records without a registered handler or from unexpected topics and
+/// forwards records to the retry and dead letter topics on all other errors.
+pub struct DefaultErrorHandler;
+
+#[async_trait]
+impl ErrorHandler for DefaultErrorHandler {
+    async fn handle_error(
+        &self,
+        message: &BorrowedMessage<'_>,
+        error: &ConsumerError,
+    ) -> ErrorAction {
+        use rdkafka::Message;
+
+        match error {
+            ConsumerError::UnknownRecord(_) | ConsumerError::UnexpectedTopic(_) => {
+                warn!(
+                    "Skipped record (topic: {}, partition: {}, offset: {}): {}",
+                    message.topic(),
+                    message.partition(),
+                    message.offset(),
+                    error
+                );
+                ErrorAction::Skip
+            }
+            _ => {
+                error!(
+                    "Consumption of record failed (topic: {}, partition: {}, offset: {}): {}",
+                    message.topic(),
+                    message.partition(),
+                    message.offset(),
+                    error
+                );
+                ErrorAction::Forward
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_error_of_unavailable_schema_registry_is_retriable() {
+        let error = ConsumerError::from(SRCError::new(
+            "Couldn't fetch schema",
+            Some("connection refused".to_string()),
+            true,
+        ));
+
+        assert!(error.is_retriable());
+    }
+
+    #[test]
+    fn decode_error_of_invalid_record_is_not_retriable() {
+        let error = ConsumerError::from(SRCError::new("Couldn't decode record", None, false));
+
+        assert!(!error.is_retriable());
+    }
+
+    #[test]
+    fn handler_error_of_unavailable_database_is_retriable() {
+        let error = ConsumerError::from(AppError::from(std::io::Error::new(
+            std::io::ErrorKind::ConnectionRefused,
+            "connection refused",
+        )));
+
+        assert!(error.is_retriable());
+    }
+
+    #[test]
+    fn handler_error_of_invalid_record_is_not_retriable() {
+        let error = ConsumerError::from(AppError::from(anyhow::anyhow!("invalid identifier")));
+
+        assert!(!error.is_retriable());
+    }
+}
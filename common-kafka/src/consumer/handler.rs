@@ -0,0 +1,108 @@
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use common_error::AppError;
+use serde::de::DeserializeOwned;
+
+use crate::consumer::error::ConsumerError;
+use crate::decoder::DecodedValue;
+
+/// Position of a consumed record.
+#[derive(Clone, Debug)]
+pub struct RecordMetadata {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub record_name: String,
+}
+
+/// A consumed record with deserialized key and payload.
+#[derive(Clone, Debug)]
+pub struct ConsumerRecord<K, P> {
+    pub metadata: RecordMetadata,
+    pub key: K,
+    pub payload: P,
+}
+
+/// Handles records of a single record type.
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+    type Key: DeserializeOwned + Send;
+    type Payload: DeserializeOwned + Send;
+
+    async fn handle(
+        &self,
+        record: ConsumerRecord<Self::Key, Self::Payload>,
+    ) -> Result<(), AppError>;
+}
+
+/// Type erased [`EventHandler`] to register handlers with different key and
+/// payload types in the same runtime.
+#[async_trait]
+pub(crate) trait DynEventHandler: Send + Sync {
+    async fn handle(
+        &self,
+        metadata: RecordMetadata,
+        key: DecodedValue,
+        payload: DecodedValue,
+    ) -> Result<(), ConsumerError>;
+}
+
+pub(crate) struct TypedEventHandler<H: EventHandler> {
+    pub(crate) handler: H,
+}
+
+#[async_trait]
+impl<H: EventHandler> DynEventHandler for TypedEventHandler<H> {
+    async fn handle(
+        &self,
+        metadata: RecordMetadata,
+        key: DecodedValue,
+        payload: DecodedValue,
+    ) -> Result<(), ConsumerError> {
+        let key = key.deserialize::<H::Key>()?;
+        let payload = payload.deserialize::<H::Payload>()?;
+
+        Ok(self
+            .handler
+            .handle(ConsumerRecord {
+                metadata,
+                key,
+                payload,
+            })
+            .await?)
+    }
+}
+
+/// Deserializes records of a previous version `V` of the payload and upcasts
+/// them into the payload type of the handler.
+pub(crate) struct UpcastingEventHandler<H: EventHandler, V> {
+    pub(crate) handler: H,
+    pub(crate) version: PhantomData<fn() -> V>,
+}
+
+#[async_trait]
+impl<H, V> DynEventHandler for UpcastingEventHandler<H, V>
+where
+    H: EventHandler,
+    V: DeserializeOwned + Into<H::Payload> + 'static,
+{
+    async fn handle(
+        &self,
+        metadata: RecordMetadata,
+        key: DecodedValue,
+        payload: DecodedValue,
+    ) -> Result<(), ConsumerError> {
+        let key = key.deserialize::<H::Key>()?;
+        let payload = payload.deserialize::<V>()?.into();
+
+        Ok(self
+            .handler
+            .handle(ConsumerRecord {
+                metadata,
+                key,
+                payload,
+            })
+            .await?)
+    }
+}
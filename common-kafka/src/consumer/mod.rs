@@ -0,0 +1,20 @@
+//! Runtime to consume kafka records encoded with the schema registry.
+//!
+//! Records are dispatched to an [`handler::EventHandler`] that is registered
+//! for the name of the record of the payload. Records can be encoded as Avro,
+//! JSON Schema or Protobuf, see
+//! [`crate::decoder::SchemaRegistryRecordDecoder`].
+//!
+//! The runtime takes care of decoding key and payload, continuing traces from
+//! the b3 header, storing offsets and routing errors to an
+//! [`error::ErrorHandler`]. Failed records are retried according to a
+//! [`retry::RetryPolicy`].
+//!
+//! Each version of an event has its own record name. Handlers are written
+//! against the latest version, previous versions are registered with
+//! [`runtime::EventConsumer::with_upcasting_handler`].
+
+pub mod error;
+pub mod handler;
+pub mod retry;
+pub mod runtime;
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// Header with the error message of the last failed attempt.
+pub const HEADER_EXCEPTION: &str = "x-exception";
+/// Header with the topic the record was consumed from initially.
+pub const HEADER_ORIGINAL_TOPIC: &str = "x-original-topic";
+/// Header with the partition the record was consumed from initially.
+pub const HEADER_ORIGINAL_PARTITION: &str = "x-original-partition";
+/// Header with the offset the record was consumed from initially.
+pub const HEADER_ORIGINAL_OFFSET: &str = "x-original-offset";
+/// Header with the number of failed attempts to process the record.
+pub const HEADER_ATTEMPT: &str = "x-attempt";
+/// Header with the epoch milliseconds before which a record from a retry
+/// topic must not be processed.
+pub const HEADER_RETRY_NOT_BEFORE: &str = "x-retry-not-before";
+
+/// Defines how often and where records are retried if their processing
+/// failed.
+///
+/// A failed record is retried `attempts - 1` times in process with an
+/// exponential backoff. If it still fails, it is forwarded to the next retry
+/// topic, where it is processed again after the configured delay. If no retry
+/// topic is left, the record is forwarded to the dead letter topic. Records
+/// that can't be decoded are forwarded to the dead letter topic immediately.
+///
+/// Without retry topics and dead letter topic, the consumer stops at the
+/// first record that can't be processed.
+///
+/// Records of retry topics are held back until their delay expired. Retry
+/// topics should therefore be consumed by separate consumers with a
+/// `max.poll.interval.ms` above the delay.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub backoff: BackoffPolicy,
+    pub topics: Vec<RetryTopic>,
+    pub dead_letter_topic: Option<String>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 1,
+            backoff: BackoffPolicy::default(),
+            topics: vec![],
+            dead_letter_topic: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the topic to forward a failed record to, that was consumed
+    /// from the given topic. Records that aren't retriable are forwarded to
+    /// the dead letter topic directly.
+    pub fn next_topic(&self, current_topic: &str, retriable: bool) -> Option<&str> {
+        if retriable {
+            let next_index = self
+                .topics
+                .iter()
+                .position(|t| t.topic == current_topic)
+                .map(|i| i + 1)
+                .unwrap_or(0);
+
+            if let Some(retry_topic) = self.topics.get(next_index) {
+                return Some(&retry_topic.topic);
+            }
+        }
+        self.dead_letter_topic.as_deref()
+    }
+
+    /// Returns the delay for records forwarded to the given topic.
+    pub fn delay_of(&self, topic: &str) -> Duration {
+        self.topics
+            .iter()
+            .find(|t| t.topic == topic)
+            .map(|t| Duration::from_millis(t.delay_ms))
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of all retry topics.
+    pub fn retry_topics(&self) -> Vec<String> {
+        self.topics.iter().map(|t| t.topic.clone()).collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct BackoffPolicy {
+    pub initial_ms: u64,
+    pub max_ms: u64,
+    pub multiplier: f64,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        BackoffPolicy {
+            initial_ms: 100,
+            max_ms: 5000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Returns the time to wait before the given (1-based) retry.
+    pub fn delay_of(&self, retry: u32) -> Duration {
+        let delay = self.initial_ms as f64 * self.multiplier.powi(retry.saturating_sub(1) as i32);
+        Duration::from_millis(delay.min(self.max_ms as f64) as u64)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetryTopic {
+    pub topic: String,
+    pub delay_ms: u64,
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use common_tracing::B3SpanExt;
+use opentelemetry_propagator_b3::propagator::Propagator;
+use opentelemetry_propagator_b3::propagator::B3_SINGLE_HEADER;
+use rdkafka::consumer::Consumer;
+use rdkafka::consumer::StreamConsumer;
+use rdkafka::message::BorrowedHeaders;
+use rdkafka::message::BorrowedMessage;
+use rdkafka::message::FromBytes;
+use rdkafka::message::Headers;
+use rdkafka::message::OwnedHeaders;
+use rdkafka::producer::FutureProducer;
+use rdkafka::producer::FutureRecord;
+use rdkafka::util::Timeout;
+use rdkafka::Message;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+use tracing::info;
+use tracing::span;
+use tracing::warn;
+use tracing::Instrument;
+use tracing::Level;
+use tracing::Span;
+
+use crate::consumer::error::ConsumerError;
+use crate::consumer::error::DefaultErrorHandler;
+use crate::consumer::error::ErrorAction;
+use crate::consumer::error::ErrorHandler;
+use crate::consumer::handler::DynEventHandler;
+use crate::consumer::handler::EventHandler;
+use crate::consumer::handler::RecordMetadata;
+use crate::consumer::handler::TypedEventHandler;
+use crate::consumer::handler::UpcastingEventHandler;
+use crate::consumer::retry::RetryPolicy;
+use crate::consumer::retry::HEADER_ATTEMPT;
+use crate::consumer::retry::HEADER_EXCEPTION;
+use crate::consumer::retry::HEADER_ORIGINAL_OFFSET;
+use crate::consumer::retry::HEADER_ORIGINAL_PARTITION;
+use crate::consumer::retry::HEADER_ORIGINAL_TOPIC;
+use crate::consumer::retry::HEADER_RETRY_NOT_BEFORE;
+use crate::decoder::RecordDecoder;
+
+/// Consumes records from a [`StreamConsumer`] and dispatches them to the
+/// handler registered for the record name of the payload.
+///
+/// The consumer must be configured with `enable.auto.offset.store=false`.
+/// Offsets are stored after a record is handled successfully or skipped by
+/// the [`ErrorHandler`] or forwarded according to the [`RetryPolicy`].
+pub struct EventConsumer {
+    stream_consumer: StreamConsumer,
+    decoder: Arc<dyn RecordDecoder>,
+    tracing_propagator: Arc<Propagator>,
+    handlers: HashMap<String, Box<dyn DynEventHandler>>,
+    error_handler: Box<dyn ErrorHandler>,
+    retry_policy: RetryPolicy,
+    producer: Option<FutureProducer>,
+}
+
+impl EventConsumer {
+    pub fn new(
+        stream_consumer: StreamConsumer,
+        decoder: Arc<dyn RecordDecoder>,
+        tracing_propagator: Arc<Propagator>,
+    ) -> Self {
+        EventConsumer {
+            stream_consumer,
+            decoder,
+            tracing_propagator,
+            handlers: HashMap::new(),
+            error_handler: Box::new(DefaultErrorHandler),
+            retry_policy: RetryPolicy::default(),
+            producer: None,
+        }
+    }
+
+    /// Registers the handler for records with the given record name.
+    pub fn with_handler<H: EventHandler + 'static>(
+        mut self,
+        record_name: &str,
+        handler: H,
+    ) -> Self {
+        self.handlers.insert(
+            record_name.to_string(),
+            Box::new(TypedEventHandler { handler }),
+        );
+        self
+    }
+
+    /// Registers the handler for records of a previous version `V` of its
+    /// payload. The records are deserialized into `V` and upcast into the
+    /// payload of the handler, so one handler processes all versions of an
+    /// event.
+    pub fn with_upcasting_handler<V, H>(mut self, record_name: &str, handler: H) -> Self
+    where
+        V: DeserializeOwned + Into<H::Payload> + 'static,
+        H: EventHandler + 'static,
+    {
+        self.handlers.insert(
+            record_name.to_string(),
+            Box::new(UpcastingEventHandler {
+                handler,
+                version: PhantomData,
+            }),
+        );
+        self
+    }
+
+    pub fn with_error_handler<E: ErrorHandler + 'static>(mut self, error_handler: E) -> Self {
+        self.error_handler = Box::new(error_handler);
+        self
+    }
+
+    /// Sets the retry policy. The producer is used to forward failed records
+    /// to the retry and dead letter topics.
+    pub fn with_retry_policy(
+        mut self,
+        retry_policy: RetryPolicy,
+        producer: FutureProducer,
+    ) -> Self {
+        self.retry_policy = retry_policy;
+        self.producer = Some(producer);
+        self
+    }
+
+    /// Consumes records until the [`ErrorHandler`] decides to stop.
+    pub async fn run(&self) -> Result<(), ConsumerError> {
+        let topics: Vec<String> = self
+            .stream_consumer
+            .subscription()?
+            .elements()
+            .iter()
+            .map(|element| element.topic().to_string())
+            .collect();
+
+        loop {
+            match self.stream_consumer.recv().await {
+                Err(e) => warn!("Error: {}", e),
+                Ok(message) => {
+                    let result = if is_subscribed(&topics, message.topic()) {
+                        let span = self.init_span(message.headers());
+
+                        // Wait until records from retry topics are due
+                        if let Some(not_before) = get_header(&message, HEADER_RETRY_NOT_BEFORE) {
+                            wait_until(not_before.parse().unwrap_or_default()).await;
+                        }
+
+                        self.process_with_retries(&message).instrument(span).await
+                    } else {
+                        Err(ConsumerError::UnexpectedTopic(message.topic().to_string()))
+                    };
+
+                    if let Err(e) = result {
+                        match self.error_handler.handle_error(&message, &e).await {
+                            ErrorAction::Forward => {
+                                if !self.forward(&message, &e).await? {
+                                    return Err(e);
+                                }
+                            }
+                            ErrorAction::Skip => {}
+                            ErrorAction::Stop => return Err(e),
+                        }
+                    }
+
+                    self.stream_consumer.store_offset_from_message(&message)?;
+                }
+            }
+        }
+    }
+
+    /// Processes the record and retries retriable errors in process with the
+    /// backoff of the retry policy.
+    async fn process_with_retries(
+        &self,
+        message: &BorrowedMessage<'_>,
+    ) -> Result<(), ConsumerError> {
+        let mut attempt = 1;
+        loop {
+            match self.process(message).await {
+                Err(e) if e.is_retriable() && attempt < self.retry_policy.attempts => {
+                    let delay = self.retry_policy.backoff.delay_of(attempt);
+                    warn!(
+                        "Attempt {} to process record failed, retry in {}ms: {}",
+                        attempt,
+                        delay.as_millis(),
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Forwards the record to the next retry topic or the dead letter topic.
+    /// Returns false if there is no topic to forward the record to.
+    async fn forward(
+        &self,
+        message: &BorrowedMessage<'_>,
+        error: &ConsumerError,
+    ) -> Result<bool, ConsumerError> {
+        let producer = match &self.producer {
+            Some(producer) => producer,
+            None => return Ok(false),
+        };
+
+        let topic = match self
+            .retry_policy
+            .next_topic(message.topic(), error.is_retriable())
+        {
+            Some(topic) => topic,
+            None => return Ok(false),
+        };
+
+        // Only retriable errors are retried in process
+        let attempts_in_process = if error.is_retriable() {
+            self.retry_policy.attempts.max(1)
+        } else {
+            1
+        };
+        let attempts: u32 = get_header(message, HEADER_ATTEMPT)
+            .and_then(|a| a.parse().ok())
+            .unwrap_or(0)
+            + attempts_in_process;
+
+        let not_before = SystemTime::now() + self.retry_policy.delay_of(topic);
+
+        // Keep the tracing header and the position of the initial record
+        let mut headers = OwnedHeaders::new()
+            .add(HEADER_EXCEPTION, &error.to_string())
+            .add(
+                HEADER_ORIGINAL_TOPIC,
+                &get_header(message, HEADER_ORIGINAL_TOPIC)
+                    .unwrap_or_else(|| message.topic().to_string()),
+            )
+            .add(
+                HEADER_ORIGINAL_PARTITION,
+                &get_header(message, HEADER_ORIGINAL_PARTITION)
+                    .unwrap_or_else(|| message.partition().to_string()),
+            )
+            .add(
+                HEADER_ORIGINAL_OFFSET,
+                &get_header(message, HEADER_ORIGINAL_OFFSET)
+                    .unwrap_or_else(|| message.offset().to_string()),
+            )
+            .add(HEADER_ATTEMPT, &attempts.to_string())
+            .add(
+                HEADER_RETRY_NOT_BEFORE,
+                &not_before
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis()
+                    .to_string(),
+            );
+        if let Some(b3) = get_header(message, B3_SINGLE_HEADER) {
+            headers = headers.add(B3_SINGLE_HEADER, &b3);
+        }
+
+        let mut record: FutureRecord<[u8], [u8]> = FutureRecord::to(topic).headers(headers);
+        if let Some(key) = message.key() {
+            record = record.key(key);
+        }
+        if let Some(payload) = message.payload() {
+            record = record.payload(payload);
+        }
+
+        producer
+            .send(record, Timeout::After(Duration::from_secs(30)))
+            .await
+            .map_err(|(e, _)| ConsumerError::KafkaError(e))?;
+
+        info!(
+            "Forwarded record (topic: {}, partition: {}, offset: {}) to {} after {} attempts",
+            message.topic(),
+            message.partition(),
+            message.offset(),
+            topic,
+            attempts
+        );
+
+        Ok(true)
+    }
+
+    async fn process(&self, message: &BorrowedMessage<'_>) -> Result<(), ConsumerError> {
+        debug!(
+            "Message received (topic: {}, partition: {}, offset: {})",
+            message.topic(),
+            message.partition(),
+            message.offset()
+        );
+
+        let key = self.decoder.decode(message.key()).await?;
+        let payload = self.decoder.decode(message.payload()).await?;
+
+        let record_name = payload.name.ok_or(ConsumerError::MissingRecordName)?;
+
+        let handler = self
+            .handlers
+            .get(&record_name)
+            .ok_or_else(|| ConsumerError::UnknownRecord(record_name.clone()))?;
+
+        let metadata = RecordMetadata {
+            topic: message.topic().to_string(),
+            partition: message.partition(),
+            offset: message.offset(),
+            record_name,
+        };
+
+        handler.handle(metadata, key.value, payload.value).await
+    }
+
+    fn init_span(&self, headers: Option<&BorrowedHeaders>) -> Span {
+        let span = span!(Level::TRACE, "consume_record");
+        if let Some(headers) = headers {
+            for i in 0..headers.count() {
+                if let Some((name, value)) = headers.get(i) {
+                    if name == B3_SINGLE_HEADER {
+                        if let Ok(trace_id) = str::from_bytes(value) {
+                            span.set_parent_from_b3(
+                                self.tracing_propagator.clone(),
+                                trace_id.to_string(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        span
+    }
+}
+
+/// Returns whether the topic is one of the subscribed topics. Assigned
+/// partitions and subscriptions with patterns aren't resolved, so all topics
+/// are accepted for them.
+fn is_subscribed(topics: &[String], topic: &str) -> bool {
+    topics.is_empty() || topics.iter().any(|t| t == topic || t.starts_with('^'))
+}
+
+fn get_header(message: &BorrowedMessage<'_>, header_name: &str) -> Option<String> {
+    let headers = message.headers()?;
+    (0..headers.count())
+        .filter_map(|i| headers.get(i))
+        .filter(|(name, _)| *name == header_name)
+        .find_map(|(_, value)| str::from_bytes(value).ok().map(|v| v.to_string()))
+}
+
+async fn wait_until(epoch_millis: u64) {
+    let due = UNIX_EPOCH + Duration::from_millis(epoch_millis);
+    if let Ok(delay) = due.duration_since(SystemTime::now()) {
+        tokio::time::sleep(delay).await;
+    }
+}
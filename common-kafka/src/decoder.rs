@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use apache_avro::from_avro_datum;
+use apache_avro::types::Value;
+use async_trait::async_trait;
+use kafka_schema_common::format::protobuf;
+use kafka_schema_common::format::SerializationFormat;
+use prost::encoding::decode_varint;
+use prost_reflect::DynamicMessage;
+use prost_reflect::SerializeOptions;
+use schema_registry_converter::async_impl::avro::AvroDecoder;
+use schema_registry_converter::async_impl::proto_raw::ProtoRawDecoder;
+use schema_registry_converter::async_impl::schema_registry::get_schema_by_id;
+use schema_registry_converter::async_impl::schema_registry::SrSettings;
+use schema_registry_converter::error::SRCError;
+use schema_registry_converter::schema_registry_common::SchemaType;
+use serde::de::DeserializeOwned;
+
+use crate::consumer::error::ConsumerError;
+use crate::schema_cache::LocalSchema;
+use crate::schema_cache::SchemaCache;
+
+/// Decoded key or payload of a record.
+#[derive(Clone, Debug)]
+pub struct DecodedRecord {
+    /// Name of the record. `None` if there were no bytes to decode.
+    pub name: Option<String>,
+    pub value: DecodedValue,
+}
+
+#[derive(Clone, Debug)]
+pub enum DecodedValue {
+    Avro(Value),
+    /// Records in JSON Schema or Protobuf format. Protobuf records are
+    /// converted with the JSON mapping of Protobuf.
+    Json(serde_json::Value),
+}
+
+impl DecodedValue {
+    /// Deserializes the value into a generated avro type.
+    pub fn deserialize<T: DeserializeOwned>(self) -> Result<T, ConsumerError> {
+        match self {
+            DecodedValue::Avro(value) => Ok(apache_avro::from_value::<T>(&value)?),
+            DecodedValue::Json(value) => Ok(serde_json::from_value::<T>(value)?),
+        }
+    }
+
+    pub fn into_json(self) -> serde_json::Value {
+        match self {
+            DecodedValue::Avro(value) => serde_json::Value::try_from(value).unwrap_or_default(),
+            DecodedValue::Json(value) => value,
+        }
+    }
+}
+
+#[async_trait]
+pub trait RecordDecoder: Send + Sync {
+    async fn decode(&self, bytes: Option<&[u8]>) -> Result<DecodedRecord, SRCError>;
+}
+
+pub struct AvroRecordDecoder<'a> {
+    pub avro_decoder: AvroDecoder<'a>,
+}
+
+impl<'a> AvroRecordDecoder<'a> {
+    pub fn new(sr_settings: SrSettings) -> AvroRecordDecoder<'a> {
+        AvroRecordDecoder {
+            avro_decoder: AvroDecoder::new(sr_settings),
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> RecordDecoder for AvroRecordDecoder<'a> {
+    async fn decode(&self, bytes: Option<&[u8]>) -> Result<DecodedRecord, SRCError> {
+        let result = self.avro_decoder.decode(bytes).await?;
+        Ok(DecodedRecord {
+            name: result.name.map(|n| n.name),
+            value: DecodedValue::Avro(result.value),
+        })
+    }
+}
+
+/// Decodes records of every [`SerializationFormat`]. The format is determined
+/// by the type of the schema the record was encoded with. Records of the
+/// schemas in the [`SchemaCache`] are decoded without requests to the schema
+/// registry.
+///
+/// [`SerializationFormat`]: kafka_schema_common::format::SerializationFormat
+pub struct SchemaRegistryRecordDecoder<'a> {
+    sr_settings: SrSettings,
+    schema_cache: Arc<SchemaCache>,
+    avro_decoder: AvroRecordDecoder<'a>,
+    proto_decoder: ProtoRawDecoder<'a>,
+    /// Types and record names of the schemas, that are not cached, by their
+    /// id.
+    schemas: Mutex<HashMap<u32, (SchemaType, Option<String>)>>,
+}
+
+impl<'a> SchemaRegistryRecordDecoder<'a> {
+    pub fn new(
+        sr_settings: SrSettings,
+        schema_cache: Arc<SchemaCache>,
+    ) -> SchemaRegistryRecordDecoder<'a> {
+        SchemaRegistryRecordDecoder {
+            sr_settings: sr_settings.clone(),
+            schema_cache,
+            avro_decoder: AvroRecordDecoder::new(sr_settings.clone()),
+            proto_decoder: ProtoRawDecoder::new(sr_settings),
+            schemas: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the type of the schema and the name of the record, that is the
+    /// title of JSON schemas.
+    async fn schema_of(&self, id: u32) -> Result<(SchemaType, Option<String>), SRCError> {
+        let cached_schema = self.schemas.lock().unwrap().get(&id).cloned();
+        if let Some(schema) = cached_schema {
+            return Ok(schema);
+        }
+
+        let registered_schema = get_schema_by_id(id, &self.sr_settings).await?;
+        let record_name = match registered_schema.schema_type {
+            SchemaType::Json => {
+                serde_json::from_str::<serde_json::Value>(&registered_schema.schema)
+                    .ok()
+                    .and_then(|s| {
+                        s.get("title")
+                            .and_then(|t| t.as_str())
+                            .map(|t| t.to_string())
+                    })
+            }
+            _ => None,
+        };
+
+        let schema = (registered_schema.schema_type, record_name);
+        self.schemas.lock().unwrap().insert(id, schema.clone());
+        Ok(schema)
+    }
+
+    /// Decodes the record with the local schema.
+    fn decode_local(
+        &self,
+        local_schema: &LocalSchema,
+        record: &[u8],
+    ) -> Result<DecodedRecord, SRCError> {
+        let mut payload = &record[5..];
+        let value = match (local_schema.format, &local_schema.avro_schema) {
+            (SerializationFormat::Avro, Some(avro_schema)) => DecodedValue::Avro(
+                from_avro_datum(avro_schema, &mut payload, None).map_err(conversion_error)?,
+            ),
+            (SerializationFormat::Avro, None) => {
+                return Err(conversion_error(format!(
+                    "No avro schema of {}",
+                    local_schema.record_name
+                )))
+            }
+            (SerializationFormat::JsonSchema, _) => {
+                DecodedValue::Json(serde_json::from_slice(payload).map_err(conversion_error)?)
+            }
+            (SerializationFormat::Protobuf, _) => {
+                // Skip the message indexes, the record is the first message
+                // of the schema
+                let count = decode_varint(&mut payload).map_err(conversion_error)? >> 1;
+                for _ in 0..count {
+                    decode_varint(&mut payload).map_err(conversion_error)?;
+                }
+                return self
+                    .decode_protobuf(&protobuf::full_name(&local_schema.record_name), payload);
+            }
+        };
+
+        Ok(DecodedRecord {
+            name: Some(local_schema.record_name.clone()),
+            value,
+        })
+    }
+
+    fn decode_protobuf(&self, full_name: &str, bytes: &[u8]) -> Result<DecodedRecord, SRCError> {
+        let descriptor = self
+            .schema_cache
+            .descriptors()
+            .get_message_by_name(full_name)
+            .ok_or_else(|| conversion_error(format!("Unknown message {}", full_name)))?;
+        let message = DynamicMessage::decode(descriptor, bytes).map_err(conversion_error)?;
+
+        // Keep the avro field names and types of the generated avro types
+        let options = SerializeOptions::new()
+            .use_proto_field_name(true)
+            .stringify_64_bit_integers(false)
+            .skip_default_fields(false);
+        let value = message
+            .serialize_with_options(serde_json::value::Serializer, &options)
+            .map_err(conversion_error)?;
+
+        Ok(DecodedRecord {
+            name: Some(protobuf::record_name_of(full_name).to_string()),
+            value: DecodedValue::Json(value),
+        })
+    }
+}
+
+#[async_trait]
+impl<'a> RecordDecoder for SchemaRegistryRecordDecoder<'a> {
+    async fn decode(&self, bytes: Option<&[u8]>) -> Result<DecodedRecord, SRCError> {
+        // Records start with a magic byte and the id of the schema
+        let record = match bytes {
+            Some(record) if record.len() > 4 && record[0] == 0 => record,
+            _ => return self.avro_decoder.decode(bytes).await,
+        };
+        let id = u32::from_be_bytes([record[1], record[2], record[3], record[4]]);
+
+        if let Some(local_schema) = self.schema_cache.schema_by_id(id) {
+            return self.decode_local(local_schema, record);
+        }
+
+        match self.schema_of(id).await? {
+            (SchemaType::Avro, _) => self.avro_decoder.decode(bytes).await,
+            (SchemaType::Json, record_name) => {
+                let value = serde_json::from_slice(&record[5..]).map_err(conversion_error)?;
+                Ok(DecodedRecord {
+                    name: record_name,
+                    value: DecodedValue::Json(value),
+                })
+            }
+            (SchemaType::Protobuf, _) => {
+                let result = self
+                    .proto_decoder
+                    .decode(bytes)
+                    .await?
+                    .ok_or_else(|| conversion_error("Empty Protobuf record"))?;
+                self.decode_protobuf(result.full_name.as_str(), result.bytes)
+            }
+            (schema_type, _) => Err(conversion_error(format!(
+                "Unsupported schema type {:?}",
+                schema_type
+            ))),
+        }
+    }
+}
+
+fn conversion_error<E: Display>(e: E) -> SRCError {
+    SRCError::new("Couldn't decode record", Some(e.to_string()), false)
+}
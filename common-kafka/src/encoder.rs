@@ -0,0 +1,154 @@
+use std::fmt::Display;
+use std::sync::Arc;
+
+use apache_avro::to_avro_datum;
+use kafka_schema_common::format::protobuf;
+use kafka_schema_common::format::SerializationFormat;
+use prost::Message;
+use prost_reflect::DynamicMessage;
+use schema_registry_converter::error::SRCError;
+use serde::Serialize;
+
+use crate::schema_cache::SchemaCache;
+
+/// Encodes records in the [`SerializationFormat`] of their topic with the
+/// schemas compiled into the service. The ids of the schemas are taken from
+/// the [`SchemaCache`], so records are encoded without requests to the schema
+/// registry once the id of the schema is known.
+pub struct RecordEncoder {
+    schema_cache: Arc<SchemaCache>,
+}
+
+impl RecordEncoder {
+    pub fn new(schema_cache: Arc<SchemaCache>) -> RecordEncoder {
+        RecordEncoder { schema_cache }
+    }
+
+    pub async fn encode<T: Serialize>(
+        &self,
+        value: T,
+        record_name: &str,
+        format: SerializationFormat,
+    ) -> Result<Vec<u8>, SRCError> {
+        let subject = format.subject_name(record_name);
+        let id = self.schema_cache.id_of(&subject).await?;
+        let local_schema = self
+            .schema_cache
+            .local_schema(&subject)
+            .ok_or_else(|| conversion_error(format!("Unknown record {}", record_name)))?;
+
+        // Records start with a magic byte and the id of the schema
+        let mut record = vec![0];
+        record.extend_from_slice(&id.to_be_bytes());
+
+        match (format, &local_schema.avro_schema) {
+            (SerializationFormat::Avro, Some(avro_schema)) => {
+                let value = apache_avro::to_value(value)
+                    .and_then(|v| v.resolve(avro_schema))
+                    .map_err(conversion_error)?;
+                record.extend(to_avro_datum(avro_schema, value).map_err(conversion_error)?);
+            }
+            (SerializationFormat::Avro, None) => {
+                return Err(conversion_error(format!(
+                    "No avro schema of {}",
+                    record_name
+                )))
+            }
+            (SerializationFormat::JsonSchema, _) => {
+                serde_json::to_writer(&mut record, &value).map_err(conversion_error)?;
+            }
+            (SerializationFormat::Protobuf, _) => {
+                let descriptor = self
+                    .schema_cache
+                    .descriptors()
+                    .get_message_by_name(&protobuf::full_name(record_name))
+                    .ok_or_else(|| conversion_error(format!("Unknown record {}", record_name)))?;
+
+                // The avro types are converted with the JSON mapping of Protobuf
+                let value = serde_json::to_value(value).map_err(conversion_error)?;
+                let message =
+                    DynamicMessage::deserialize(descriptor, value).map_err(conversion_error)?;
+
+                // The record is the first message of the schema, that is
+                // encoded as a single zero as message index
+                record.push(0);
+                message.encode(&mut record).map_err(conversion_error)?;
+            }
+        }
+        Ok(record)
+    }
+}
+
+fn conversion_error<E: Display>(e: E) -> SRCError {
+    SRCError::new(
+        "Couldn't convert record into the serialization format",
+        Some(e.to_string()),
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use kafka_schema_common::schema_key::KeyAvro;
+    use kafka_schema_common::schema_key::SCHEMA_NAME_KEY;
+    use kafka_schema_common::IdentifierAvro;
+    use kafka_schema_registry::EmbeddedSchemaRegistry;
+    use schema_registry_converter::async_impl::schema_registry::SrSettings;
+
+    use super::*;
+    use crate::decoder::RecordDecoder;
+    use crate::decoder::SchemaRegistryRecordDecoder;
+
+    #[tokio::test]
+    async fn encode_and_decode_in_all_formats() {
+        let formats = [
+            SerializationFormat::Avro,
+            SerializationFormat::JsonSchema,
+            SerializationFormat::Protobuf,
+        ];
+        let embedded_registry = EmbeddedSchemaRegistry::start().unwrap();
+        embedded_registry
+            .registry()
+            .register_record_schemas(kafka_schema_common::SCHEMAS, &formats)
+            .unwrap();
+
+        let schema_cache = Arc::new(
+            SchemaCache::init(embedded_registry.url(), kafka_schema_common::SCHEMAS, None)
+                .await
+                .unwrap(),
+        );
+        let sr_settings = SrSettings::new(embedded_registry.url().to_string());
+        let encoder = RecordEncoder::new(schema_cache.clone());
+        let decoder = SchemaRegistryRecordDecoder::new(sr_settings, schema_cache);
+
+        let key = KeyAvro {
+            context_identifier: "d8f1a4a7-6f0a-4b2c-9a53-2fa0f1c1b7a1".to_string(),
+            identifier: IdentifierAvro {
+                data_type: "user".to_string(),
+                identifier: "5b4c0b7e-8f1d-4e63-9d2c-0d6b7f0bde27".to_string(),
+                version: 3,
+            },
+        };
+
+        for format in formats {
+            let encoded = encoder
+                .encode(key.clone(), SCHEMA_NAME_KEY, format)
+                .await
+                .unwrap();
+            let decoded = decoder.decode(Some(&encoded)).await.unwrap();
+
+            assert_eq!(
+                decoded.name.as_deref(),
+                Some(SCHEMA_NAME_KEY),
+                "{:?}",
+                format
+            );
+            assert_eq!(
+                serde_json::to_value(decoded.value.deserialize::<KeyAvro>().unwrap()).unwrap(),
+                serde_json::to_value(&key).unwrap(),
+                "{:?}",
+                format
+            );
+        }
+    }
+}
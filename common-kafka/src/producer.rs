@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use common_error::AppError;
+use common_metrics::outbox;
+use rdkafka::error::KafkaError;
+use rdkafka::error::RDKafkaErrorCode;
+use rdkafka::producer::FutureProducer;
+use rdkafka::producer::Producer;
+use rdkafka::util::Timeout;
+use rdkafka::ClientConfig;
+use serde::Deserialize;
+use tracing::warn;
+
+/// Properties of the [`TransactionalProducer`] of the outbox connectors.
+#[derive(Debug, Deserialize)]
+pub struct ProducerProperties {
+    pub client_id: String,
+    pub transactional_id: String,
+    /// Stable id of this instance of the connector, e.g. the ordinal of the
+    /// pod of a stateful set. It is appended to the `transactional_id`, so that
+    /// a restarted instance fences the open transaction of its previous
+    /// incarnation. Can be set with the `CONNECTOR_INSTANCE_ID` environment
+    /// variable.
+    #[serde(default)]
+    pub instance_id: Option<String>,
+    /// Timeout in milliseconds to initialize, commit and abort transactions.
+    pub transaction_timeout_ms: u64,
+    /// Timeout in milliseconds for the response of a request to the broker.
+    pub request_timeout_ms: u64,
+    /// Timeout in milliseconds to report the success or failure of a sent
+    /// record.
+    pub delivery_timeout_ms: u64,
+    /// Time in milliseconds to wait for more records to send them together.
+    pub linger_ms: u64,
+    pub acks: String,
+    pub retries: u32,
+    /// Additional librdkafka properties, e.g. `compression.type`. They
+    /// override the properties above.
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+/// Transactional producer that is re-created after fatal errors, e.g. after it
+/// was fenced by a producer with the same transactional id. Jobs take the
+/// current producer for each batch.
+pub struct TransactionalProducer {
+    client_config: ClientConfig,
+    transaction_timeout: Duration,
+    producer: RwLock<Arc<FutureProducer>>,
+}
+
+impl TransactionalProducer {
+    pub fn get(&self) -> Arc<FutureProducer> {
+        self.producer
+            .read()
+            .expect("Producer lock poisoned")
+            .clone()
+    }
+
+    /// Re-creates the producer if the error left it unusable. Returns whether
+    /// the producer was re-created.
+    pub async fn recover(&self, error: &AppError) -> Result<bool, AppError> {
+        let producer = self.get();
+        // The producer is also stuck if a failed transaction couldn't be aborted
+        let is_fatal = match error {
+            AppError::KafkaError(KafkaError::Transaction(e)) => {
+                e.is_fatal() || e.code() == RDKafkaErrorCode::State
+            }
+            _ => false,
+        };
+        if !is_fatal && producer.client().fatal_error().is_none() {
+            return Ok(false);
+        }
+
+        warn!("Re-creating producer after fatal error: {:?}", error);
+        let client_config = self.client_config.clone();
+        let transaction_timeout = self.transaction_timeout;
+        let producer = tokio::task::spawn_blocking(move || {
+            create_producer(&client_config, transaction_timeout)
+        })
+        .await
+        .map_err(|e| anyhow!(e))??;
+        *self.producer.write().expect("Producer lock poisoned") = Arc::new(producer);
+        outbox::record_producer_recreated();
+
+        Ok(true)
+    }
+}
+
+pub fn init_producer(
+    broker_urls: &str,
+    properties: &ProducerProperties,
+) -> Result<TransactionalProducer, AppError> {
+    let mut client_config = ClientConfig::new();
+    client_config
+        .set("bootstrap.servers", broker_urls)
+        // Maximum amount of time the client will wait for the response of a request
+        .set(
+            "request.timeout.ms",
+            properties.request_timeout_ms.to_string(),
+        )
+        // Upper bound on the time to report success or failure after a call to send() returns
+        .set(
+            "delivery.timeout.ms",
+            properties.delivery_timeout_ms.to_string(),
+        )
+        .set("enable.idempotence", "true") // Ensure that exactly one copy of each message is written in the stream
+        // Number of unacknowledged requests the client will send on a single connection before
+        // blocking
+        .set("max.in.flight.requests.per.connection", "5")
+        .set("linger.ms", properties.linger_ms.to_string()) // Wait to group sending messages
+        .set("transactional.id", transactional_id(properties)?)
+        .set("request.required.acks", properties.acks.clone()) // Wait for acknowledge from brokers
+        .set("message.send.max.retries", properties.retries.to_string())
+        .set("client.id", properties.client_id.clone()); // Set an identifiable name for traceability
+
+    // Additional properties, e.g. to tune the throughput per environment
+    for (key, value) in &properties.properties {
+        client_config.set(key, value);
+    }
+
+    let transaction_timeout = Duration::from_millis(properties.transaction_timeout_ms);
+    let producer = create_producer(&client_config, transaction_timeout)?;
+
+    Ok(TransactionalProducer {
+        client_config,
+        transaction_timeout,
+        producer: RwLock::new(Arc::new(producer)),
+    })
+}
+
+fn create_producer(
+    client_config: &ClientConfig,
+    transaction_timeout: Duration,
+) -> Result<FutureProducer, AppError> {
+    let producer: FutureProducer = client_config.create()?;
+
+    producer.init_transactions(Timeout::from(transaction_timeout))?;
+
+    Ok(producer)
+}
+
+/// Connector instances need distinct transactional ids, otherwise the producer
+/// of one instance fences the producers of the others. The id must be stable
+/// across restarts, so that the producer fences the open transaction of the
+/// previous incarnation of the instance.
+fn transactional_id(properties: &ProducerProperties) -> Result<String, AppError> {
+    match properties.instance_id.as_deref().map(str::trim) {
+        Some(instance_id) if !instance_id.is_empty() => {
+            Ok(format!("{}-{}", properties.transactional_id, instance_id))
+        }
+        _ => Err(anyhow!(
+            "No instance id configured, set kafka.producer.instance_id or CONNECTOR_INSTANCE_ID"
+        )
+        .into()),
+    }
+}
+
+/// Result of the last connectivity check of the brokers. Health probes read the
+/// result instead of checking the brokers themselves, so that slow brokers
+/// don't stall the probes.
+#[derive(Default)]
+pub struct BrokerStatus {
+    reachable: AtomicBool,
+}
+
+impl BrokerStatus {
+    pub fn is_reachable(&self) -> bool {
+        self.reachable.load(Ordering::Relaxed)
+    }
+
+    /// Checks the connectivity to the brokers and keeps the result.
+    pub async fn refresh(&self, producer: Arc<FutureProducer>, timeout: Duration) {
+        let result = check_broker(producer, timeout).await;
+        if let Err(e) = &result {
+            warn!("Broker isn't reachable: {:?}", e);
+        }
+        self.reachable.store(result.is_ok(), Ordering::Relaxed);
+    }
+}
+
+/// Checks the connectivity to the brokers by fetching the cluster metadata.
+pub async fn check_broker(
+    producer: Arc<FutureProducer>,
+    timeout: Duration,
+) -> Result<(), AppError> {
+    tokio::task::spawn_blocking(move || {
+        producer
+            .client()
+            .fetch_metadata(None, Timeout::from(timeout))
+            .map(|_| ())
+    })
+    .await
+    .map_err(|e| anyhow!(e))??;
+
+    Ok(())
+}
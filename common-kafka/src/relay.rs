@@ -0,0 +1,130 @@
+use futures::future;
+use futures::Future;
+
+/// Sends the records of each topic partition sequentially in the order of the
+/// list, while the partitions are sent concurrently. The events of an aggregate
+/// are always written to the same partition, so their order is kept.
+pub async fn send_in_order<'a, T, P, F, Fut, R, E>(
+    records: &'a [T],
+    topic_partition_of: P,
+    send: F,
+) -> Result<Vec<(&'a T, R)>, E>
+where
+    P: Fn(&T) -> (&str, i32),
+    F: Fn(&'a T) -> Fut,
+    Fut: Future<Output = Result<R, E>>,
+{
+    let mut partitions: Vec<Vec<&T>> = Vec::new();
+    for record in records {
+        match partitions
+            .iter_mut()
+            .find(|p| topic_partition_of(p[0]) == topic_partition_of(record))
+        {
+            Some(partition) => partition.push(record),
+            None => partitions.push(vec![record]),
+        }
+    }
+
+    let results = future::try_join_all(partitions.into_iter().map(|partition| {
+        let send = &send;
+        async move {
+            let mut results = Vec::new();
+            for record in partition {
+                results.push((record, send(record).await?));
+            }
+            Ok(results)
+        }
+    }))
+    .await?;
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct Record {
+        id: u64,
+        topic: String,
+        partition: i32,
+    }
+
+    fn record(id: u64, topic: &str, partition: i32) -> Record {
+        Record {
+            id,
+            topic: topic.to_string(),
+            partition,
+        }
+    }
+
+    fn topic_partition_of(record: &Record) -> (&str, i32) {
+        (record.topic.as_str(), record.partition)
+    }
+
+    #[tokio::test]
+    async fn records_of_a_partition_are_sent_in_order() {
+        // Interleaved writes of aggregates in three partitions of two topics
+        let records: Vec<Record> = (0..30)
+            .map(|id| record(id, if id % 2 == 0 { "user" } else { "room" }, id as i32 % 3))
+            .collect();
+
+        let sent = Mutex::new(Vec::new());
+        send_in_order(&records, topic_partition_of, |record| {
+            let sent = &sent;
+            async move {
+                // Later records would overtake earlier ones if sent concurrently
+                tokio::time::sleep(Duration::from_millis(3 * (30 - record.id))).await;
+                sent.lock().unwrap().push(record.clone());
+                Ok::<(), ()>(())
+            }
+        })
+        .await
+        .unwrap();
+
+        let sent = sent.into_inner().unwrap();
+        assert_eq!(sent.len(), records.len());
+        for topic in ["user", "room"] {
+            for partition in 0..3 {
+                let ids_of = |records: &[Record]| -> Vec<u64> {
+                    records
+                        .iter()
+                        .filter(|r| r.topic == topic && r.partition == partition)
+                        .map(|r| r.id)
+                        .collect()
+                };
+                assert_eq!(ids_of(&sent), ids_of(&records));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn sending_of_a_partition_stops_at_first_error() {
+        let records = vec![
+            record(1, "user", 0),
+            record(2, "user", 1),
+            record(3, "user", 0),
+        ];
+
+        let sent = Mutex::new(Vec::new());
+        let result = send_in_order(&records, topic_partition_of, |record| {
+            let sent = &sent;
+            async move {
+                sent.lock().unwrap().push(record.id);
+                if record.id == 1 {
+                    Err("failed")
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result, Err("failed"));
+        assert!(!sent.into_inner().unwrap().contains(&3));
+    }
+}
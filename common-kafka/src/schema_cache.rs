@@ -0,0 +1,578 @@
+//! Cache of the ids of the schemas compiled into the services.
+//!
+//! The ids of the schemas of all records and formats are looked up in the
+//! schema registry on startup, so records of these schemas are encoded and
+//! decoded without requests to the schema registry. If the ids are persisted
+//! to a file, they are loaded from the file on startup when the schema
+//! registry isn't available, so that services keep producing records of known
+//! schemas while the schema registry is down. Ids looked up later are
+//! persisted in the background, at most once per `PERSIST_DELAY`.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use kafka_schema_common::format::protobuf;
+use kafka_schema_common::format::SerializationFormat;
+use kafka_schema_common::reference::resolve_references;
+use kafka_schema_common::RecordSchema;
+use kafka_schema_common::SchemaReference;
+use prost_reflect::DescriptorPool;
+use reqwest::StatusCode;
+use schema_registry_converter::error::SRCError;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::json;
+use serde_json::Value;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+const CONTENT_TYPE_SCHEMA_REGISTRY: &str = "application/vnd.schemaregistry.v1+json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time to wait before ids that were looked up are persisted, so that the ids
+/// of multiple lookups are written at once.
+const PERSIST_DELAY: Duration = Duration::from_secs(1);
+
+/// Schema of a record in a format, as it is registered in the schema registry.
+pub struct LocalSchema {
+    pub subject: String,
+    pub record_name: String,
+    pub format: SerializationFormat,
+    /// The referenced types of avro schemas are registered as separate
+    /// subjects.
+    definition: String,
+    references: &'static [SchemaReference],
+    /// Parsed avro schema with the referenced types inlined.
+    pub avro_schema: Option<apache_avro::Schema>,
+}
+
+impl LocalSchema {
+    fn new(
+        record_schema: &RecordSchema,
+        format: SerializationFormat,
+    ) -> Result<LocalSchema, SRCError> {
+        let (definition, references, avro_schema) = match format {
+            SerializationFormat::Avro => {
+                let resolved_schema =
+                    resolve_references(record_schema.raw_schema, record_schema.references)
+                        .map_err(|e| schema_error(record_schema.name, e))?;
+                let avro_schema = apache_avro::Schema::parse_str(&resolved_schema)
+                    .map_err(|e| schema_error(record_schema.name, e))?;
+                (
+                    record_schema.raw_schema.to_string(),
+                    record_schema.references,
+                    Some(avro_schema),
+                )
+            }
+            _ => (
+                format
+                    .schema_of(record_schema)
+                    .map_err(|e| schema_error(record_schema.name, e))?,
+                &[][..],
+                None,
+            ),
+        };
+
+        Ok(LocalSchema {
+            subject: format.subject_name(record_schema.name),
+            record_name: record_schema.name.to_string(),
+            format,
+            definition,
+            references,
+            avro_schema,
+        })
+    }
+}
+
+#[derive(Default)]
+struct CachedIds {
+    ids: HashMap<String, u32>,
+    subjects: HashMap<u32, String>,
+}
+
+/// Id of a subject persisted together with the schema it was looked up for,
+/// so that ids of changed schemas are not loaded.
+#[derive(Deserialize, Serialize)]
+struct PersistedId {
+    id: u32,
+    schema: String,
+}
+
+/// Writes the cached ids to the file without blocking the runtime threads.
+struct IdFile {
+    file: PathBuf,
+    /// Schema definitions by subject.
+    definitions: HashMap<String, String>,
+    /// Whether a write is scheduled.
+    pending: AtomicBool,
+    /// Serializes the writes to the file.
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+impl IdFile {
+    /// Loads the persisted ids of the schemas that didn't change.
+    async fn load(&self) -> Result<HashMap<String, u32>, String> {
+        let content = match tokio::fs::read_to_string(&self.file).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let persisted_ids = serde_json::from_str::<BTreeMap<String, PersistedId>>(&content)
+            .map_err(|e| e.to_string())?;
+
+        Ok(persisted_ids
+            .into_iter()
+            .filter(|(subject, persisted_id)| {
+                self.definitions.get(subject) == Some(&persisted_id.schema)
+            })
+            .map(|(subject, persisted_id)| (subject, persisted_id.id))
+            .collect())
+    }
+
+    /// Persists the ids after the `PERSIST_DELAY`, unless a write is already
+    /// scheduled.
+    fn schedule(self: &Arc<Self>, ids: &Arc<RwLock<CachedIds>>) {
+        if self.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let id_file = self.clone();
+        let ids = ids.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(PERSIST_DELAY).await;
+            // Ids inserted from now on schedule another write
+            id_file.pending.store(false, Ordering::Release);
+            id_file.persist(&ids).await;
+        });
+    }
+
+    async fn persist(&self, ids: &RwLock<CachedIds>) {
+        let content = {
+            let ids = ids.read().unwrap();
+            let persisted_ids: BTreeMap<&String, PersistedId> = ids
+                .ids
+                .iter()
+                .filter_map(|(subject, id)| {
+                    self.definitions.get(subject).map(|definition| {
+                        (subject, PersistedId {
+                            id: *id,
+                            schema: definition.clone(),
+                        })
+                    })
+                })
+                .collect();
+            serde_json::to_string_pretty(&persisted_ids)
+        };
+
+        let _write_guard = self.write_lock.lock().await;
+        let result = match content {
+            Ok(content) => tokio::fs::write(&self.file, content)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = result {
+            warn!("Couldn't persist schema ids to {:?}: {}", self.file, e);
+        }
+    }
+}
+
+pub struct SchemaCache {
+    client: reqwest::Client,
+    url: String,
+    id_file: Option<Arc<IdFile>>,
+    local_schemas: HashMap<String, LocalSchema>,
+    descriptors: DescriptorPool,
+    ids: Arc<RwLock<CachedIds>>,
+}
+
+impl SchemaCache {
+    /// Initializes the cache with the schemas of the records in all formats.
+    /// The ids are loaded from the file, if given, and looked up in the
+    /// schema registry afterwards.
+    pub async fn init(
+        url: &str,
+        schemas: &[RecordSchema],
+        file: Option<PathBuf>,
+    ) -> Result<SchemaCache, SRCError> {
+        let mut local_schemas = HashMap::new();
+        for record_schema in schemas {
+            for format in [
+                SerializationFormat::Avro,
+                SerializationFormat::JsonSchema,
+                SerializationFormat::Protobuf,
+            ] {
+                let local_schema = LocalSchema::new(record_schema, format)?;
+                local_schemas.insert(local_schema.subject.clone(), local_schema);
+            }
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| {
+                SRCError::new("Couldn't create http client", Some(e.to_string()), false)
+            })?;
+
+        let id_file = file.map(|file| {
+            Arc::new(IdFile {
+                file,
+                definitions: local_schemas
+                    .iter()
+                    .map(|(subject, s)| (subject.clone(), s.definition.clone()))
+                    .collect(),
+                pending: AtomicBool::new(false),
+                write_lock: tokio::sync::Mutex::new(()),
+            })
+        });
+
+        let cache = SchemaCache {
+            client,
+            url: url.trim_end_matches('/').to_string(),
+            id_file,
+            local_schemas,
+            descriptors: protobuf::descriptor_pool(schemas)
+                .map_err(|e| schema_error("descriptors", e))?,
+            ids: Arc::default(),
+        };
+        cache.load().await;
+        cache.warm_up().await;
+        if let Some(id_file) = &cache.id_file {
+            id_file.persist(&cache.ids).await;
+        }
+        Ok(cache)
+    }
+
+    /// Returns the id of the schema of the subject. The id is looked up in
+    /// the schema registry if it isn't cached yet.
+    pub async fn id_of(&self, subject: &str) -> Result<u32, SRCError> {
+        if let Some(id) = self.ids.read().unwrap().ids.get(subject).copied() {
+            metrics::increment_counter!(
+                "schema_cache_requests_total",
+                "lookup" => "id_by_subject",
+                "result" => "hit"
+            );
+            return Ok(id);
+        }
+        metrics::increment_counter!(
+            "schema_cache_requests_total",
+            "lookup" => "id_by_subject",
+            "result" => "miss"
+        );
+
+        let local_schema = self.local_schema(subject).ok_or_else(|| {
+            SRCError::new(
+                "Unknown subject",
+                Some(format!("No schema compiled in for subject {}", subject)),
+                false,
+            )
+        })?;
+
+        let id = self
+            .lookup(local_schema)
+            .await?
+            .ok_or_else(|| {
+                SRCError::new(
+                    "Schema not registered",
+                    Some(format!("Schema of subject {} is not registered", subject)),
+                    false,
+                )
+            })?
+            .0;
+        self.insert(subject, id);
+        if let Some(id_file) = &self.id_file {
+            id_file.schedule(&self.ids);
+        }
+        Ok(id)
+    }
+
+    pub fn local_schema(&self, subject: &str) -> Option<&LocalSchema> {
+        self.local_schemas.get(subject)
+    }
+
+    /// Returns the local schema with the id. Returns `None` for schemas that
+    /// are not compiled into the service or whose id isn't known yet.
+    pub fn schema_by_id(&self, id: u32) -> Option<&LocalSchema> {
+        let local_schema = self
+            .ids
+            .read()
+            .unwrap()
+            .subjects
+            .get(&id)
+            .and_then(|subject| self.local_schemas.get(subject));
+
+        let result = if local_schema.is_some() {
+            "hit"
+        } else {
+            "miss"
+        };
+        metrics::increment_counter!(
+            "schema_cache_requests_total",
+            "lookup" => "schema_by_id",
+            "result" => result
+        );
+        local_schema
+    }
+
+    /// Protobuf descriptors of the records.
+    pub fn descriptors(&self) -> &DescriptorPool {
+        &self.descriptors
+    }
+
+    /// Looks up the ids of all local schemas. Stops at the first error, as
+    /// the schema registry is most likely not available.
+    async fn warm_up(&self) {
+        let mut subjects: Vec<&String> = self.local_schemas.keys().collect();
+        subjects.sort();
+
+        let mut registered = 0;
+        for subject in subjects {
+            match self.lookup(&self.local_schemas[subject]).await {
+                Ok(Some((id, _))) => {
+                    self.insert(subject, id);
+                    registered += 1;
+                }
+                Ok(None) => debug!("Schema of subject {} is not registered", subject),
+                Err(e) => {
+                    warn!(
+                        "Schema registry not available, {} cached schema ids are used: {}",
+                        self.ids.read().unwrap().ids.len(),
+                        e
+                    );
+                    return;
+                }
+            }
+        }
+        info!("Looked up the ids of {} schemas", registered);
+    }
+
+    /// Looks up the id and version of the schema in the schema registry.
+    /// Returns `None` if the schema isn't registered. The referenced types
+    /// are looked up first, as they are part of the lookup request.
+    async fn lookup(&self, local_schema: &LocalSchema) -> Result<Option<(u32, u32)>, SRCError> {
+        let mut versions = HashMap::new();
+        for reference in flatten_references(local_schema.references) {
+            let version = self
+                .lookup_subject(
+                    reference.name,
+                    reference.raw_schema,
+                    SerializationFormat::Avro,
+                    &references_json(reference.references, &versions),
+                )
+                .await?;
+            match version {
+                Some((_, version)) => versions.insert(reference.name, version),
+                None => return Ok(None),
+            };
+        }
+
+        self.lookup_subject(
+            &local_schema.subject,
+            &local_schema.definition,
+            local_schema.format,
+            &references_json(local_schema.references, &versions),
+        )
+        .await
+    }
+
+    async fn lookup_subject(
+        &self,
+        subject: &str,
+        schema: &str,
+        format: SerializationFormat,
+        references: &[Value],
+    ) -> Result<Option<(u32, u32)>, SRCError> {
+        #[derive(Deserialize)]
+        struct LookupResponse {
+            id: u32,
+            version: u32,
+        }
+
+        let mut body = json!({ "schema": schema, "references": references });
+        match format {
+            SerializationFormat::Avro => {}
+            SerializationFormat::JsonSchema => body["schemaType"] = "JSON".into(),
+            SerializationFormat::Protobuf => body["schemaType"] = "PROTOBUF".into(),
+        }
+
+        let start = Instant::now();
+        let response = self
+            .client
+            .post(format!("{}/subjects/{}", self.url, subject))
+            .header(reqwest::header::CONTENT_TYPE, CONTENT_TYPE_SCHEMA_REGISTRY)
+            .json(&body)
+            .send()
+            .await;
+        let result = match response {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => Ok(None),
+            Ok(response) => match response.error_for_status() {
+                Ok(response) => response.json::<LookupResponse>().await.map(Some),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+
+        let labels = [
+            ("operation", "lookup".to_string()),
+            (
+                "result",
+                match &result {
+                    Ok(Some(_)) => "found",
+                    Ok(None) => "not_found",
+                    Err(_) => "error",
+                }
+                .to_string(),
+            ),
+        ];
+        metrics::increment_counter!("schema_registry_requests_total", &labels);
+        metrics::histogram!(
+            "schema_registry_requests_duration_seconds",
+            start.elapsed().as_secs_f64(),
+            &labels
+        );
+
+        result.map(|r| r.map(|r| (r.id, r.version))).map_err(|e| {
+            SRCError::new(
+                "Lookup of schema failed",
+                Some(format!("{}: {}", subject, e)),
+                true,
+            )
+        })
+    }
+
+    fn insert(&self, subject: &str, id: u32) {
+        let mut ids = self.ids.write().unwrap();
+        ids.ids.insert(subject.to_string(), id);
+        ids.subjects.insert(id, subject.to_string());
+    }
+
+    /// Loads the persisted ids of the schemas that didn't change.
+    async fn load(&self) {
+        let id_file = match &self.id_file {
+            Some(id_file) => id_file,
+            None => return,
+        };
+
+        match id_file.load().await {
+            Ok(persisted_ids) => {
+                for (subject, id) in persisted_ids {
+                    self.insert(&subject, id);
+                }
+            }
+            Err(e) => warn!("Couldn't load schema ids from {:?}: {}", id_file.file, e),
+        }
+    }
+}
+
+/// Returns the referenced types and their references, so that every type
+/// comes after the types it references.
+fn flatten_references(references: &'static [SchemaReference]) -> Vec<&'static SchemaReference> {
+    let mut flattened: Vec<&'static SchemaReference> = Vec::new();
+    for reference in references {
+        for nested in flatten_references(reference.references) {
+            if !flattened.iter().any(|r| r.name == nested.name) {
+                flattened.push(nested);
+            }
+        }
+        if !flattened.iter().any(|r| r.name == reference.name) {
+            flattened.push(reference);
+        }
+    }
+    flattened
+}
+
+/// Referenced types are registered with their name as subject.
+fn references_json(references: &[SchemaReference], versions: &HashMap<&str, u32>) -> Vec<Value> {
+    references
+        .iter()
+        .map(|reference| {
+            json!({
+                "name": reference.name,
+                "subject": reference.name,
+                "version": versions.get(reference.name).copied().unwrap_or(1),
+            })
+        })
+        .collect()
+}
+
+fn schema_error<E: ToString>(name: &str, e: E) -> SRCError {
+    SRCError::new(
+        "Invalid schema",
+        Some(format!("{}: {}", name, e.to_string())),
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use kafka_schema_registry::EmbeddedSchemaRegistry;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn ids_are_loaded_from_file_if_registry_is_down() {
+        let file = std::env::temp_dir().join(format!("schema-cache-{}.json", uuid::Uuid::new_v4()));
+        let subject = SerializationFormat::Avro.subject_name(kafka_schema_common::SCHEMAS[0].name);
+
+        let embedded_registry = EmbeddedSchemaRegistry::start().unwrap();
+        embedded_registry
+            .registry()
+            .register_record_schemas(kafka_schema_common::SCHEMAS, &[SerializationFormat::Avro])
+            .unwrap();
+        let cache = SchemaCache::init(
+            embedded_registry.url(),
+            kafka_schema_common::SCHEMAS,
+            Some(file.clone()),
+        )
+        .await
+        .unwrap();
+        let id = cache.id_of(&subject).await.unwrap();
+
+        let url = embedded_registry.url().to_string();
+        drop(embedded_registry);
+        let cache = SchemaCache::init(&url, kafka_schema_common::SCHEMAS, Some(file.clone()))
+            .await
+            .unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(cache.id_of(&subject).await.unwrap(), id);
+        assert_eq!(
+            cache.schema_by_id(id).map(|s| s.subject.as_str()),
+            Some(subject.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn ids_looked_up_after_init_are_persisted() {
+        let file = std::env::temp_dir().join(format!("schema-cache-{}.json", uuid::Uuid::new_v4()));
+        let subject = SerializationFormat::Avro.subject_name(kafka_schema_common::SCHEMAS[0].name);
+
+        let embedded_registry = EmbeddedSchemaRegistry::start().unwrap();
+        let cache = SchemaCache::init(
+            embedded_registry.url(),
+            kafka_schema_common::SCHEMAS,
+            Some(file.clone()),
+        )
+        .await
+        .unwrap();
+        embedded_registry
+            .registry()
+            .register_record_schemas(kafka_schema_common::SCHEMAS, &[SerializationFormat::Avro])
+            .unwrap();
+        let id = cache.id_of(&subject).await.unwrap();
+
+        tokio::time::sleep(PERSIST_DELAY * 2).await;
+        let content = std::fs::read_to_string(&file).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        let persisted_ids: BTreeMap<String, PersistedId> = serde_json::from_str(&content).unwrap();
+        assert_eq!(persisted_ids.get(&subject).map(|p| p.id), Some(id));
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use common_error::AppError;
+use config::Config;
+use config::ConfigError;
+use config::File;
+use config::FileFormat;
+use rdkafka::consumer::BaseConsumer;
+use rdkafka::consumer::Consumer;
+use rdkafka::util::Timeout;
+use rdkafka::ClientConfig;
+use serde::Deserialize;
+use tracing::warn;
+
+/// Topic manifest shared by the topic client, that applies it to the cluster,
+/// and the services, that partition their records based on it.
+pub const RAW_TOPIC_MANIFEST: &str = include_str!("../resources/topics.yml");
+
+/// Desired state of the topics in the cluster.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TopicManifest {
+    pub topics: Vec<TopicDefinition>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct TopicDefinition {
+    pub name: String,
+    pub partitions: i32,
+    pub replication: i32,
+    /// Topic level configs, e.g. `retention.ms` or `cleanup.policy`.
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+}
+
+impl TopicManifest {
+    /// Loads the shared topic manifest.
+    pub fn load_shared() -> Result<Self, ConfigError> {
+        Config::builder()
+            .add_source(File::from_str(RAW_TOPIC_MANIFEST, FileFormat::Yaml))
+            .build()?
+            .try_deserialize()
+    }
+
+    /// Loads a topic manifest from the given file.
+    pub fn load(path: &str) -> Result<Self, ConfigError> {
+        Config::builder()
+            .add_source(File::with_name(path))
+            .build()?
+            .try_deserialize()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TopicDefinition> {
+        self.topics.iter().find(|t| t.name == name)
+    }
+
+    /// Returns the number of partitions of the topic with the given name.
+    pub fn partitions_of(&self, name: &str) -> Result<i32, ConfigError> {
+        self.get(name).map(|t| t.partitions).ok_or_else(|| {
+            ConfigError::Message(format!("Topic {} not found in topic manifest", name))
+        })
+    }
+}
+
+/// Defines how services react if the partitions of a topic in the cluster
+/// don't match the configured partitions.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PartitionCheck {
+    /// Refuse to start.
+    #[default]
+    Fail,
+    /// Log a warning and start anyway.
+    Warn,
+}
+
+/// Compares the configured partitions of the topics with the partitions in the
+/// cluster. Records are partitioned with [`crate::partition_of`], so a
+/// mismatch routes records with the same key to different partitions.
+pub fn verify_partitions(
+    bootstrap_servers: &str,
+    topics: &[(String, i32)],
+    check: PartitionCheck,
+) -> Result<(), AppError> {
+    let consumer: BaseConsumer = ClientConfig::new()
+        .set("bootstrap.servers", bootstrap_servers)
+        .create()?;
+
+    for (topic, partitions) in topics {
+        let metadata =
+            consumer.fetch_metadata(Some(topic), Timeout::After(Duration::from_secs(10)))?;
+
+        let actual_partitions = metadata
+            .topics()
+            .iter()
+            .find(|t| t.name() == topic)
+            .map(|t| t.partitions().len() as i32)
+            .unwrap_or_default();
+
+        if actual_partitions != *partitions {
+            let message = format!(
+                "Topic {} has {} partitions but {} are configured",
+                topic, actual_partitions, partitions
+            );
+            match check {
+                PartitionCheck::Fail => return Err(ConfigError::Message(message).into()),
+                PartitionCheck::Warn => warn!("{}", message),
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -6,6 +6,8 @@ use metrics_exporter_prometheus::Matcher;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use metrics_exporter_prometheus::PrometheusHandle;
 
+use crate::outbox;
+
 pub fn init_routing() -> Router {
     let recorder_handle = setup_metrics_recorder();
     Router::new().route("/metrics", get(move || ready(recorder_handle.render())))
@@ -17,6 +19,9 @@ fn setup_metrics_recorder() -> PrometheusHandle {
         0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
     ];
 
+    // Specify batch size buckets of the outbox relays
+    const BATCH_SIZES: &[f64] = &[1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0];
+
     // Initialize recorder to track request durations
     PrometheusBuilder::new()
         .set_buckets_for_metric(
@@ -24,6 +29,13 @@ fn setup_metrics_recorder() -> PrometheusHandle {
             EXPONENTIAL_SECONDS,
         )
         .expect("Initializing prometheus buckets failed")
+        .set_buckets_for_metric(
+            Matcher::Full(outbox::SEND_DURATION_SECONDS.to_string()),
+            EXPONENTIAL_SECONDS,
+        )
+        .expect("Initializing prometheus buckets failed")
+        .set_buckets_for_metric(Matcher::Full(outbox::BATCH_SIZE.to_string()), BATCH_SIZES)
+        .expect("Initializing prometheus buckets failed")
         .install_recorder()
         .expect("Installation of prometheus recorder failed")
 }
@@ -0,0 +1,56 @@
+//! Metrics of the outbox relays of the kafka connectors.
+
+use std::time::Duration;
+
+pub const EVENTS_SENT_TOTAL: &str = "outbox_events_sent_total";
+pub const SEND_DURATION_SECONDS: &str = "outbox_send_duration_seconds";
+pub const BATCH_SIZE: &str = "outbox_batch_size";
+pub const TRANSACTIONS_TOTAL: &str = "outbox_transactions_total";
+pub const UNSENT_EVENTS: &str = "outbox_unsent_events";
+pub const OLDEST_UNSENT_EVENT_AGE_SECONDS: &str = "outbox_oldest_unsent_event_age_seconds";
+pub const RELAY_FAILURES_TOTAL: &str = "outbox_relay_failures_total";
+pub const CONSECUTIVE_RELAY_FAILURES: &str = "outbox_consecutive_relay_failures";
+pub const PRODUCERS_RECREATED_TOTAL: &str = "outbox_producers_recreated_total";
+
+/// Records the events sent to a topic.
+pub fn record_sent_events(topic: &str, count: u64) {
+    metrics::counter!(EVENTS_SENT_TOTAL, count, "topic" => topic.to_string());
+}
+
+/// Records a batch of events that was sent in one kafka transaction.
+pub fn record_batch(size: usize, duration: Duration) {
+    metrics::histogram!(BATCH_SIZE, size as f64);
+    metrics::histogram!(SEND_DURATION_SECONDS, duration.as_secs_f64());
+}
+
+pub fn record_transaction_committed() {
+    metrics::increment_counter!(TRANSACTIONS_TOTAL, "result" => "commit");
+}
+
+pub fn record_transaction_aborted() {
+    metrics::increment_counter!(TRANSACTIONS_TOTAL, "result" => "abort");
+}
+
+/// Records the events in the outbox that weren't sent yet.
+pub fn record_lag(unsent_events: u64, oldest_unsent_event_age: Duration) {
+    metrics::gauge!(UNSENT_EVENTS, unsent_events as f64);
+    metrics::gauge!(
+        OLDEST_UNSENT_EVENT_AGE_SECONDS,
+        oldest_unsent_event_age.as_secs_f64()
+    );
+}
+
+/// Records a failed run of the relay and the number of failed runs in a row.
+pub fn record_relay_failure(consecutive_failures: u32) {
+    metrics::increment_counter!(RELAY_FAILURES_TOTAL);
+    metrics::gauge!(CONSECUTIVE_RELAY_FAILURES, consecutive_failures as f64);
+}
+
+pub fn record_relay_recovered() {
+    metrics::gauge!(CONSECUTIVE_RELAY_FAILURES, 0.0);
+}
+
+/// Records a producer that was re-created after a fatal error.
+pub fn record_producer_recreated() {
+    metrics::increment_counter!(PRODUCERS_RECREATED_TOTAL);
+}
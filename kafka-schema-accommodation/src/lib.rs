@@ -1,38 +1,124 @@
-use serde::Deserialize;
-use serde::Serialize;
-
-pub mod schema_create_accommodation;
-pub mod schema_create_room_type;
-pub mod schema_delete_room_type;
-pub mod schema_update_accommodation;
-pub mod schema_update_room_type;
+// Avro types generated from the schemas in the resources directory
+include!(concat!(env!("OUT_DIR"), "/schemas.rs"));
 
 pub const DATA_TYPE_ACCOMMODATION: &str = "accommodation";
 
 pub const DATA_TYPE_ROOM_TYPE: &str = "roomType";
 
-#[derive(Debug, Deserialize, Serialize)]
-pub enum IsoCountryCodeEnumAvro {
-    DE,
-    US,
-}
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AccommodationAddressAvro {
-    pub street: String,
-    pub house_number: u16,
-    pub zip_code: String,
-    pub city: String,
-    pub area: Option<String>,
-    pub country: IsoCountryCodeEnumAvro,
-}
+    use kafka_schema_common::compatibility::assert_formats_derivable;
+    use kafka_schema_common::compatibility::assert_round_trip;
+    use kafka_schema_common::compatibility::assert_versions_compatible;
+    use kafka_schema_common::IsoCountryCodeEnumAvro;
+
+    use super::*;
+    use crate::schema_create_accommodation::*;
+    use crate::schema_create_room_type::*;
+    use crate::schema_delete_room_type::*;
+    use crate::schema_update_accommodation::*;
+    use crate::schema_update_room_type::*;
+
+    const ACCOMMODATION_ID: &str = "0f0d4b5e-2a3c-4e0b-8a7d-6c1b2d3e4f50";
+    const ROOM_TYPE_ID: &str = "9a8b7c6d-5e4f-4a3b-9c2d-1e0f2a3b4c5d";
+
+    fn address(area: Option<String>) -> AccommodationAddressAvro {
+        AccommodationAddressAvro {
+            street: "Hauptstraße".to_string(),
+            house_number: 12,
+            zip_code: "80331".to_string(),
+            city: "München".to_string(),
+            area,
+            country: IsoCountryCodeEnumAvro::De,
+        }
+    }
+
+    #[test]
+    fn schema_versions_compatible() {
+        assert_versions_compatible(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("resources"),
+            &[REFERENCES, kafka_schema_common::REFERENCES].concat(),
+        );
+    }
+
+    #[test]
+    fn schemas_derivable_in_all_formats() {
+        assert_formats_derivable(SCHEMAS);
+    }
+
+    #[test]
+    fn create_accommodation_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_CREATE_ACCOMMODATION_V1,
+            REFERENCES_CREATE_ACCOMMODATION_V1,
+            &CreateAccommodationAvro {
+                identifier: ACCOMMODATION_ID.to_string(),
+                name: "Hotel am See".to_string(),
+                description: "Quiet hotel at the lake".to_string(),
+                address: address(Some("Bavaria".to_string())),
+            },
+        );
+    }
+
+    #[test]
+    fn update_accommodation_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_UPDATE_ACCOMMODATION_V1,
+            REFERENCES_UPDATE_ACCOMMODATION_V1,
+            &UpdateAccommodationAvro {
+                identifier: ACCOMMODATION_ID.to_string(),
+                name: "Hotel am See".to_string(),
+                description: "Renovated hotel at the lake".to_string(),
+                address: address(None),
+            },
+        );
+    }
+
+    #[test]
+    fn create_room_type_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_CREATE_ROOM_TYPE_V1,
+            REFERENCES_CREATE_ROOM_TYPE_V1,
+            &CreateRoomTypeAvro {
+                accommodation_id: ACCOMMODATION_ID.to_string(),
+                identifier: ROOM_TYPE_ID.to_string(),
+                size: 25,
+                balcony: true,
+                bed_type: BedTypeEnumAvro::TwinSingle,
+                tv: true,
+                wifi: false,
+            },
+        );
+    }
+
+    #[test]
+    fn update_room_type_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_UPDATE_ROOM_TYPE_V1,
+            REFERENCES_UPDATE_ROOM_TYPE_V1,
+            &UpdateRoomTypeAvro {
+                accommodation_id: ACCOMMODATION_ID.to_string(),
+                identifier: ROOM_TYPE_ID.to_string(),
+                size: 30,
+                balcony: false,
+                bed_type: BedTypeEnumAvro::King,
+                tv: false,
+                wifi: true,
+            },
+        );
+    }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "UPPERCASE")]
-pub enum BedTypeAvro {
-    Single,
-    TwinSingle,
-    Double,
-    King,
+    #[test]
+    fn delete_room_type_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_DELETE_ROOM_TYPE_V1,
+            REFERENCES_DELETE_ROOM_TYPE_V1,
+            &DeleteRoomTypeAvro {
+                accommodation_id: ACCOMMODATION_ID.to_string(),
+                identifier: ROOM_TYPE_ID.to_string(),
+            },
+        );
+    }
 }
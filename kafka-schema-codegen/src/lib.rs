@@ -0,0 +1,805 @@
+//! Generates rust types from avro schemas. Used by the build scripts of the
+//! kafka-schema crates.
+//!
+//! Every schema file `{event}_v{version}.avsc` becomes a module
+//! `schema_{event}` with:
+//! - a struct for the top level record of each version,
+//! - the constants `SCHEMA_NAME_{EVENT}_V{version}`,
+//!   `RAW_SCHEMA_{EVENT}_V{version}` and `REFERENCES_{EVENT}_V{version}`,
+//! - the constants `SCHEMA_NAME_{EVENT}`, `RAW_SCHEMA_{EVENT}` and
+//!   `REFERENCES_{EVENT}` and a type alias without version suffix for the
+//!   latest version.
+//!
+//! Schema files without version suffix only get the constants
+//! `SCHEMA_NAME_{EVENT}`, `RAW_SCHEMA_{EVENT}` and `REFERENCES_{EVENT}`.
+//!
+//! The constant `SCHEMAS` in the root of the crate lists the schemas of all
+//! files and versions.
+//!
+//! Schema files in the `types` subdirectory define types that are shared
+//! between schemas and referenced by their name. They are generated in the
+//! root of the crate together with the constants `SCHEMA_NAME_{TYPE}`,
+//! `RAW_SCHEMA_{TYPE}` and `REFERENCE_{TYPE}`. Nested records and enums are
+//! generated in the root of the crate as well.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde_json::Map;
+use serde_json::Value;
+
+/// Name of the directory with the shared types.
+pub const TYPES_DIRECTORY: &str = "types";
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Generates the rust types of the schemas of a directory and its
+/// subdirectories. The output file is included with
+/// `include!(concat!(env!("OUT_DIR"), "/schemas.rs"))`.
+pub struct Codegen {
+    directory: PathBuf,
+    external_types: Vec<(PathBuf, String)>,
+}
+
+impl Codegen {
+    pub fn new(directory: &Path) -> Self {
+        Codegen {
+            directory: directory.to_path_buf(),
+            external_types: vec![],
+        }
+    }
+
+    /// Makes the shared types of another schema crate available to the
+    /// schemas. The directory is the `types` directory of the other crate.
+    pub fn with_external_types(mut self, directory: &Path, crate_name: &str) -> Self {
+        self.external_types
+            .push((directory.to_path_buf(), crate_name.to_string()));
+        self
+    }
+
+    pub fn generate(&self, out_file: &Path) -> io::Result<()> {
+        let mut generator = Generator::default();
+
+        // Types of other crates are only registered, not generated
+        for (directory, crate_name) in &self.external_types {
+            generator.emit = false;
+            generator.crate_path = crate_name.clone();
+            load_types(&mut generator, directory)?;
+            generator.generate_pending_types()?;
+        }
+
+        generator.emit = true;
+        generator.crate_path = "crate".to_string();
+        let types_directory = self.directory.join(TYPES_DIRECTORY);
+        if types_directory.is_dir() {
+            load_types(&mut generator, &types_directory)?;
+        }
+        generator.generate_pending_types()?;
+
+        let mut files = Vec::new();
+        collect_schema_files(&self.directory, &mut files)?;
+        files.sort();
+
+        let mut modules: BTreeMap<String, Vec<SchemaFile>> = BTreeMap::new();
+        for path in files {
+            let (event, version) = split_version(&file_stem(&path));
+            let schema = read_schema(&path)?;
+
+            generator.references.push(vec![]);
+            let record = generator
+                .generate_record(&schema, false)
+                .map_err(|e| invalid_schema(&path, &e))?;
+            let references = generator.references.pop().unwrap_or_default();
+
+            modules.entry(event).or_default().push(SchemaFile {
+                version,
+                path: fs::canonicalize(&path)?,
+                record,
+                references,
+            });
+        }
+
+        let mut code = String::new();
+        for definition in generator.root_definitions.values() {
+            code.push_str(definition);
+        }
+        code.push_str(&generator.type_constants);
+        let _ = writeln!(
+            code,
+            "/// All shared types of the crate.\npub const REFERENCES: \
+             &[kafka_schema_common::SchemaReference] = &[{}];\n",
+            generator.own_references.join(", ")
+        );
+        let mut schemas = Vec::new();
+        for (event, files) in modules.iter_mut() {
+            files.sort_by_key(|f| f.version);
+            code.push_str(&generate_module(event, files));
+
+            for file in files.iter() {
+                let suffix = constant_suffix(event, file.version);
+                schemas.push(format!(
+                    "kafka_schema_common::RecordSchema {{\n    name: \
+                     schema_{0}::SCHEMA_NAME_{1},\n    raw_schema: \
+                     schema_{0}::RAW_SCHEMA_{1},\n    references: \
+                     schema_{0}::REFERENCES_{1},\n}}",
+                    event, suffix
+                ));
+            }
+        }
+        let _ = writeln!(
+            code,
+            "/// Schemas of all records of the crate.\npub const SCHEMAS: \
+             &[kafka_schema_common::RecordSchema] = &[{}];",
+            schemas.join(", ")
+        );
+
+        fs::write(out_file, code)
+    }
+}
+
+struct SchemaFile {
+    version: Option<u32>,
+    path: PathBuf,
+    record: Record,
+    references: Vec<String>,
+}
+
+struct Record {
+    avro_name: String,
+    rust_name: String,
+    code: String,
+}
+
+struct TypeFile {
+    path: PathBuf,
+    schema: Value,
+}
+
+struct Generator {
+    /// Whether code is generated for the types or they are only registered.
+    emit: bool,
+    /// Path of the crate the types are generated in.
+    crate_path: String,
+    /// Rust paths of the named avro types by their avro name.
+    names: BTreeMap<String, String>,
+    /// Shared types that are not generated yet by their avro name.
+    pending_types: BTreeMap<String, TypeFile>,
+    /// Paths of the reference constants of the shared types by their avro name.
+    reference_constants: BTreeMap<String, String>,
+    /// References of the schemas that are currently generated.
+    references: Vec<Vec<String>>,
+    /// Code of the nested records and enums by their rust name.
+    root_definitions: BTreeMap<String, String>,
+    /// Constants of the shared types.
+    type_constants: String,
+    /// Reference constants of the shared types of the crate.
+    own_references: Vec<String>,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator {
+            emit: true,
+            crate_path: "crate".to_string(),
+            names: BTreeMap::new(),
+            pending_types: BTreeMap::new(),
+            reference_constants: BTreeMap::new(),
+            references: vec![],
+            root_definitions: BTreeMap::new(),
+            type_constants: String::new(),
+            own_references: vec![],
+        }
+    }
+}
+
+impl Generator {
+    fn generate_pending_types(&mut self) -> io::Result<()> {
+        while let Some(name) = self.pending_types.keys().next().cloned() {
+            self.generate_type(&name)?;
+        }
+        Ok(())
+    }
+
+    /// Generates a shared type and its constants.
+    fn generate_type(&mut self, name: &str) -> io::Result<()> {
+        let type_file = match self.pending_types.remove(name) {
+            Some(type_file) => type_file,
+            None => return Ok(()),
+        };
+        let path = &type_file.path;
+        let constant = file_stem(path).to_uppercase();
+
+        self.references.push(vec![]);
+        self.rust_type(&type_file.schema)
+            .map_err(|e| invalid_schema(path, &e))?;
+        let references = self.references.pop().unwrap_or_default();
+
+        let reference_constant = format!("{}::REFERENCE_{}", self.crate_path, constant);
+        self.reference_constants
+            .insert(name.to_string(), reference_constant.clone());
+
+        if self.emit {
+            let _ = writeln!(
+                self.type_constants,
+                "pub const SCHEMA_NAME_{}: &str = \"{}\";\n",
+                constant, name
+            );
+            let _ = writeln!(
+                self.type_constants,
+                "pub const RAW_SCHEMA_{}: &str = include_str!({:?});\n",
+                constant,
+                fs::canonicalize(path)?.display().to_string()
+            );
+            let _ = writeln!(
+                self.type_constants,
+                "pub const REFERENCE_{0}: kafka_schema_common::SchemaReference = \
+                 kafka_schema_common::SchemaReference {{\n    name: SCHEMA_NAME_{0},\n    \
+                 raw_schema: RAW_SCHEMA_{0},\n    references: &[{1}],\n}};\n",
+                constant,
+                references.join(", ")
+            );
+            self.own_references.push(reference_constant);
+        }
+        Ok(())
+    }
+
+    /// Generates the struct of a record. Nested records are added to the root
+    /// definitions.
+    fn generate_record(&mut self, schema: &Value, nested: bool) -> Result<Record, String> {
+        let object = schema
+            .as_object()
+            .filter(|o| o.get("type").and_then(|t| t.as_str()) == Some("record"))
+            .ok_or_else(|| format!("Expected a record but found {}", schema))?;
+
+        let avro_name = name_of(object)?;
+        let rust_name = type_name(&avro_name);
+        let path = if nested {
+            format!("{}::{}", self.crate_path, rust_name)
+        } else {
+            rust_name.clone()
+        };
+        self.names.insert(avro_name.clone(), path);
+
+        let fields = object
+            .get("fields")
+            .and_then(|f| f.as_array())
+            .ok_or_else(|| format!("Record {} has no fields", avro_name))?;
+
+        let mut code = String::new();
+        write_doc(&mut code, object, "");
+        code.push_str("#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]\n");
+        let _ = writeln!(code, "pub struct {} {{", rust_name);
+
+        for field in fields {
+            let field = field
+                .as_object()
+                .ok_or_else(|| format!("Invalid field in record {}", avro_name))?;
+            let field_name = name_of(field)?;
+            let field_type = field
+                .get("type")
+                .ok_or_else(|| format!("Field {} has no type", field_name))?;
+            let rust_type = self.rust_type(field_type)?;
+
+            write_doc(&mut code, field, "    ");
+            let rust_field_name = field_name_of(&field_name);
+            if rust_field_name.trim_start_matches("r#") != field_name {
+                let _ = writeln!(code, "    #[serde(rename = \"{}\")]", field_name);
+            }
+            let _ = writeln!(code, "    pub {}: {},", rust_field_name, rust_type);
+        }
+        code.push_str("}\n\n");
+
+        Ok(Record {
+            avro_name,
+            rust_name,
+            code,
+        })
+    }
+
+    fn generate_enum(&mut self, object: &Map<String, Value>) -> Result<String, String> {
+        let avro_name = name_of(object)?;
+        let rust_name = type_name(&avro_name);
+        let path = format!("{}::{}", self.crate_path, rust_name);
+        self.names.insert(avro_name.clone(), path.clone());
+
+        let symbols = object
+            .get("symbols")
+            .and_then(|s| s.as_array())
+            .ok_or_else(|| format!("Enum {} has no symbols", avro_name))?;
+
+        let mut code = String::new();
+        write_doc(&mut code, object, "");
+        code.push_str(
+            "#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]\n",
+        );
+        let _ = writeln!(code, "pub enum {} {{", rust_name);
+        for symbol in symbols {
+            let symbol = symbol
+                .as_str()
+                .ok_or_else(|| format!("Invalid symbol in enum {}", avro_name))?;
+            let variant = variant_name(symbol);
+            if variant != symbol {
+                let _ = writeln!(code, "    #[serde(rename = \"{}\")]", symbol);
+            }
+            let _ = writeln!(code, "    {},", variant);
+        }
+        code.push_str("}\n\n");
+
+        self.add_root_definition(&rust_name, code)?;
+        Ok(path)
+    }
+
+    /// Adds a nested record or enum. Definitions with the same name must be
+    /// identical in all schemas of the crate.
+    fn add_root_definition(&mut self, rust_name: &str, code: String) -> Result<(), String> {
+        if !self.emit {
+            return Ok(());
+        }
+        match self.root_definitions.get(rust_name) {
+            Some(existing) if *existing != code => Err(format!(
+                "{} is defined differently in multiple schemas. Rename the type in the new \
+                 version of the schema (e.g. {}V2) or move it to the {} directory.",
+                rust_name, rust_name, TYPES_DIRECTORY
+            )),
+            Some(_) => Ok(()),
+            None => {
+                self.root_definitions.insert(rust_name.to_string(), code);
+                Ok(())
+            }
+        }
+    }
+
+    fn rust_type(&mut self, schema: &Value) -> Result<String, String> {
+        match schema {
+            Value::String(name) => self.primitive_or_reference(name),
+            Value::Array(variants) => self.union_type(variants),
+            Value::Object(object) => {
+                let schema_type = object
+                    .get("type")
+                    .ok_or_else(|| format!("Schema has no type: {}", schema))?;
+
+                match schema_type.as_str() {
+                    Some("record") => {
+                        let record = self.generate_record(schema, true)?;
+                        self.add_root_definition(&record.rust_name, record.code)?;
+                        Ok(format!("{}::{}", self.crate_path, record.rust_name))
+                    }
+                    Some("enum") => self.generate_enum(object),
+                    Some("array") => {
+                        let items = object
+                            .get("items")
+                            .ok_or_else(|| format!("Array has no items: {}", schema))?;
+                        Ok(format!("Vec<{}>", self.rust_type(items)?))
+                    }
+                    Some("map") => {
+                        let values = object
+                            .get("values")
+                            .ok_or_else(|| format!("Map has no values: {}", schema))?;
+                        Ok(format!(
+                            "std::collections::HashMap<String, {}>",
+                            self.rust_type(values)?
+                        ))
+                    }
+                    Some("fixed") => Err(format!("Fixed types aren't supported: {}", schema)),
+                    // Primitive types with logical type or nested type definitions
+                    _ => self.rust_type(schema_type),
+                }
+            }
+            _ => Err(format!("Invalid schema: {}", schema)),
+        }
+    }
+
+    fn primitive_or_reference(&mut self, name: &str) -> Result<String, String> {
+        let rust_type = match name {
+            "null" => "()",
+            "boolean" => "bool",
+            "int" => "i32",
+            "long" => "i64",
+            "float" => "f32",
+            "double" => "f64",
+            "bytes" => "Vec<u8>",
+            "string" => "String",
+            _ => return self.reference(name),
+        };
+        Ok(rust_type.to_string())
+    }
+
+    /// Resolves a type by its name. Shared types are added to the references
+    /// of the schema that is currently generated.
+    fn reference(&mut self, name: &str) -> Result<String, String> {
+        if self.pending_types.contains_key(name) {
+            self.generate_type(name).map_err(|e| e.to_string())?;
+        }
+
+        if let Some(reference_constant) = self.reference_constants.get(name) {
+            if let Some(references) = self.references.last_mut() {
+                if !references.contains(reference_constant) {
+                    references.push(reference_constant.clone());
+                }
+            }
+        }
+
+        self.names
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown type {}", name))
+    }
+
+    /// Only unions of null and one other type are supported. They are mapped
+    /// to an [`Option`].
+    fn union_type(&mut self, variants: &[Value]) -> Result<String, String> {
+        let non_null: Vec<&Value> = variants
+            .iter()
+            .filter(|v| v.as_str() != Some("null"))
+            .collect();
+
+        match non_null[..] {
+            [variant] if non_null.len() < variants.len() => {
+                Ok(format!("Option<{}>", self.rust_type(variant)?))
+            }
+            [variant] => self.rust_type(variant),
+            _ => Err(format!(
+                "Only unions of null and one other type are supported: {:?}",
+                variants
+            )),
+        }
+    }
+}
+
+fn generate_module(event: &str, files: &[SchemaFile]) -> String {
+    let event_constant = event.to_uppercase();
+
+    let mut code = String::new();
+    let _ = writeln!(code, "pub mod schema_{} {{", event);
+
+    for file in files {
+        let suffix = constant_suffix(event, file.version);
+        let _ = writeln!(
+            code,
+            "pub const SCHEMA_NAME_{}: &str = \"{}\";\n",
+            suffix, file.record.avro_name
+        );
+        let _ = writeln!(
+            code,
+            "pub const RAW_SCHEMA_{}: &str = include_str!({:?});\n",
+            suffix,
+            file.path.display().to_string()
+        );
+        let _ = writeln!(
+            code,
+            "pub const REFERENCES_{}: &[kafka_schema_common::SchemaReference] = &[{}];\n",
+            suffix,
+            file.references.join(", ")
+        );
+        code.push_str(&file.record.code);
+    }
+
+    // Aliases for the latest version
+    if let Some(latest) = files.last().filter(|f| f.version.is_some()) {
+        let suffix = constant_suffix(event, latest.version);
+        for constant in ["SCHEMA_NAME", "RAW_SCHEMA"] {
+            let _ = writeln!(
+                code,
+                "pub const {0}_{1}: &str = {0}_{2};\n",
+                constant, event_constant, suffix
+            );
+        }
+        let _ = writeln!(
+            code,
+            "pub const REFERENCES_{}: &[kafka_schema_common::SchemaReference] = REFERENCES_{};\n",
+            event_constant, suffix
+        );
+        if let Some(unversioned) = strip_version(&latest.record.rust_name) {
+            let _ = writeln!(
+                code,
+                "pub type {} = {};",
+                unversioned, latest.record.rust_name
+            );
+        }
+    }
+
+    code.push_str("}\n\n");
+    code
+}
+
+/// Suffix of the constants of a schema file, e.g. `CREATE_USER_V1`.
+fn constant_suffix(event: &str, version: Option<u32>) -> String {
+    match version {
+        Some(version) => format!("{}_V{}", event.to_uppercase(), version),
+        None => event.to_uppercase(),
+    }
+}
+
+/// Registers the shared types of the directory to be generated on demand.
+fn load_types(generator: &mut Generator, directory: &Path) -> io::Result<()> {
+    let mut files = Vec::new();
+    collect_schema_files(directory, &mut files)?;
+
+    for path in files {
+        let schema = read_schema(&path)?;
+        let name = schema
+            .as_object()
+            .ok_or_else(|| invalid_schema(&path, "Expected a record or enum"))
+            .and_then(|o| name_of(o).map_err(|e| invalid_schema(&path, &e)))?;
+
+        generator
+            .pending_types
+            .insert(name, TypeFile { path, schema });
+    }
+    Ok(())
+}
+
+/// Collects the schema files of the directory and its subdirectories except
+/// the shared types.
+fn collect_schema_files(directory: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if path.file_name().and_then(|n| n.to_str()) != Some(TYPES_DIRECTORY) {
+                collect_schema_files(&path, files)?;
+            }
+        } else if path.extension().and_then(|e| e.to_str()) == Some("avsc") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_schema(path: &Path) -> io::Result<Value> {
+    let raw_schema = fs::read_to_string(path)?;
+    serde_json::from_str(&raw_schema)
+        .map_err(|e| invalid_schema(path, &format!("Schema is not valid json: {}", e)))
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Splits a file stem like `create_user_v2` into the event and the version.
+fn split_version(stem: &str) -> (String, Option<u32>) {
+    if let Some((event, version)) = stem.rsplit_once("_v") {
+        if let Ok(version) = version.parse() {
+            return (event.to_string(), Some(version));
+        }
+    }
+    (stem.to_string(), None)
+}
+
+/// Strips a version suffix like `V2` from a type name.
+fn strip_version(name: &str) -> Option<&str> {
+    let without_digits = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if without_digits.len() == name.len() {
+        return None;
+    }
+    without_digits
+        .strip_suffix('V')
+        .filter(|unversioned| !unversioned.is_empty())
+}
+
+fn name_of(object: &Map<String, Value>) -> Result<String, String> {
+    object
+        .get("name")
+        .and_then(|n| n.as_str())
+        // Namespaces are not part of the rust names
+        .map(|n| n.rsplit('.').next().unwrap_or(n).to_string())
+        .ok_or_else(|| format!("Missing name in {:?}", object))
+}
+
+/// Upper camel case name with `Avro` suffix, e.g. `phoneNumber` becomes
+/// `PhoneNumberAvro`.
+fn type_name(avro_name: &str) -> String {
+    let mut name = upper_camel_case(avro_name);
+    if !strip_version(&name).unwrap_or(&name).ends_with("Avro") {
+        name.push_str("Avro");
+    }
+    name
+}
+
+/// Upper camel case name of an enum symbol, e.g. `TWIN_SINGLE` becomes
+/// `TwinSingle`.
+fn variant_name(symbol: &str) -> String {
+    if symbol.contains('_') || symbol.chars().all(|c| !c.is_ascii_lowercase()) {
+        symbol
+            .split('_')
+            .filter(|w| !w.is_empty())
+            .map(|w| upper_camel_case(&w.to_ascii_lowercase()))
+            .collect()
+    } else {
+        upper_camel_case(symbol)
+    }
+}
+
+fn upper_camel_case(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Snake case name of a field, e.g. `phoneNumbers` becomes `phone_numbers`.
+fn field_name_of(avro_name: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in avro_name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+
+    if RUST_KEYWORDS.contains(&name.as_str()) {
+        format!("r#{}", name)
+    } else {
+        name
+    }
+}
+
+fn write_doc(code: &mut String, object: &Map<String, Value>, indent: &str) {
+    if let Some(doc) = object.get("doc").and_then(|d| d.as_str()) {
+        for line in doc.lines() {
+            let _ = writeln!(code, "{}/// {}", indent, line.trim());
+        }
+    }
+}
+
+fn invalid_schema(path: &Path, message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", path.display(), message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn test_resources(directory: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-resources")
+            .join(directory)
+    }
+
+    fn generate(codegen: Codegen, name: &str) -> io::Result<String> {
+        let out_file = env::temp_dir().join(format!("kafka-schema-codegen-{}.rs", name));
+        codegen.generate(&out_file)?;
+        let code = fs::read_to_string(&out_file)?;
+        fs::remove_file(&out_file)?;
+        Ok(code)
+    }
+
+    #[test]
+    fn versioned_schemas_with_aliases_for_latest_version() {
+        let code = generate(Codegen::new(&test_resources("schemas")), "versioned").unwrap();
+
+        assert!(code.contains("pub mod schema_create_booking {"));
+        assert!(code
+            .contains("pub const SCHEMA_NAME_CREATE_BOOKING_V1: &str = \"CreateBookingAvroV1\";"));
+        assert!(code
+            .contains("pub const SCHEMA_NAME_CREATE_BOOKING_V2: &str = \"CreateBookingAvroV2\";"));
+        assert!(code.contains("pub struct CreateBookingAvroV1 {"));
+        assert!(code.contains("pub struct CreateBookingAvroV2 {"));
+        assert!(code.contains(
+            "pub const SCHEMA_NAME_CREATE_BOOKING: &str = SCHEMA_NAME_CREATE_BOOKING_V2;"
+        ));
+        assert!(code
+            .contains("pub const RAW_SCHEMA_CREATE_BOOKING: &str = RAW_SCHEMA_CREATE_BOOKING_V2;"));
+        assert!(code.contains("pub type CreateBookingAvro = CreateBookingAvroV2;"));
+        assert!(code.contains(
+            "schema_create_booking::SCHEMA_NAME_CREATE_BOOKING_V1,\n    raw_schema: \
+             schema_create_booking::RAW_SCHEMA_CREATE_BOOKING_V1,"
+        ));
+    }
+
+    #[test]
+    fn unversioned_schema_without_alias() {
+        let code = generate(Codegen::new(&test_resources("schemas")), "unversioned").unwrap();
+
+        assert!(code.contains("pub mod schema_cancel_booking {"));
+        assert!(
+            code.contains("pub const SCHEMA_NAME_CANCEL_BOOKING: &str = \"CancelBookingAvro\";")
+        );
+        assert!(code.contains("pub struct CancelBookingAvro {"));
+        assert!(!code.contains("pub type CancelBookingAvro"));
+    }
+
+    #[test]
+    fn field_types() {
+        let code = generate(Codegen::new(&test_resources("schemas")), "field_types").unwrap();
+
+        assert!(code.contains("/// A booking of a room\n#[derive("));
+        assert!(code.contains("    pub nights: i32,"));
+        assert!(code.contains("    pub price: crate::PriceAvro,"));
+        assert!(code.contains("    pub r#type: crate::BookingTypeEnumAvro,"));
+        assert!(code
+            .contains("    #[serde(rename = \"guestNames\")]\n    pub guest_names: Vec<String>,"));
+        assert!(
+            code.contains("    pub extras: std::collections::HashMap<String, crate::PriceAvro>,")
+        );
+        assert!(code.contains("    pub comment: Option<String>,"));
+        assert!(code.contains("    pub guest: crate::GuestAvro,"));
+        assert!(code.contains("pub struct GuestAvro {\n    pub email: String,\n}"));
+        assert!(code.contains(
+            "pub enum BookingTypeEnumAvro {\n    #[serde(rename = \"LAST_MINUTE\")]\n    \
+             LastMinute,\n    Regular,\n}"
+        ));
+    }
+
+    #[test]
+    fn shared_types_are_referenced() {
+        let code = generate(Codegen::new(&test_resources("schemas")), "shared_types").unwrap();
+
+        assert!(code.contains("pub struct PriceAvro {"));
+        assert!(code.contains("pub const SCHEMA_NAME_PRICE: &str = \"price\";"));
+        assert!(code.contains(
+            "pub const REFERENCES_CREATE_BOOKING_V1: &[kafka_schema_common::SchemaReference] = \
+             &[crate::REFERENCE_PRICE];"
+        ));
+        assert!(code.contains(
+            "pub const REFERENCES_CANCEL_BOOKING: &[kafka_schema_common::SchemaReference] = &[];"
+        ));
+        assert!(code.contains(
+            "pub const REFERENCES: &[kafka_schema_common::SchemaReference] = \
+             &[crate::REFERENCE_PRICE];"
+        ));
+    }
+
+    #[test]
+    fn external_types_are_not_generated() {
+        let codegen = Codegen::new(&test_resources("schemas/booking"))
+            .with_external_types(&test_resources("schemas/types"), "other_crate");
+        let code = generate(codegen, "external_types").unwrap();
+
+        assert!(!code.contains("pub struct PriceAvro"));
+        assert!(code.contains("    pub price: other_crate::PriceAvro,"));
+        assert!(code.contains("&[other_crate::REFERENCE_PRICE];"));
+        assert!(
+            code.contains("pub const REFERENCES: &[kafka_schema_common::SchemaReference] = &[];")
+        );
+    }
+
+    #[test]
+    fn differently_defined_nested_types_are_rejected() {
+        let error =
+            generate(Codegen::new(&test_resources("conflicting")), "conflicting").unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error
+            .to_string()
+            .contains("AddressAvro is defined differently in multiple schemas"));
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            split_version("create_user_v2"),
+            ("create_user".to_string(), Some(2))
+        );
+        assert_eq!(split_version("key"), ("key".to_string(), None));
+        assert_eq!(strip_version("CreateUserAvroV12"), Some("CreateUserAvro"));
+        assert_eq!(strip_version("CreateUserAvro"), None);
+        assert_eq!(type_name("phoneNumber"), "PhoneNumberAvro");
+        assert_eq!(type_name("CreateUserAvroV1"), "CreateUserAvroV1");
+        assert_eq!(variant_name("TWIN_SINGLE"), "TwinSingle");
+        assert_eq!(variant_name("DE"), "De");
+        assert_eq!(field_name_of("phoneNumbers"), "phone_numbers");
+        assert_eq!(field_name_of("type"), "r#type");
+    }
+}
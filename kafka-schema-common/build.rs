@@ -0,0 +1,15 @@
+use std::env;
+use std::path::Path;
+
+use kafka_schema_codegen::Codegen;
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+
+    Codegen::new(&Path::new(&manifest_dir).join("resources"))
+        .generate(&Path::new(&out_dir).join("schemas.rs"))
+        .expect("Generation of avro types failed");
+
+    println!("cargo:rerun-if-changed=resources");
+}
@@ -0,0 +1,238 @@
+//! Offline compatibility checks of avro schemas.
+//!
+//! Versions of an event are stored as `{event}_v{version}.avsc` in the
+//! `resources` directory of the schema crates. The record name of a version
+//! carries the version as suffix (e.g. `CreateUserAvroV1`), so the record names
+//! are ignored when versions of an event are compared.
+//!
+//! Referenced types are inlined into the schemas before they are parsed.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use apache_avro::from_avro_datum;
+use apache_avro::from_value;
+use apache_avro::schema_compatibility::SchemaCompatibility;
+use apache_avro::to_avro_datum;
+use apache_avro::to_value;
+use apache_avro::Schema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::format::protobuf;
+use crate::format::SerializationFormat;
+use crate::reference::resolve_references;
+use crate::reference::SchemaReference;
+use crate::RecordSchema;
+
+/// Compatibility levels as defined by the schema registry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompatibilityLevel {
+    /// Data written with the previous schema can be read with the next schema.
+    Backward,
+    /// Data written with the next schema can be read with the previous schema.
+    Forward,
+    /// Backward and forward compatible.
+    Full,
+}
+
+#[derive(Clone, Debug)]
+pub struct SchemaVersion {
+    pub event: String,
+    pub version: u32,
+    pub path: PathBuf,
+    pub raw_schema: String,
+}
+
+/// Loads all schemas of the directory and its subdirectories grouped by event
+/// and ordered by version. Schemas without version suffix are version 1. The
+/// references are inlined into the raw schemas.
+pub fn load_versions(
+    directory: &Path,
+    references: &[SchemaReference],
+) -> io::Result<BTreeMap<String, Vec<SchemaVersion>>> {
+    let mut versions: BTreeMap<String, Vec<SchemaVersion>> = BTreeMap::new();
+    collect_versions(directory, references, &mut versions)?;
+
+    for event_versions in versions.values_mut() {
+        event_versions.sort_by_key(|v| v.version);
+    }
+    Ok(versions)
+}
+
+fn collect_versions(
+    directory: &Path,
+    references: &[SchemaReference],
+    versions: &mut BTreeMap<String, Vec<SchemaVersion>>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_versions(&path, references, versions)?;
+            continue;
+        }
+        if path.extension().and_then(|e| e.to_str()) != Some("avsc") {
+            continue;
+        }
+
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        let (event, version) = split_version(&stem);
+        let raw_schema = resolve_references(&fs::read_to_string(&path)?, references)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        versions
+            .entry(event.clone())
+            .or_default()
+            .push(SchemaVersion {
+                event,
+                version,
+                raw_schema,
+                path,
+            });
+    }
+    Ok(())
+}
+
+/// Splits a file stem like `create_user_v2` into the event and the version.
+fn split_version(stem: &str) -> (String, u32) {
+    if let Some((event, version)) = stem.rsplit_once("_v") {
+        if let Ok(version) = version.parse() {
+            return (event.to_string(), version);
+        }
+    }
+    (stem.to_string(), 1)
+}
+
+/// Checks whether the next version of a schema is compatible with the
+/// previous version.
+pub fn is_compatible(
+    previous_raw_schema: &str,
+    next_raw_schema: &str,
+    level: CompatibilityLevel,
+) -> Result<bool, apache_avro::Error> {
+    let previous = parse_unnamed(previous_raw_schema)?;
+    let next = parse_unnamed(next_raw_schema)?;
+
+    Ok(match level {
+        CompatibilityLevel::Backward => SchemaCompatibility::can_read(&previous, &next),
+        CompatibilityLevel::Forward => SchemaCompatibility::can_read(&next, &previous),
+        CompatibilityLevel::Full => SchemaCompatibility::mutual_read(&previous, &next),
+    })
+}
+
+/// Checks every pair of versions of every event. Returns a description of
+/// each incompatible pair.
+pub fn check_versions(
+    versions: &BTreeMap<String, Vec<SchemaVersion>>,
+    level: CompatibilityLevel,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for event_versions in versions.values() {
+        for (i, previous) in event_versions.iter().enumerate() {
+            for next in &event_versions[i + 1..] {
+                match is_compatible(&previous.raw_schema, &next.raw_schema, level) {
+                    Ok(true) => {}
+                    Ok(false) => errors.push(format!(
+                        "{} v{} is not {:?} compatible with v{}",
+                        next.event, next.version, level, previous.version
+                    )),
+                    Err(e) => errors.push(format!(
+                        "{} v{} or v{} is invalid: {}",
+                        next.event, previous.version, next.version, e
+                    )),
+                }
+            }
+        }
+    }
+    errors
+}
+
+/// Parses the schema with a common record name, as the record names of the
+/// versions differ by their version suffix.
+fn parse_unnamed(raw_schema: &str) -> Result<Schema, apache_avro::Error> {
+    let mut json: serde_json::Value =
+        serde_json::from_str(raw_schema).map_err(apache_avro::Error::ParseSchemaJson)?;
+    if let Some(object) = json.as_object_mut() {
+        if object.contains_key("name") {
+            object.insert("name".to_string(), "VersionedSchema".into());
+        }
+    }
+    Schema::parse(&json)
+}
+
+/// Encodes the value with the schema and decodes it again. Fails if the
+/// fields of the type don't match the schema.
+pub fn round_trip<T: Serialize + DeserializeOwned>(
+    raw_schema: &str,
+    references: &[SchemaReference],
+    value: &T,
+) -> Result<T, apache_avro::Error> {
+    let raw_schema =
+        resolve_references(raw_schema, references).map_err(apache_avro::Error::ParseSchemaJson)?;
+    let schema = Schema::parse_str(&raw_schema)?;
+    let encoded = to_avro_datum(&schema, to_value(value)?)?;
+    let decoded = from_avro_datum(&schema, &mut encoded.as_slice(), None)?;
+    from_value::<T>(&decoded)
+}
+
+/// Asserts that the value survives a [`round_trip`] through the schema.
+pub fn assert_round_trip<T: Serialize + DeserializeOwned>(
+    raw_schema: &str,
+    references: &[SchemaReference],
+    value: &T,
+) {
+    let decoded = round_trip(raw_schema, references, value)
+        .unwrap_or_else(|e| panic!("Round trip through schema failed: {}", e));
+
+    assert_eq!(
+        to_value(value).expect("Couldn't serialize value"),
+        to_value(&decoded).expect("Couldn't serialize decoded value")
+    );
+}
+
+/// Asserts that all versions of the schemas in the directory are fully
+/// compatible with each other. The references must contain all types that are
+/// referenced by the schemas.
+pub fn assert_versions_compatible(directory: &Path, references: &[SchemaReference]) {
+    let versions = load_versions(directory, references)
+        .unwrap_or_else(|e| panic!("Couldn't load schemas from {:?}: {}", directory, e));
+    assert!(!versions.is_empty(), "No schemas found in {:?}", directory);
+
+    for level in [
+        CompatibilityLevel::Backward,
+        CompatibilityLevel::Forward,
+        CompatibilityLevel::Full,
+    ] {
+        let errors = check_versions(&versions, level);
+        assert!(errors.is_empty(), "{}", errors.join("\n"));
+    }
+}
+
+/// Asserts that the JSON and Protobuf schemas can be derived from the avro
+/// schemas of the records.
+pub fn assert_formats_derivable(schemas: &[RecordSchema]) {
+    for schema in schemas {
+        for format in [
+            SerializationFormat::JsonSchema,
+            SerializationFormat::Protobuf,
+        ] {
+            if let Err(e) = format.schema_of(schema) {
+                panic!(
+                    "Couldn't derive {:?} schema of {}: {}",
+                    format, schema.name, e
+                );
+            }
+        }
+    }
+
+    if let Err(e) = protobuf::descriptor_pool(schemas) {
+        panic!("Couldn't build Protobuf descriptors: {}", e);
+    }
+}
@@ -0,0 +1,217 @@
+//! Derives JSON schemas (draft-07) from avro schemas.
+
+use std::collections::HashMap;
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::format::FormatError;
+
+/// Converts an avro schema without references into a JSON schema. The title
+/// of the JSON schema is the name of the avro record.
+pub fn to_json_schema(resolved_schema: &str) -> Result<String, FormatError> {
+    let schema: Value = serde_json::from_str(resolved_schema)?;
+
+    let mut json_schema = convert(&schema, &mut HashMap::new())?;
+    if let Some(object) = json_schema.as_object_mut() {
+        object.insert(
+            "$schema".to_string(),
+            "http://json-schema.org/draft-07/schema#".into(),
+        );
+    }
+    Ok(serde_json::to_string(&json_schema)?)
+}
+
+/// Converts an avro type. Named types are inlined again at every later usage.
+fn convert(schema: &Value, named_types: &mut HashMap<String, Value>) -> Result<Value, FormatError> {
+    match schema {
+        Value::String(name) => primitive(name)
+            .or_else(|| named_types.get(name).cloned())
+            .ok_or_else(|| FormatError::UnsupportedType(name.clone())),
+        // Union
+        Value::Array(variants) => Ok(json!({
+            "oneOf": variants
+                .iter()
+                .map(|v| convert(v, named_types))
+                .collect::<Result<Vec<Value>, FormatError>>()?
+        })),
+        Value::Object(object) => {
+            let schema_type = object
+                .get("type")
+                .ok_or_else(|| FormatError::UnsupportedType(schema.to_string()))?;
+
+            let converted = match schema_type.as_str() {
+                Some("record") => convert_record(object, named_types)?,
+                Some("enum") => json!({
+                    "type": "string",
+                    "enum": object.get("symbols").cloned().unwrap_or_default(),
+                }),
+                Some("array") => json!({
+                    "type": "array",
+                    "items": convert(object.get("items").unwrap_or(&Value::Null), named_types)?,
+                }),
+                Some("map") => json!({
+                    "type": "object",
+                    "additionalProperties":
+                        convert(object.get("values").unwrap_or(&Value::Null), named_types)?,
+                }),
+                Some("fixed") => json!({ "type": "string" }),
+                // Primitive types with logical type or nested type definitions
+                _ => convert(schema_type, named_types)?,
+            };
+
+            if let Some(name) = object.get("name").and_then(|n| n.as_str()) {
+                named_types.insert(name.to_string(), converted.clone());
+            }
+            Ok(converted)
+        }
+        _ => Err(FormatError::UnsupportedType(schema.to_string())),
+    }
+}
+
+/// Fields without default value are required.
+fn convert_record(
+    object: &Map<String, Value>,
+    named_types: &mut HashMap<String, Value>,
+) -> Result<Value, FormatError> {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    let fields = object
+        .get("fields")
+        .and_then(|f| f.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for field in &fields {
+        let name = field
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| FormatError::UnsupportedType(field.to_string()))?;
+
+        let mut property = convert(field.get("type").unwrap_or(&Value::Null), named_types)?;
+        if let (Some(doc), Some(property)) = (field.get("doc"), property.as_object_mut()) {
+            property.insert("description".to_string(), doc.clone());
+        }
+        properties.insert(name.to_string(), property);
+
+        if field.get("default").is_none() {
+            required.push(name.to_string());
+        }
+    }
+
+    let mut record = json!({
+        "title": object.get("name").cloned().unwrap_or_default(),
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    });
+    if let (Some(doc), Some(record)) = (object.get("doc"), record.as_object_mut()) {
+        record.insert("description".to_string(), doc.clone());
+    }
+    Ok(record)
+}
+
+fn primitive(name: &str) -> Option<Value> {
+    let json_type = match name {
+        "null" => "null",
+        "boolean" => "boolean",
+        "int" | "long" => "integer",
+        "float" | "double" => "number",
+        "bytes" | "string" => "string",
+        _ => return None,
+    };
+    Some(json!({ "type": json_type }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW_SCHEMA_BOOKING: &str = r#"{
+        "name": "BookingAvroV1",
+        "type": "record",
+        "doc": "A booking",
+        "fields": [
+            { "name": "identifier", "type": "string" },
+            { "name": "nights", "type": "int", "doc": "Number of nights" },
+            {
+                "name": "type",
+                "type": {
+                    "name": "BookingTypeEnumAvro",
+                    "type": "enum",
+                    "symbols": ["Regular", "LastMinute"]
+                }
+            },
+            { "name": "comment", "type": ["null", "string"], "default": null },
+            {
+                "name": "guests",
+                "type": {
+                    "type": "array",
+                    "items": {
+                        "name": "GuestAvro",
+                        "type": "record",
+                        "fields": [{ "name": "name", "type": "string" }]
+                    }
+                }
+            },
+            { "name": "host", "type": "GuestAvro" },
+            { "name": "prices", "type": { "type": "map", "values": "long" } },
+            { "name": "previousType", "type": ["null", "BookingTypeEnumAvro"], "default": null }
+        ]
+    }"#;
+
+    #[test]
+    fn record_with_all_types() {
+        let json_schema: Value =
+            serde_json::from_str(&to_json_schema(RAW_SCHEMA_BOOKING).unwrap()).unwrap();
+
+        let booking_type = json!({ "type": "string", "enum": ["Regular", "LastMinute"] });
+        let guest = json!({
+            "title": "GuestAvro",
+            "type": "object",
+            "properties": { "name": { "type": "string" } },
+            "required": ["name"],
+            "additionalProperties": false,
+        });
+        assert_eq!(
+            json_schema,
+            json!({
+                "$schema": "http://json-schema.org/draft-07/schema#",
+                "title": "BookingAvroV1",
+                "description": "A booking",
+                "type": "object",
+                "properties": {
+                    "identifier": { "type": "string" },
+                    "nights": { "type": "integer", "description": "Number of nights" },
+                    "type": booking_type,
+                    "comment": { "oneOf": [{ "type": "null" }, { "type": "string" }] },
+                    "guests": { "type": "array", "items": guest },
+                    "host": guest,
+                    "prices": {
+                        "type": "object",
+                        "additionalProperties": { "type": "integer" },
+                    },
+                    "previousType": { "oneOf": [{ "type": "null" }, booking_type] },
+                },
+                "required": ["identifier", "nights", "type", "guests", "host", "prices"],
+                "additionalProperties": false,
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_type_is_unsupported() {
+        let raw_schema = r#"{
+            "name": "BookingAvroV1",
+            "type": "record",
+            "fields": [{ "name": "host", "type": "GuestAvro" }]
+        }"#;
+
+        assert!(matches!(
+            to_json_schema(raw_schema),
+            Err(FormatError::UnsupportedType(t)) if t == "GuestAvro"
+        ));
+    }
+}
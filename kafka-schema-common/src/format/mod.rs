@@ -0,0 +1,180 @@
+//! Serialization formats of the records.
+//!
+//! The avro schemas are the source of the schemas of all formats. The JSON
+//! and Protobuf schemas are derived from them, so the generated avro types can
+//! be serialized in every format.
+
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use serde::Deserialize;
+
+use crate::reference::resolve_references;
+use crate::RecordSchema;
+
+pub mod json_schema;
+pub mod protobuf;
+
+/// Format of the records of a topic. Every format is backed by the schema
+/// registry.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializationFormat {
+    #[default]
+    Avro,
+    JsonSchema,
+    Protobuf,
+}
+
+impl SerializationFormat {
+    /// Subject of the schema of the record in this format. A subject only
+    /// contains schemas of one type, so JSON and Protobuf schemas are
+    /// registered with a suffix.
+    pub fn subject_name(&self, record_name: &str) -> String {
+        match self {
+            SerializationFormat::Avro => record_name.to_string(),
+            SerializationFormat::JsonSchema => format!("{}-json", record_name),
+            SerializationFormat::Protobuf => format!("{}-protobuf", record_name),
+        }
+    }
+
+    /// Derives the schema of the record in this format. The referenced types
+    /// are inlined.
+    pub fn schema_of(&self, record: &RecordSchema) -> Result<String, FormatError> {
+        let resolved_schema = resolve_references(record.raw_schema, record.references)?;
+        match self {
+            SerializationFormat::Avro => Ok(resolved_schema),
+            SerializationFormat::JsonSchema => json_schema::to_json_schema(&resolved_schema),
+            SerializationFormat::Protobuf => Ok(protobuf::to_proto(&protobuf::file_descriptor(
+                &resolved_schema,
+            )?)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FormatError {
+    InvalidSchema(serde_json::Error),
+    /// The avro schema contains a type that can't be represented in the format.
+    UnsupportedType(String),
+    InvalidDescriptor(prost_reflect::DescriptorError),
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::InvalidSchema(e) => write!(f, "Invalid schema: {}", e),
+            FormatError::UnsupportedType(t) => write!(f, "Unsupported type: {}", t),
+            FormatError::InvalidDescriptor(e) => write!(f, "Invalid descriptor: {}", e),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl From<serde_json::Error> for FormatError {
+    fn from(e: serde_json::Error) -> Self {
+        FormatError::InvalidSchema(e)
+    }
+}
+
+impl From<prost_reflect::DescriptorError> for FormatError {
+    fn from(e: prost_reflect::DescriptorError) -> Self {
+        FormatError::InvalidDescriptor(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::SchemaReference;
+    use crate::REFERENCE_ISO_COUNTRY_CODE;
+
+    const REFERENCE_ADDRESS: SchemaReference = SchemaReference {
+        name: "AddressAvro",
+        raw_schema: r#"{
+            "name": "AddressAvro",
+            "type": "record",
+            "fields": [
+                { "name": "city", "type": "string" },
+                { "name": "country", "type": "IsoCountryCodeEnumAvro" }
+            ]
+        }"#,
+        references: &[REFERENCE_ISO_COUNTRY_CODE],
+    };
+
+    const RECORD: RecordSchema = RecordSchema {
+        name: "MoveAvroV1",
+        raw_schema: r#"{
+            "name": "MoveAvroV1",
+            "type": "record",
+            "fields": [
+                { "name": "address", "type": "AddressAvro" },
+                { "name": "previousAddress", "type": ["null", "AddressAvro"], "default": null }
+            ]
+        }"#,
+        references: &[REFERENCE_ADDRESS],
+    };
+
+    #[test]
+    fn subject_names() {
+        assert_eq!(
+            SerializationFormat::Avro.subject_name("MoveAvroV1"),
+            "MoveAvroV1"
+        );
+        assert_eq!(
+            SerializationFormat::JsonSchema.subject_name("MoveAvroV1"),
+            "MoveAvroV1-json"
+        );
+        assert_eq!(
+            SerializationFormat::Protobuf.subject_name("MoveAvroV1"),
+            "MoveAvroV1-protobuf"
+        );
+    }
+
+    #[test]
+    fn json_schema_with_nested_references() {
+        let json_schema: Value =
+            serde_json::from_str(&SerializationFormat::JsonSchema.schema_of(&RECORD).unwrap())
+                .unwrap();
+
+        let address = json!({
+            "title": "AddressAvro",
+            "type": "object",
+            "properties": {
+                "city": { "type": "string" },
+                "country": { "type": "string", "enum": ["DE", "US"] },
+            },
+            "required": ["city", "country"],
+            "additionalProperties": false,
+        });
+        assert_eq!(json_schema["properties"]["address"], address);
+        assert_eq!(
+            json_schema["properties"]["previousAddress"],
+            json!({ "oneOf": [{ "type": "null" }, address] })
+        );
+    }
+
+    #[test]
+    fn protobuf_schema_with_nested_references() {
+        assert_eq!(
+            SerializationFormat::Protobuf.schema_of(&RECORD).unwrap(),
+            "syntax = \"proto3\";\n\npackage move_avro_v1;\n\nmessage MoveAvroV1 {\n  AddressAvro \
+             address = 1;\n  AddressAvro previousAddress = 2;\n}\n\nmessage AddressAvro {\n  \
+             string city = 1;\n  IsoCountryCodeEnumAvro country = 2;\n}\n\nenum \
+             IsoCountryCodeEnumAvro {\n  DE = 0;\n  US = 1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn avro_schema_with_nested_references() {
+        let avro_schema = SerializationFormat::Avro.schema_of(&RECORD).unwrap();
+
+        assert_eq!(avro_schema.matches("\"name\":\"AddressAvro\"").count(), 1);
+        assert_eq!(avro_schema.matches("\"symbols\"").count(), 1);
+    }
+}
@@ -0,0 +1,431 @@
+//! Derives Protobuf schemas from avro schemas.
+//!
+//! Each record schema becomes a proto file with its own package (e.g.
+//! `create_user_avro_v1`), so that types with the same name in different
+//! schemas don't collide. The top level record is the first message of the
+//! file. Nested records and enums are added as further messages and enums.
+//! Fields have the names of the avro fields and are numbered in the order of
+//! the avro fields.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use prost_reflect::DescriptorPool;
+use prost_types::field_descriptor_proto::Label;
+use prost_types::field_descriptor_proto::Type;
+use prost_types::DescriptorProto;
+use prost_types::EnumDescriptorProto;
+use prost_types::EnumValueDescriptorProto;
+use prost_types::FieldDescriptorProto;
+use prost_types::FileDescriptorProto;
+use prost_types::FileDescriptorSet;
+use prost_types::OneofDescriptorProto;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::format::FormatError;
+use crate::reference::resolve_references;
+use crate::RecordSchema;
+
+/// Package of the proto file of a record, e.g. `create_user_avro_v1` for
+/// `CreateUserAvroV1`.
+pub fn package_of(record_name: &str) -> String {
+    let mut package = String::new();
+    for (i, c) in record_name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                package.push('_');
+            }
+            package.push(c.to_ascii_lowercase());
+        } else {
+            package.push(c);
+        }
+    }
+    package
+}
+
+/// Fully qualified name of the message of a record.
+pub fn full_name(record_name: &str) -> String {
+    format!("{}.{}", package_of(record_name), record_name)
+}
+
+/// Name of the record of a fully qualified message name.
+pub fn record_name_of(full_name: &str) -> &str {
+    full_name.rsplit('.').next().unwrap_or(full_name)
+}
+
+/// Builds the descriptors of the messages of all records.
+pub fn descriptor_pool(schemas: &[RecordSchema]) -> Result<DescriptorPool, FormatError> {
+    let mut files = Vec::new();
+    for schema in schemas {
+        let resolved_schema = resolve_references(schema.raw_schema, schema.references)?;
+        files.push(file_descriptor(&resolved_schema)?);
+    }
+    Ok(DescriptorPool::from_file_descriptor_set(
+        FileDescriptorSet { file: files },
+    )?)
+}
+
+/// Converts an avro schema without references into a proto file.
+pub fn file_descriptor(resolved_schema: &str) -> Result<FileDescriptorProto, FormatError> {
+    let schema: Value = serde_json::from_str(resolved_schema)?;
+    let object = schema
+        .as_object()
+        .filter(|o| o.get("type").and_then(|t| t.as_str()) == Some("record"))
+        .ok_or_else(|| FormatError::UnsupportedType(schema.to_string()))?;
+
+    let package = package_of(&name_of(object)?);
+    let mut builder = FileBuilder {
+        package: package.clone(),
+        messages: vec![],
+        enums: vec![],
+        named_types: HashMap::new(),
+    };
+    builder.add_message(object)?;
+
+    Ok(FileDescriptorProto {
+        name: Some(format!("{}.proto", package)),
+        package: Some(package),
+        message_type: builder.messages,
+        enum_type: builder.enums,
+        syntax: Some("proto3".to_string()),
+        ..Default::default()
+    })
+}
+
+/// Renders the proto file to register it in the schema registry.
+pub fn to_proto(file: &FileDescriptorProto) -> String {
+    let prefix = format!(".{}.", file.package());
+
+    let mut proto = String::new();
+    let _ = writeln!(proto, "syntax = \"proto3\";\n");
+    let _ = writeln!(proto, "package {};", file.package());
+
+    for message in &file.message_type {
+        let _ = writeln!(proto, "\nmessage {} {{", message.name());
+        for field in &message.field {
+            let label = if field.label() == Label::Repeated {
+                "repeated "
+            } else if field.proto3_optional() {
+                "optional "
+            } else {
+                ""
+            };
+            let field_type = match field.r#type() {
+                Type::Message | Type::Enum => field
+                    .type_name()
+                    .strip_prefix(&prefix)
+                    .unwrap_or_else(|| field.type_name()),
+                Type::Bool => "bool",
+                Type::Int32 => "int32",
+                Type::Int64 => "int64",
+                Type::Float => "float",
+                Type::Double => "double",
+                Type::Bytes => "bytes",
+                _ => "string",
+            };
+            let _ = writeln!(
+                proto,
+                "  {}{} {} = {};",
+                label,
+                field_type,
+                field.name(),
+                field.number()
+            );
+        }
+        proto.push_str("}\n");
+    }
+
+    for enumeration in &file.enum_type {
+        let _ = writeln!(proto, "\nenum {} {{", enumeration.name());
+        for value in &enumeration.value {
+            let _ = writeln!(proto, "  {} = {};", value.name(), value.number());
+        }
+        proto.push_str("}\n");
+    }
+
+    proto
+}
+
+struct FileBuilder {
+    package: String,
+    messages: Vec<DescriptorProto>,
+    enums: Vec<EnumDescriptorProto>,
+    /// Fields referring to the named types by their avro name.
+    named_types: HashMap<String, FieldDescriptorProto>,
+}
+
+impl FileBuilder {
+    fn add_message(
+        &mut self,
+        object: &Map<String, Value>,
+    ) -> Result<FieldDescriptorProto, FormatError> {
+        let name = name_of(object)?;
+        let reference = typed(Type::Message, Some(self.type_name(&name)));
+        self.named_types.insert(name.clone(), reference.clone());
+
+        // Reserve the position, nested records are added while the fields are
+        // converted
+        let index = self.messages.len();
+        self.messages.push(DescriptorProto::default());
+
+        let mut message = DescriptorProto {
+            name: Some(name),
+            ..Default::default()
+        };
+
+        let fields = object
+            .get("fields")
+            .and_then(|f| f.as_array())
+            .cloned()
+            .unwrap_or_default();
+        for (i, avro_field) in fields.iter().enumerate() {
+            let field_name = avro_field
+                .get("name")
+                .and_then(|n| n.as_str())
+                .ok_or_else(|| FormatError::UnsupportedType(avro_field.to_string()))?;
+
+            let mut field = self.field(avro_field.get("type").unwrap_or(&Value::Null))?;
+            field.name = Some(field_name.to_string());
+            field.json_name = Some(field_name.to_string());
+            field.number = Some(i as i32 + 1);
+
+            // Optional fields are wrapped in a synthetic oneof
+            if field.proto3_optional() {
+                field.oneof_index = Some(message.oneof_decl.len() as i32);
+                message.oneof_decl.push(OneofDescriptorProto {
+                    name: Some(format!("_{}", field_name)),
+                    ..Default::default()
+                });
+            }
+            message.field.push(field);
+        }
+
+        self.messages[index] = message;
+        Ok(reference)
+    }
+
+    fn add_enum(
+        &mut self,
+        object: &Map<String, Value>,
+    ) -> Result<FieldDescriptorProto, FormatError> {
+        let name = name_of(object)?;
+        let reference = typed(Type::Enum, Some(self.type_name(&name)));
+        self.named_types.insert(name.clone(), reference.clone());
+
+        // The first symbol is the default value
+        let value = object
+            .get("symbols")
+            .and_then(|s| s.as_array())
+            .cloned()
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(|(i, symbol)| EnumValueDescriptorProto {
+                name: symbol.as_str().map(|s| s.to_string()),
+                number: Some(i as i32),
+                ..Default::default()
+            })
+            .collect();
+
+        self.enums.push(EnumDescriptorProto {
+            name: Some(name),
+            value,
+            ..Default::default()
+        });
+        Ok(reference)
+    }
+
+    /// Converts an avro type into a field without name and number.
+    fn field(&mut self, schema: &Value) -> Result<FieldDescriptorProto, FormatError> {
+        match schema {
+            Value::String(name) => self.primitive_or_named(name),
+            // Only unions of null and one other type are supported
+            Value::Array(variants) => {
+                let non_null: Vec<&Value> = variants
+                    .iter()
+                    .filter(|v| v.as_str() != Some("null"))
+                    .collect();
+
+                match non_null[..] {
+                    [variant] => {
+                        let mut field = self.field(variant)?;
+                        // Messages have presence and repeated fields can't be
+                        // optional
+                        if non_null.len() < variants.len()
+                            && field.r#type() != Type::Message
+                            && field.label() != Label::Repeated
+                        {
+                            field.proto3_optional = Some(true);
+                        }
+                        Ok(field)
+                    }
+                    _ => Err(FormatError::UnsupportedType(schema.to_string())),
+                }
+            }
+            Value::Object(object) => {
+                let schema_type = object
+                    .get("type")
+                    .ok_or_else(|| FormatError::UnsupportedType(schema.to_string()))?;
+
+                match schema_type.as_str() {
+                    Some("record") => self.add_message(object),
+                    Some("enum") => self.add_enum(object),
+                    Some("array") => {
+                        let mut field = self.field(object.get("items").unwrap_or(&Value::Null))?;
+                        if field.label() == Label::Repeated || field.proto3_optional() {
+                            return Err(FormatError::UnsupportedType(schema.to_string()));
+                        }
+                        field.label = Some(Label::Repeated as i32);
+                        Ok(field)
+                    }
+                    Some("map") | Some("fixed") => {
+                        Err(FormatError::UnsupportedType(schema.to_string()))
+                    }
+                    // Primitive types with logical type or nested type definitions
+                    _ => self.field(schema_type),
+                }
+            }
+            _ => Err(FormatError::UnsupportedType(schema.to_string())),
+        }
+    }
+
+    fn primitive_or_named(&self, name: &str) -> Result<FieldDescriptorProto, FormatError> {
+        let field_type = match name {
+            "boolean" => Type::Bool,
+            "int" => Type::Int32,
+            "long" => Type::Int64,
+            "float" => Type::Float,
+            "double" => Type::Double,
+            "bytes" => Type::Bytes,
+            "string" => Type::String,
+            _ => {
+                return self
+                    .named_types
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| FormatError::UnsupportedType(name.to_string()))
+            }
+        };
+        Ok(typed(field_type, None))
+    }
+
+    fn type_name(&self, name: &str) -> String {
+        format!(".{}.{}", self.package, name)
+    }
+}
+
+fn typed(field_type: Type, type_name: Option<String>) -> FieldDescriptorProto {
+    FieldDescriptorProto {
+        label: Some(Label::Optional as i32),
+        r#type: Some(field_type as i32),
+        type_name,
+        ..Default::default()
+    }
+}
+
+fn name_of(object: &Map<String, Value>) -> Result<String, FormatError> {
+    object
+        .get("name")
+        .and_then(|n| n.as_str())
+        // Namespaces are replaced by the package
+        .map(|n| n.rsplit('.').next().unwrap_or(n).to_string())
+        .ok_or_else(|| FormatError::UnsupportedType(format!("{:?}", object)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW_SCHEMA_BOOKING: &str = r#"{
+        "name": "BookingAvroV1",
+        "type": "record",
+        "fields": [
+            { "name": "identifier", "type": "string" },
+            { "name": "nights", "type": "int" },
+            {
+                "name": "type",
+                "type": {
+                    "name": "BookingTypeEnumAvro",
+                    "type": "enum",
+                    "symbols": ["Regular", "LastMinute"]
+                }
+            },
+            { "name": "comment", "type": ["null", "string"], "default": null },
+            {
+                "name": "guests",
+                "type": {
+                    "type": "array",
+                    "items": {
+                        "name": "GuestAvro",
+                        "type": "record",
+                        "fields": [{ "name": "name", "type": "string" }]
+                    }
+                }
+            },
+            { "name": "host", "type": ["null", "GuestAvro"], "default": null },
+            { "name": "previousType", "type": ["null", "BookingTypeEnumAvro"], "default": null }
+        ]
+    }"#;
+
+    #[test]
+    fn record_with_all_types() {
+        let file = file_descriptor(RAW_SCHEMA_BOOKING).unwrap();
+
+        assert_eq!(file.package(), "booking_avro_v1");
+        assert_eq!(
+            to_proto(&file),
+            "syntax = \"proto3\";\n\npackage booking_avro_v1;\n\nmessage BookingAvroV1 {\n  \
+             string identifier = 1;\n  int32 nights = 2;\n  BookingTypeEnumAvro type = 3;\n  \
+             optional string comment = 4;\n  repeated GuestAvro guests = 5;\n  GuestAvro host = \
+             6;\n  optional BookingTypeEnumAvro previousType = 7;\n}\n\nmessage GuestAvro {\n  \
+             string name = 1;\n}\n\nenum BookingTypeEnumAvro {\n  Regular = 0;\n  LastMinute = \
+             1;\n}\n"
+        );
+
+        // Optional fields are wrapped in synthetic oneofs
+        let booking = &file.message_type[0];
+        assert_eq!(
+            booking
+                .oneof_decl
+                .iter()
+                .map(|o| o.name())
+                .collect::<Vec<_>>(),
+            vec!["_comment", "_previousType"]
+        );
+        assert_eq!(booking.field[6].oneof_index, Some(1));
+    }
+
+    #[test]
+    fn map_is_unsupported() {
+        let raw_schema = r#"{
+            "name": "PricesAvroV1",
+            "type": "record",
+            "fields": [{ "name": "prices", "type": { "type": "map", "values": "long" } }]
+        }"#;
+
+        assert!(matches!(
+            file_descriptor(raw_schema),
+            Err(FormatError::UnsupportedType(_))
+        ));
+    }
+
+    #[test]
+    fn descriptors_of_records() {
+        let pool = descriptor_pool(&[RecordSchema {
+            name: "BookingAvroV1",
+            raw_schema: RAW_SCHEMA_BOOKING,
+            references: &[],
+        }])
+        .unwrap();
+
+        let booking = pool
+            .get_message_by_name(&full_name("BookingAvroV1"))
+            .unwrap();
+        assert!(booking.get_field_by_name("guests").unwrap().is_list());
+        assert!(pool
+            .get_enum_by_name("booking_avro_v1.BookingTypeEnumAvro")
+            .is_some());
+        assert_eq!(record_name_of(booking.full_name()), "BookingAvroV1");
+    }
+}
@@ -1,12 +1,179 @@
-use serde::Deserialize;
-use serde::Serialize;
+// Allows the generated code to refer to this crate by its name
+extern crate self as kafka_schema_common;
 
-pub mod schema_key;
+pub mod compatibility;
+pub mod format;
+pub mod record;
+pub mod reference;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct IdentifierAvro {
-    pub data_type: String,
-    pub identifier: String,
-    pub version: i64,
+pub use record::RecordSchema;
+pub use reference::SchemaReference;
+
+// Avro types generated from the schemas in the resources directory
+include!(concat!(env!("OUT_DIR"), "/schemas.rs"));
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use apache_avro::Schema;
+    use serde::Deserialize;
+    use serde::Serialize;
+
+    use super::*;
+    use crate::compatibility::*;
+    use crate::reference::resolve_references;
+    use crate::schema_key::*;
+
+    const RAW_SCHEMA_V1: &str = r#"{
+        "name": "TestAvroV1",
+        "type": "record",
+        "fields": [{ "name": "name", "type": "string" }]
+    }"#;
+
+    const RAW_SCHEMA_V2_WITH_DEFAULT: &str = r#"{
+        "name": "TestAvroV2",
+        "type": "record",
+        "fields": [
+            { "name": "name", "type": "string" },
+            { "name": "email", "type": ["null", "string"], "default": null }
+        ]
+    }"#;
+
+    const RAW_SCHEMA_V2_WITHOUT_DEFAULT: &str = r#"{
+        "name": "TestAvroV2",
+        "type": "record",
+        "fields": [
+            { "name": "name", "type": "string" },
+            { "name": "email", "type": "string" }
+        ]
+    }"#;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    struct TestAvro {
+        name: String,
+        email: String,
+    }
+
+    #[test]
+    fn key_round_trip() {
+        assert_round_trip(RAW_SCHEMA_KEY, REFERENCES_KEY, &KeyAvro {
+            context_identifier: "d8f1a4a7-6f0a-4b2c-9a53-2fa0f1c1b7a1".to_string(),
+            identifier: IdentifierAvro {
+                data_type: "user".to_string(),
+                identifier: "5b4c0b7e-8f1d-4e63-9d2c-0d6b7f0bde27".to_string(),
+                version: 3,
+            },
+        });
+    }
+
+    #[test]
+    fn schema_versions_compatible() {
+        assert_versions_compatible(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("resources"),
+            REFERENCES,
+        );
+    }
+
+    #[test]
+    fn schemas_derivable_in_all_formats() {
+        assert_formats_derivable(SCHEMAS);
+    }
+
+    #[test]
+    fn optional_field_with_default_is_full_compatible() {
+        assert!(is_compatible(
+            RAW_SCHEMA_V1,
+            RAW_SCHEMA_V2_WITH_DEFAULT,
+            CompatibilityLevel::Full
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn field_without_default_is_not_backward_compatible() {
+        assert!(!is_compatible(
+            RAW_SCHEMA_V1,
+            RAW_SCHEMA_V2_WITHOUT_DEFAULT,
+            CompatibilityLevel::Backward
+        )
+        .unwrap());
+        assert!(is_compatible(
+            RAW_SCHEMA_V1,
+            RAW_SCHEMA_V2_WITHOUT_DEFAULT,
+            CompatibilityLevel::Forward
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn versions_with_optional_field_are_compatible() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/compatible");
+        let versions = load_versions(&directory, REFERENCES).unwrap();
+        assert_eq!(
+            versions["change_address"]
+                .iter()
+                .map(|v| v.version)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        for level in [
+            CompatibilityLevel::Backward,
+            CompatibilityLevel::Forward,
+            CompatibilityLevel::Full,
+        ] {
+            assert!(check_versions(&versions, level).is_empty());
+        }
+        assert_versions_compatible(&directory, REFERENCES);
+    }
+
+    #[test]
+    fn versions_with_required_field_are_incompatible() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/incompatible");
+        let versions = load_versions(&directory, REFERENCES).unwrap();
+
+        assert_eq!(
+            check_versions(&versions, CompatibilityLevel::Backward),
+            vec!["change_address v2 is not Backward compatible with v1".to_string()]
+        );
+        assert!(check_versions(&versions, CompatibilityLevel::Forward).is_empty());
+        assert_eq!(check_versions(&versions, CompatibilityLevel::Full), vec![
+            "change_address v2 is not Full compatible with v1".to_string()
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "change_address v2 is not Backward compatible with v1")]
+    fn assert_versions_compatible_fails_for_incompatible_versions() {
+        assert_versions_compatible(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("test-resources/incompatible"),
+            REFERENCES,
+        );
+    }
+
+    #[test]
+    fn round_trip_fails_for_struct_not_matching_schema() {
+        let value = TestAvro {
+            name: "name".to_string(),
+            email: "mail@example.com".to_string(),
+        };
+        assert!(round_trip(RAW_SCHEMA_V1, &[], &value).is_err());
+    }
+
+    #[test]
+    fn references_are_inlined_at_first_usage() {
+        let raw_schema = r#"{
+            "name": "AddressAvro",
+            "type": "record",
+            "fields": [
+                { "name": "country", "type": "IsoCountryCodeEnumAvro" },
+                { "name": "previousCountry", "type": ["null", "IsoCountryCodeEnumAvro"] }
+            ]
+        }"#;
+
+        let resolved = resolve_references(raw_schema, REFERENCES).unwrap();
+        assert_eq!(resolved.matches("\"symbols\"").count(), 1);
+        assert!(Schema::parse_str(&resolved).is_ok());
+    }
 }
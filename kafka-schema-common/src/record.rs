@@ -0,0 +1,11 @@
+use crate::reference::SchemaReference;
+
+/// Schema of a record that is written to the topics, e.g. a version of an
+/// event.
+#[derive(Clone, Copy, Debug)]
+pub struct RecordSchema {
+    /// Name of the record, that is also the subject of its avro schema.
+    pub name: &'static str,
+    pub raw_schema: &'static str,
+    pub references: &'static [SchemaReference],
+}
@@ -0,0 +1,97 @@
+//! References between schemas.
+//!
+//! Types that are shared between schemas (e.g. `IsoCountryCodeEnumAvro`) are
+//! defined once in the `resources/types` directory of the schema crates and
+//! registered as separate subjects. Other schemas refer to them by their name.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use serde_json::Value;
+
+/// A named type defined in a separate schema, that is referenced by its name.
+#[derive(Clone, Copy, Debug)]
+pub struct SchemaReference {
+    /// Name of the referenced type, that is also the subject of its schema.
+    pub name: &'static str,
+    pub raw_schema: &'static str,
+    /// References of the referenced schema.
+    pub references: &'static [SchemaReference],
+}
+
+/// Inlines the referenced types into the schema, so that it can be parsed
+/// without the referenced schemas. Each type is inlined at its first usage,
+/// later usages keep referring to it by name.
+pub fn resolve_references(
+    raw_schema: &str,
+    references: &[SchemaReference],
+) -> Result<String, serde_json::Error> {
+    let mut referenced_schemas = HashMap::new();
+    collect_references(references, &mut referenced_schemas);
+    resolve_named_types(raw_schema, &referenced_schemas)
+}
+
+/// Inlines the referenced types like [`resolve_references`]. The raw schemas
+/// of the referenced types are mapped by their name, e.g. for schemas that are
+/// not known at compile time.
+pub fn resolve_named_types(
+    raw_schema: &str,
+    referenced_schemas: &HashMap<&str, &str>,
+) -> Result<String, serde_json::Error> {
+    let mut schema: Value = serde_json::from_str(raw_schema)?;
+    inline_references(&mut schema, referenced_schemas, &mut HashSet::new())?;
+    serde_json::to_string(&schema)
+}
+
+fn collect_references(
+    references: &[SchemaReference],
+    referenced_schemas: &mut HashMap<&'static str, &'static str>,
+) {
+    for reference in references {
+        referenced_schemas.insert(reference.name, reference.raw_schema);
+        collect_references(reference.references, referenced_schemas);
+    }
+}
+
+fn inline_references(
+    schema: &mut Value,
+    referenced_schemas: &HashMap<&str, &str>,
+    defined_names: &mut HashSet<String>,
+) -> Result<(), serde_json::Error> {
+    match schema {
+        Value::String(name) => {
+            if let Some(raw_schema) = referenced_schemas.get(name.as_str()) {
+                if defined_names.insert(name.clone()) {
+                    let mut referenced_schema: Value = serde_json::from_str(raw_schema)?;
+                    inline_references(&mut referenced_schema, referenced_schemas, defined_names)?;
+                    *schema = referenced_schema;
+                }
+            }
+        }
+        // Union
+        Value::Array(variants) => {
+            for variant in variants {
+                inline_references(variant, referenced_schemas, defined_names)?;
+            }
+        }
+        Value::Object(object) => {
+            if let Some(name) = object.get("name").and_then(|n| n.as_str()) {
+                defined_names.insert(name.to_string());
+            }
+            for key in ["type", "items", "values"] {
+                if let Some(value) = object.get_mut(key) {
+                    inline_references(value, referenced_schemas, defined_names)?;
+                }
+            }
+            if let Some(Value::Array(fields)) = object.get_mut("fields") {
+                for field in fields {
+                    if let Some(field_type) = field.get_mut("type") {
+                        inline_references(field_type, referenced_schemas, defined_names)?;
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
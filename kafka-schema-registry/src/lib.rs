@@ -0,0 +1,12 @@
+//! Stand-in for the Confluent schema registry, so that records can be encoded
+//! and decoded in tests and local development without a running registry.
+//!
+//! The [`InMemorySchemaRegistry`] can be used directly or served over HTTP with
+//! the [`EmbeddedSchemaRegistry`], whose url is used as `schema_registry.url`
+//! of the encoders and decoders.
+
+pub mod registry;
+pub mod server;
+
+pub use registry::InMemorySchemaRegistry;
+pub use server::EmbeddedSchemaRegistry;
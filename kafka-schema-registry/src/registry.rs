@@ -0,0 +1,598 @@
+//! Schema registry keeping the schemas in memory.
+//!
+//! Avro schemas are validated and checked against the compatibility level of
+//! their subject. JSON and Protobuf schemas are only stored, they are neither
+//! validated nor checked for compatibility.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+
+use kafka_schema_common::compatibility::is_compatible;
+use kafka_schema_common::compatibility::CompatibilityLevel;
+use kafka_schema_common::format::SerializationFormat;
+use kafka_schema_common::reference::resolve_named_types;
+use kafka_schema_common::RecordSchema;
+use kafka_schema_common::SchemaReference;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SchemaType {
+    #[default]
+    Avro,
+    Json,
+    Protobuf,
+}
+
+impl SchemaType {
+    fn is_avro(&self) -> bool {
+        *self == SchemaType::Avro
+    }
+}
+
+impl From<SerializationFormat> for SchemaType {
+    fn from(format: SerializationFormat) -> Self {
+        match format {
+            SerializationFormat::Avro => SchemaType::Avro,
+            SerializationFormat::JsonSchema => SchemaType::Json,
+            SerializationFormat::Protobuf => SchemaType::Protobuf,
+        }
+    }
+}
+
+/// Reference to a version of another subject defining the named type.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Reference {
+    pub name: String,
+    pub subject: String,
+    pub version: u32,
+}
+
+/// Schema as it is sent to and returned by the schema registry. The schema
+/// type is omitted for avro schemas.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    pub schema: String,
+    #[serde(default, skip_serializing_if = "SchemaType::is_avro")]
+    pub schema_type: SchemaType,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Reference>,
+}
+
+impl Schema {
+    pub fn avro(schema: &str) -> Schema {
+        Schema {
+            schema: schema.to_string(),
+            schema_type: SchemaType::Avro,
+            references: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SubjectVersion {
+    pub subject: String,
+    pub id: u32,
+    pub version: u32,
+    #[serde(flatten)]
+    pub schema: Schema,
+}
+
+/// Compatibility levels of the subjects. Transitive levels check a schema
+/// against all versions of the subject instead of the latest version only.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Compatibility {
+    None,
+    #[default]
+    Backward,
+    BackwardTransitive,
+    Forward,
+    ForwardTransitive,
+    Full,
+    FullTransitive,
+}
+
+impl Compatibility {
+    /// Level of the checks and whether they are transitive.
+    fn level(&self) -> Option<(CompatibilityLevel, bool)> {
+        match self {
+            Compatibility::None => None,
+            Compatibility::Backward => Some((CompatibilityLevel::Backward, false)),
+            Compatibility::BackwardTransitive => Some((CompatibilityLevel::Backward, true)),
+            Compatibility::Forward => Some((CompatibilityLevel::Forward, false)),
+            Compatibility::ForwardTransitive => Some((CompatibilityLevel::Forward, true)),
+            Compatibility::Full => Some((CompatibilityLevel::Full, false)),
+            Compatibility::FullTransitive => Some((CompatibilityLevel::Full, true)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Version {
+    Latest,
+    Number(u32),
+}
+
+impl FromStr for Version {
+    type Err = RegistryError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        match version {
+            "latest" => Ok(Version::Latest),
+            _ => version
+                .parse()
+                .map(Version::Number)
+                .map_err(|_| RegistryError::InvalidVersion(version.to_string())),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RegistryError {
+    SubjectNotFound(String),
+    VersionNotFound(String, u32),
+    SchemaNotFound,
+    InvalidVersion(String),
+    InvalidSchema(String),
+    /// The schema is incompatible with previous versions of the subject.
+    Incompatible(Vec<String>),
+}
+
+impl RegistryError {
+    /// Error code of the schema registry REST API.
+    pub fn error_code(&self) -> u32 {
+        match self {
+            RegistryError::SubjectNotFound(_) => 40401,
+            RegistryError::VersionNotFound(_, _) => 40402,
+            RegistryError::SchemaNotFound => 40403,
+            RegistryError::InvalidVersion(_) => 42202,
+            RegistryError::InvalidSchema(_) => 42201,
+            RegistryError::Incompatible(_) => 409,
+        }
+    }
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::SubjectNotFound(subject) => {
+                write!(f, "Subject '{}' not found.", subject)
+            }
+            RegistryError::VersionNotFound(subject, version) => {
+                write!(f, "Version {} of subject '{}' not found.", version, subject)
+            }
+            RegistryError::SchemaNotFound => write!(f, "Schema not found."),
+            RegistryError::InvalidVersion(version) => {
+                write!(f, "Version '{}' is not valid.", version)
+            }
+            RegistryError::InvalidSchema(e) => write!(f, "Invalid schema: {}", e),
+            RegistryError::Incompatible(messages) => write!(
+                f,
+                "Schema being registered is incompatible with an earlier schema: {}",
+                messages.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for RegistryError {}
+
+/// In-memory stand-in for the schema registry. Clones share the registered
+/// schemas.
+#[derive(Clone, Default)]
+pub struct InMemorySchemaRegistry {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemorySchemaRegistry {
+    pub fn new() -> InMemorySchemaRegistry {
+        Self::default()
+    }
+
+    /// Registers the schema as the next version of the subject and returns
+    /// its id. Registering a schema again returns the id of the existing
+    /// version. Identical schemas of different subjects share their id.
+    pub fn register(&self, subject: &str, schema: Schema) -> Result<u32, RegistryError> {
+        let mut state = self.state();
+        if let Ok(version) = state.lookup(subject, &schema) {
+            return Ok(version.id);
+        }
+
+        let resolved_schema = state.resolve(&schema)?;
+        if let Some((level, transitive)) = state.compatibility_of(subject).level() {
+            let versions = state.versions(subject).unwrap_or_default();
+            let checked_versions = if transitive {
+                &versions[..]
+            } else {
+                &versions[versions.len().saturating_sub(1)..]
+            };
+
+            let messages =
+                state.incompatibilities(&schema, &resolved_schema, checked_versions, level)?;
+            if !messages.is_empty() {
+                return Err(RegistryError::Incompatible(messages));
+            }
+        }
+
+        let id = match state.schemas.iter().position(|s| *s == schema) {
+            Some(index) => index as u32 + 1,
+            None => {
+                state.schemas.push(schema);
+                state.schemas.len() as u32
+            }
+        };
+        state
+            .subjects
+            .entry(subject.to_string())
+            .or_default()
+            .push(id);
+        Ok(id)
+    }
+
+    /// Returns the version of the subject with the schema.
+    pub fn lookup(&self, subject: &str, schema: &Schema) -> Result<SubjectVersion, RegistryError> {
+        self.state().lookup(subject, schema)
+    }
+
+    pub fn schema_by_id(&self, id: u32) -> Result<Schema, RegistryError> {
+        self.state().schema(id).cloned()
+    }
+
+    pub fn version(
+        &self,
+        subject: &str,
+        version: Version,
+    ) -> Result<SubjectVersion, RegistryError> {
+        self.state().version(subject, version)
+    }
+
+    pub fn subjects(&self) -> Vec<String> {
+        self.state().subjects.keys().cloned().collect()
+    }
+
+    /// Returns the version numbers of the subject.
+    pub fn versions(&self, subject: &str) -> Result<Vec<u32>, RegistryError> {
+        Ok(self
+            .state()
+            .versions(subject)?
+            .iter()
+            .map(|v| v.version)
+            .collect())
+    }
+
+    /// Checks the schema against a version of the subject with the
+    /// compatibility level of the subject. Returns the reasons why the schema
+    /// is incompatible, that is an empty list if it is compatible.
+    pub fn check_compatibility(
+        &self,
+        subject: &str,
+        version: Version,
+        schema: &Schema,
+    ) -> Result<Vec<String>, RegistryError> {
+        let state = self.state();
+        let checked_version = state.version(subject, version)?;
+        let resolved_schema = state.resolve(schema)?;
+
+        match state.compatibility_of(subject).level() {
+            Some((level, _)) => {
+                state.incompatibilities(schema, &resolved_schema, &[checked_version], level)
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns the compatibility level of the subject, or the global level if
+    /// no subject is given.
+    pub fn compatibility(&self, subject: Option<&str>) -> Compatibility {
+        let state = self.state();
+        match subject {
+            Some(subject) => state.compatibility_of(subject),
+            None => state.default_compatibility,
+        }
+    }
+
+    /// Sets the compatibility level of the subject, or the global level if no
+    /// subject is given.
+    pub fn set_compatibility(&self, subject: Option<&str>, compatibility: Compatibility) {
+        let mut state = self.state();
+        match subject {
+            Some(subject) => {
+                state
+                    .compatibility
+                    .insert(subject.to_string(), compatibility);
+            }
+            None => state.default_compatibility = compatibility,
+        }
+    }
+
+    /// Registers the schemas of the records in the formats like the
+    /// `app-kafka-schema-publisher`. The referenced types of the avro schemas
+    /// are registered as separate subjects.
+    pub fn register_record_schemas(
+        &self,
+        schemas: &[RecordSchema],
+        formats: &[SerializationFormat],
+    ) -> Result<(), RegistryError> {
+        for record_schema in schemas {
+            for format in formats {
+                match format {
+                    SerializationFormat::Avro => {
+                        let schema = Schema {
+                            references: self.register_references(record_schema.references)?,
+                            ..Schema::avro(record_schema.raw_schema)
+                        };
+                        self.register(record_schema.name, schema)?;
+                    }
+                    _ => {
+                        let schema = Schema {
+                            schema: format
+                                .schema_of(record_schema)
+                                .map_err(|e| RegistryError::InvalidSchema(e.to_string()))?,
+                            schema_type: (*format).into(),
+                            references: vec![],
+                        };
+                        self.register(&format.subject_name(record_schema.name), schema)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn register_references(
+        &self,
+        references: &[SchemaReference],
+    ) -> Result<Vec<Reference>, RegistryError> {
+        let mut registered_references = Vec::new();
+        for reference in references {
+            let schema = Schema {
+                references: self.register_references(reference.references)?,
+                ..Schema::avro(reference.raw_schema)
+            };
+            self.register(reference.name, schema.clone())?;
+
+            registered_references.push(Reference {
+                name: reference.name.to_string(),
+                subject: reference.name.to_string(),
+                version: self.lookup(reference.name, &schema)?.version,
+            });
+        }
+        Ok(registered_references)
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Schemas by their id, ids start at 1.
+    schemas: Vec<Schema>,
+    /// Ids of the schemas of the versions of the subjects.
+    subjects: BTreeMap<String, Vec<u32>>,
+    default_compatibility: Compatibility,
+    compatibility: HashMap<String, Compatibility>,
+}
+
+impl State {
+    fn schema(&self, id: u32) -> Result<&Schema, RegistryError> {
+        id.checked_sub(1)
+            .and_then(|index| self.schemas.get(index as usize))
+            .ok_or(RegistryError::SchemaNotFound)
+    }
+
+    fn versions(&self, subject: &str) -> Result<Vec<SubjectVersion>, RegistryError> {
+        let ids = self
+            .subjects
+            .get(subject)
+            .ok_or_else(|| RegistryError::SubjectNotFound(subject.to_string()))?;
+
+        ids.iter()
+            .enumerate()
+            .map(|(index, id)| {
+                Ok(SubjectVersion {
+                    subject: subject.to_string(),
+                    id: *id,
+                    version: index as u32 + 1,
+                    schema: self.schema(*id)?.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn version(&self, subject: &str, version: Version) -> Result<SubjectVersion, RegistryError> {
+        let mut versions = self.versions(subject)?;
+        let number = match version {
+            Version::Latest => versions.len() as u32,
+            Version::Number(number) => number,
+        };
+
+        if number == 0 || number as usize > versions.len() {
+            return Err(RegistryError::VersionNotFound(subject.to_string(), number));
+        }
+        Ok(versions.swap_remove(number as usize - 1))
+    }
+
+    fn lookup(&self, subject: &str, schema: &Schema) -> Result<SubjectVersion, RegistryError> {
+        self.versions(subject)?
+            .into_iter()
+            .find(|v| v.schema == *schema)
+            .ok_or(RegistryError::SchemaNotFound)
+    }
+
+    fn compatibility_of(&self, subject: &str) -> Compatibility {
+        self.compatibility
+            .get(subject)
+            .copied()
+            .unwrap_or(self.default_compatibility)
+    }
+
+    /// Validates the schema. Referenced types of avro schemas are inlined.
+    fn resolve(&self, schema: &Schema) -> Result<String, RegistryError> {
+        match schema.schema_type {
+            SchemaType::Avro => {
+                let mut referenced_schemas = HashMap::new();
+                self.collect_references(&schema.references, &mut referenced_schemas)?;
+                let referenced_schemas = referenced_schemas
+                    .iter()
+                    .map(|(name, raw_schema)| (name.as_str(), raw_schema.as_str()))
+                    .collect();
+
+                let resolved_schema = resolve_named_types(&schema.schema, &referenced_schemas)
+                    .map_err(|e| RegistryError::InvalidSchema(e.to_string()))?;
+                apache_avro::Schema::parse_str(&resolved_schema)
+                    .map_err(|e| RegistryError::InvalidSchema(e.to_string()))?;
+                Ok(resolved_schema)
+            }
+            SchemaType::Json => {
+                serde_json::from_str::<serde_json::Value>(&schema.schema)
+                    .map_err(|e| RegistryError::InvalidSchema(e.to_string()))?;
+                Ok(schema.schema.clone())
+            }
+            SchemaType::Protobuf => Ok(schema.schema.clone()),
+        }
+    }
+
+    fn collect_references(
+        &self,
+        references: &[Reference],
+        referenced_schemas: &mut HashMap<String, String>,
+    ) -> Result<(), RegistryError> {
+        for reference in references {
+            let referenced_version =
+                self.version(&reference.subject, Version::Number(reference.version))?;
+            self.collect_references(&referenced_version.schema.references, referenced_schemas)?;
+            referenced_schemas.insert(reference.name.clone(), referenced_version.schema.schema);
+        }
+        Ok(())
+    }
+
+    fn incompatibilities(
+        &self,
+        schema: &Schema,
+        resolved_schema: &str,
+        versions: &[SubjectVersion],
+        level: CompatibilityLevel,
+    ) -> Result<Vec<String>, RegistryError> {
+        let mut messages = Vec::new();
+        for version in versions {
+            if version.schema.schema_type != schema.schema_type {
+                messages.push(format!(
+                    "Schema type differs from the type of version {}",
+                    version.version
+                ));
+                continue;
+            }
+            if !schema.schema_type.is_avro() {
+                continue;
+            }
+
+            let previous_schema = self.resolve(&version.schema)?;
+            match is_compatible(&previous_schema, resolved_schema, level) {
+                Ok(true) => {}
+                Ok(false) => messages.push(format!(
+                    "Schema is not {:?} compatible with version {}",
+                    level, version.version
+                )),
+                Err(e) => return Err(RegistryError::InvalidSchema(e.to_string())),
+            }
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW_SCHEMA_V1: &str = r#"{
+        "name": "TestAvro",
+        "type": "record",
+        "fields": [{ "name": "name", "type": "string" }]
+    }"#;
+
+    const RAW_SCHEMA_V2: &str = r#"{
+        "name": "TestAvro",
+        "type": "record",
+        "fields": [
+            { "name": "name", "type": "string" },
+            { "name": "email", "type": "string" }
+        ]
+    }"#;
+
+    #[test]
+    fn register_same_schema_again_returns_existing_id() {
+        let registry = InMemorySchemaRegistry::new();
+        let id = registry
+            .register("test", Schema::avro(RAW_SCHEMA_V1))
+            .unwrap();
+
+        assert_eq!(
+            registry
+                .register("test", Schema::avro(RAW_SCHEMA_V1))
+                .unwrap(),
+            id
+        );
+        assert_eq!(
+            registry
+                .register("other", Schema::avro(RAW_SCHEMA_V1))
+                .unwrap(),
+            id
+        );
+        assert_eq!(registry.versions("test").unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn register_incompatible_schema_fails() {
+        let registry = InMemorySchemaRegistry::new();
+        registry
+            .register("test", Schema::avro(RAW_SCHEMA_V1))
+            .unwrap();
+
+        assert!(matches!(
+            registry.register("test", Schema::avro(RAW_SCHEMA_V2)),
+            Err(RegistryError::Incompatible(_))
+        ));
+        assert!(!registry
+            .check_compatibility("test", Version::Latest, &Schema::avro(RAW_SCHEMA_V2))
+            .unwrap()
+            .is_empty());
+
+        registry.set_compatibility(Some("test"), Compatibility::Forward);
+        assert_eq!(
+            registry
+                .register("test", Schema::avro(RAW_SCHEMA_V2))
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn register_record_schemas_with_references() {
+        let registry = InMemorySchemaRegistry::new();
+        registry
+            .register_record_schemas(kafka_schema_user::SCHEMAS, &[
+                SerializationFormat::Avro,
+                SerializationFormat::JsonSchema,
+                SerializationFormat::Protobuf,
+            ])
+            .unwrap();
+
+        let subjects = registry.subjects();
+        assert!(subjects.contains(&"phoneNumber".to_string()));
+        assert!(subjects.contains(&"CreateUserAvroV1".to_string()));
+        assert!(subjects.contains(&"CreateUserAvroV1-json".to_string()));
+        assert!(subjects.contains(&"CreateUserAvroV1-protobuf".to_string()));
+    }
+}
@@ -0,0 +1,262 @@
+//! HTTP server serving an [`InMemorySchemaRegistry`] with the subset of the
+//! REST API of the Confluent schema registry that is used by the services and
+//! the `schema_registry_converter`.
+
+use std::io;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::routing::get;
+use axum::routing::post;
+use axum::Extension;
+use axum::Json;
+use axum::Router;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::Value;
+use tokio::task::JoinHandle;
+
+use crate::registry::Compatibility;
+use crate::registry::InMemorySchemaRegistry;
+use crate::registry::RegistryError;
+use crate::registry::Schema;
+use crate::registry::SubjectVersion;
+use crate::registry::Version;
+
+/// Schema registry served on a random local port, e.g. for tests. The server
+/// is stopped when it is dropped.
+pub struct EmbeddedSchemaRegistry {
+    registry: InMemorySchemaRegistry,
+    url: String,
+    server: JoinHandle<()>,
+}
+
+impl EmbeddedSchemaRegistry {
+    /// Starts the server with an empty registry. Must be called within a tokio
+    /// runtime.
+    pub fn start() -> io::Result<EmbeddedSchemaRegistry> {
+        Self::start_with(InMemorySchemaRegistry::new())
+    }
+
+    /// Starts the server with the registry, e.g. with pre-registered schemas.
+    pub fn start_with(registry: InMemorySchemaRegistry) -> io::Result<EmbeddedSchemaRegistry> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        listener.set_nonblocking(true)?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let server = axum::Server::from_tcp(listener)
+            .map_err(io::Error::other)?
+            .serve(routing(registry.clone()).into_make_service());
+        let server = tokio::spawn(async move {
+            if let Err(e) = server.await {
+                tracing::error!("Schema registry stopped: {}", e);
+            }
+        });
+
+        Ok(EmbeddedSchemaRegistry {
+            registry,
+            url,
+            server,
+        })
+    }
+
+    /// Url to be used as `schema_registry.url`.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn registry(&self) -> &InMemorySchemaRegistry {
+        &self.registry
+    }
+}
+
+impl Drop for EmbeddedSchemaRegistry {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+pub fn routing(registry: InMemorySchemaRegistry) -> Router {
+    Router::new()
+        .route("/subjects", get(subjects))
+        .route("/subjects/:subject", post(lookup))
+        .route("/subjects/:subject/versions", get(versions).post(register))
+        .route("/subjects/:subject/versions/:version", get(version))
+        .route("/schemas/ids/:id", get(schema_by_id))
+        .route(
+            "/compatibility/subjects/:subject/versions/:version",
+            post(check_compatibility),
+        )
+        .route("/config", get(global_config).put(update_global_config))
+        .route(
+            "/config/:subject",
+            get(subject_config).put(update_subject_config),
+        )
+        .layer(Extension(registry))
+}
+
+impl IntoResponse for RegistryError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            RegistryError::SubjectNotFound(_)
+            | RegistryError::VersionNotFound(_, _)
+            | RegistryError::SchemaNotFound => StatusCode::NOT_FOUND,
+            RegistryError::InvalidVersion(_) | RegistryError::InvalidSchema(_) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            RegistryError::Incompatible(_) => StatusCode::CONFLICT,
+        };
+
+        let body = Json(json!({
+            "error_code": self.error_code(),
+            "message": self.to_string(),
+        }));
+        (status, body).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct ConfigRequest {
+    compatibility: Compatibility,
+}
+
+async fn subjects(Extension(registry): Extension<InMemorySchemaRegistry>) -> Json<Vec<String>> {
+    Json(registry.subjects())
+}
+
+async fn lookup(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path(subject): Path<String>,
+    Json(schema): Json<Schema>,
+) -> Result<Json<SubjectVersion>, RegistryError> {
+    Ok(Json(registry.lookup(&subject, &schema)?))
+}
+
+async fn versions(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path(subject): Path<String>,
+) -> Result<Json<Vec<u32>>, RegistryError> {
+    Ok(Json(registry.versions(&subject)?))
+}
+
+async fn register(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path(subject): Path<String>,
+    Json(schema): Json<Schema>,
+) -> Result<Json<Value>, RegistryError> {
+    let id = registry.register(&subject, schema)?;
+    Ok(Json(json!({ "id": id })))
+}
+
+async fn version(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path((subject, version)): Path<(String, String)>,
+) -> Result<Json<SubjectVersion>, RegistryError> {
+    Ok(Json(registry.version(&subject, version.parse()?)?))
+}
+
+async fn schema_by_id(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path(id): Path<u32>,
+) -> Result<Json<Schema>, RegistryError> {
+    Ok(Json(registry.schema_by_id(id)?))
+}
+
+async fn check_compatibility(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path((subject, version)): Path<(String, String)>,
+    Json(schema): Json<Schema>,
+) -> Result<Json<Value>, RegistryError> {
+    let version: Version = version.parse()?;
+    let messages = registry.check_compatibility(&subject, version, &schema)?;
+    Ok(Json(json!({
+        "is_compatible": messages.is_empty(),
+        "messages": messages,
+    })))
+}
+
+async fn global_config(Extension(registry): Extension<InMemorySchemaRegistry>) -> Json<Value> {
+    Json(json!({ "compatibilityLevel": registry.compatibility(None) }))
+}
+
+async fn update_global_config(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Json(request): Json<ConfigRequest>,
+) -> Json<Value> {
+    registry.set_compatibility(None, request.compatibility);
+    Json(json!({ "compatibility": request.compatibility }))
+}
+
+async fn subject_config(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path(subject): Path<String>,
+) -> Json<Value> {
+    Json(json!({ "compatibilityLevel": registry.compatibility(Some(&subject)) }))
+}
+
+async fn update_subject_config(
+    Extension(registry): Extension<InMemorySchemaRegistry>,
+    Path(subject): Path<String>,
+    Json(request): Json<ConfigRequest>,
+) -> Json<Value> {
+    registry.set_compatibility(Some(&subject), request.compatibility);
+    Json(json!({ "compatibility": request.compatibility }))
+}
+
+#[cfg(test)]
+mod tests {
+    use kafka_schema_common::format::SerializationFormat;
+    use kafka_schema_common::schema_key::KeyAvro;
+    use kafka_schema_common::schema_key::SCHEMA_NAME_KEY;
+    use kafka_schema_common::IdentifierAvro;
+    use schema_registry_converter::async_impl::avro::AvroDecoder;
+    use schema_registry_converter::async_impl::avro::AvroEncoder;
+    use schema_registry_converter::async_impl::schema_registry::SrSettings;
+    use schema_registry_converter::schema_registry_common::SubjectNameStrategy;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn encode_and_decode_with_embedded_registry() {
+        let embedded_registry = EmbeddedSchemaRegistry::start().unwrap();
+        embedded_registry
+            .registry()
+            .register_record_schemas(kafka_schema_common::SCHEMAS, &[SerializationFormat::Avro])
+            .unwrap();
+
+        let key = KeyAvro {
+            context_identifier: "d8f1a4a7-6f0a-4b2c-9a53-2fa0f1c1b7a1".to_string(),
+            identifier: IdentifierAvro {
+                data_type: "user".to_string(),
+                identifier: "5b4c0b7e-8f1d-4e63-9d2c-0d6b7f0bde27".to_string(),
+                version: 3,
+            },
+        };
+
+        let sr_settings = SrSettings::new(embedded_registry.url().to_string());
+        let encoded = AvroEncoder::new(sr_settings.clone())
+            .encode_struct(
+                key.clone(),
+                &SubjectNameStrategy::RecordNameStrategy(SCHEMA_NAME_KEY.to_string()),
+            )
+            .await
+            .unwrap();
+        let decoded = AvroDecoder::new(sr_settings)
+            .decode(Some(&encoded))
+            .await
+            .unwrap();
+
+        assert_eq!(decoded.name.unwrap().name, SCHEMA_NAME_KEY);
+        assert_eq!(
+            apache_avro::from_value::<KeyAvro>(&decoded.value)
+                .unwrap()
+                .identifier
+                .identifier,
+            key.identifier.identifier
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use std::env;
+use std::path::Path;
+
+use kafka_schema_codegen::Codegen;
+use kafka_schema_codegen::TYPES_DIRECTORY;
+
+fn main() {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR not set");
+    let common_types = Path::new(&manifest_dir)
+        .join("../kafka-schema-common/resources")
+        .join(TYPES_DIRECTORY);
+
+    Codegen::new(&Path::new(&manifest_dir).join("resources"))
+        .with_external_types(&common_types, "kafka_schema_common")
+        .generate(&Path::new(&out_dir).join("schemas.rs"))
+        .expect("Generation of avro types failed");
+
+    println!("cargo:rerun-if-changed=resources");
+    println!("cargo:rerun-if-changed={}", common_types.display());
+}
@@ -1,27 +1,134 @@
-use serde::Deserialize;
-use serde::Serialize;
+// Avro types generated from the schemas in the resources directory
+include!(concat!(env!("OUT_DIR"), "/schemas.rs"));
 
-pub mod schema_create_user;
+pub mod upcast;
 
 pub const DATA_TYPE_USER: &str = "user";
 
-#[derive(Debug, Deserialize, Serialize)]
-pub enum IsoCountryCodeEnumAvro {
-    DE,
-    US,
-}
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
 
-#[derive(Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct PhoneNumberAvro {
-    pub country_code: String,
-    pub phone_number_type: PhoneNumberTypeEnumAvro,
-    pub call_number: String,
-}
+    use apache_avro::from_avro_datum;
+    use apache_avro::to_avro_datum;
+    use apache_avro::to_value;
+    use apache_avro::Schema;
+    use kafka_schema_common::compatibility::assert_formats_derivable;
+    use kafka_schema_common::compatibility::assert_round_trip;
+    use kafka_schema_common::compatibility::assert_versions_compatible;
+    use kafka_schema_common::reference::resolve_references;
+    use kafka_schema_common::IsoCountryCodeEnumAvro;
+
+    use super::*;
+    use crate::schema_create_user::*;
+    use crate::schema_delete_user::*;
+    use crate::schema_update_user::*;
+
+    const IDENTIFIER: &str = "5b4c0b7e-8f1d-4e63-9d2c-0d6b7f0bde27";
+
+    fn phone_numbers() -> Vec<PhoneNumberAvro> {
+        vec![
+            PhoneNumberAvro {
+                country_code: "DE".to_string(),
+                phone_number_type: PhoneNumberTypeEnumAvro::Business,
+                call_number: "+49 89 1234567".to_string(),
+            },
+            PhoneNumberAvro {
+                country_code: "US".to_string(),
+                phone_number_type: PhoneNumberTypeEnumAvro::Mobile,
+                call_number: "+1 555 1234567".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn schema_versions_compatible() {
+        assert_versions_compatible(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("resources"),
+            &[REFERENCES, kafka_schema_common::REFERENCES].concat(),
+        );
+    }
+
+    #[test]
+    fn schemas_derivable_in_all_formats() {
+        assert_formats_derivable(SCHEMAS);
+    }
+
+    #[test]
+    fn create_user_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_CREATE_USER,
+            REFERENCES_CREATE_USER,
+            &CreateUserAvro {
+                identifier: IDENTIFIER.to_string(),
+                name: "Max Mustermann".to_string(),
+                email: "max@example.com".to_string(),
+                country: IsoCountryCodeEnumAvro::De,
+                phone_numbers: phone_numbers(),
+                locale: Some("de-DE".to_string()),
+            },
+        );
+    }
+
+    #[test]
+    fn create_user_v1_upcast_to_v2() {
+        let parse = |raw_schema, references| {
+            Schema::parse_str(&resolve_references(raw_schema, references).unwrap()).unwrap()
+        };
+        let schema_v1 = parse(RAW_SCHEMA_CREATE_USER_V1, REFERENCES_CREATE_USER_V1);
+        let schema_v2 = parse(RAW_SCHEMA_CREATE_USER_V2, REFERENCES_CREATE_USER_V2);
+
+        let v1 = CreateUserAvroV1 {
+            identifier: IDENTIFIER.to_string(),
+            name: "Max Mustermann".to_string(),
+            email: "max@example.com".to_string(),
+            country: IsoCountryCodeEnumAvro::De,
+            phone_numbers: phone_numbers(),
+        };
+        let encoded = to_avro_datum(&schema_v1, to_value(&v1).unwrap()).unwrap();
+
+        // Consumers decode with the writer schema and upcast the record
+        let decoded = from_avro_datum(&schema_v1, &mut encoded.as_slice(), None).unwrap();
+        let upcast: CreateUserAvroV2 = apache_avro::from_value::<CreateUserAvroV1>(&decoded)
+            .unwrap()
+            .into();
+        assert_eq!(upcast.identifier, IDENTIFIER);
+        assert_eq!(upcast.name, "Max Mustermann");
+        assert_eq!(upcast.email, "max@example.com");
+        assert_eq!(upcast.country, IsoCountryCodeEnumAvro::De);
+        assert_eq!(upcast.phone_numbers.len(), 2);
+        assert_eq!(upcast.locale, None);
+
+        // Schema resolution with V2 as reader schema yields the same record
+        let resolved =
+            from_avro_datum(&schema_v1, &mut encoded.as_slice(), Some(&schema_v2)).unwrap();
+        let resolved = apache_avro::from_value::<CreateUserAvroV2>(&resolved).unwrap();
+        assert_eq!(to_value(&resolved).unwrap(), to_value(&upcast).unwrap());
+    }
+
+    #[test]
+    fn update_user_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_UPDATE_USER_V1,
+            REFERENCES_UPDATE_USER_V1,
+            &UpdateUserAvro {
+                identifier: IDENTIFIER.to_string(),
+                name: "John Doe".to_string(),
+                email: "john@example.com".to_string(),
+                country: IsoCountryCodeEnumAvro::Us,
+                phone_numbers: vec![],
+            },
+        );
+    }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub enum PhoneNumberTypeEnumAvro {
-    Business,
-    Home,
-    Mobile,
+    #[test]
+    fn delete_user_round_trip() {
+        assert_round_trip(
+            RAW_SCHEMA_DELETE_USER_V1,
+            REFERENCES_DELETE_USER_V1,
+            &DeleteUserAvro {
+                identifier: IDENTIFIER.to_string(),
+            },
+        );
+    }
 }
@@ -0,0 +1,18 @@
+//! Conversions of previous versions of the events into their next version.
+//! Consumers register them with `EventConsumer::with_upcasting_handler`.
+
+use crate::schema_create_user::CreateUserAvroV1;
+use crate::schema_create_user::CreateUserAvroV2;
+
+impl From<CreateUserAvroV1> for CreateUserAvroV2 {
+    fn from(v1: CreateUserAvroV1) -> Self {
+        CreateUserAvroV2 {
+            identifier: v1.identifier,
+            name: v1.name,
+            email: v1.email,
+            country: v1.country,
+            phone_numbers: v1.phone_numbers,
+            locale: None,
+        }
+    }
+}